@@ -6,12 +6,19 @@ use serde::{Deserialize, Serialize};
 pub struct BaseResponse<T> {
     pub trace_id : String,
     pub message: String,
+    /// stable machine-readable code, only set on errors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
     pub data: Option<T>
 }
 
 impl<T> BaseResponse<T> {
     pub fn new(trace_id : String, message : String, data: Option<T>) -> Self {
-        Self { trace_id, message, data }
+        Self { trace_id, message, error_code: None, data }
+    }
+
+    pub fn error(trace_id : String, error_code : &str, message : String) -> Self {
+        Self { trace_id, message, error_code: Some(error_code.to_string()), data: None }
     }
 }
 
@@ -34,6 +41,12 @@ impl AuditMetadata {
     }
 }
 
+impl Default for AuditMetadata {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// trait for all auditable entities
 pub trait Auditable {
     fn audit(&self) -> &AuditMetadata;
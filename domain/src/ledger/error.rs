@@ -0,0 +1,17 @@
+use crate::money::money::Money;
+use crate::wallet::error::WalletError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum LedgerError {
+    #[error("Journal entry needs at least two postings")]
+    TooFewPostings,
+    #[error("Posting amount must not be zero")]
+    ZeroPosting,
+    #[error("Journal entry does not balance, {0} left over")]
+    Unbalanced(Money),
+    #[error("Unknown ledger account: {0}")]
+    UnknownAccount(String),
+    #[error(transparent)]
+    Amount(#[from] WalletError),
+}
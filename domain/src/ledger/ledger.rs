@@ -0,0 +1,237 @@
+use crate::ledger::error::LedgerError;
+use crate::money::money::{Currency, Money};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Account a posting is booked against. Wallet pockets are keyed by user id,
+/// the currency comes from the posting amount.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LedgerAccount {
+    Wallet(i32),
+    /// money entering or leaving the system (top-ups, withdrawals, opening balances)
+    External,
+    /// counterpart of currency conversions so each currency nets to zero
+    FxClearing,
+    /// money debited from a sender whose receiver has not been credited yet
+    Transit,
+    /// balances of closed wallets that named no wallet to sweep them to
+    Suspense,
+}
+
+impl fmt::Display for LedgerAccount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LedgerAccount::Wallet(user_id) => write!(f, "wallet:{}", user_id),
+            LedgerAccount::External => f.write_str("system:external"),
+            LedgerAccount::FxClearing => f.write_str("system:fx_clearing"),
+            LedgerAccount::Transit => f.write_str("system:transit"),
+            LedgerAccount::Suspense => f.write_str("system:suspense"),
+        }
+    }
+}
+
+impl FromStr for LedgerAccount {
+    type Err = LedgerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "system:external" => Ok(LedgerAccount::External),
+            "system:fx_clearing" => Ok(LedgerAccount::FxClearing),
+            "system:transit" => Ok(LedgerAccount::Transit),
+            "system:suspense" => Ok(LedgerAccount::Suspense),
+            _ => s
+                .strip_prefix("wallet:")
+                .and_then(|id| id.parse().ok())
+                .map(LedgerAccount::Wallet)
+                .ok_or_else(|| LedgerError::UnknownAccount(s.to_string())),
+        }
+    }
+}
+
+/// One side of a journal entry; positive amounts increase the account, negative decrease it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Posting {
+    pub account: LedgerAccount,
+    pub amount: Money,
+}
+
+impl Posting {
+    pub fn new(account: LedgerAccount, amount: Money) -> Self {
+        Self { account, amount }
+    }
+}
+
+/// Balanced set of postings: for every currency the amounts sum to zero.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub id: Option<i64>,
+    /// business reference, e.g. the transfer's transaction id
+    pub reference: String,
+    pub description: String,
+    pub postings: Vec<Posting>,
+    pub created_date: DateTime<Utc>,
+}
+
+impl JournalEntry {
+    pub fn new(reference: &str, description: &str, postings: Vec<Posting>) -> Result<Self, LedgerError> {
+        let entry = Self {
+            id: None,
+            reference: reference.to_string(),
+            description: description.to_string(),
+            postings,
+            created_date: Utc::now(),
+        };
+        entry.validate()?;
+        Ok(entry)
+    }
+
+    /// Moves `debit` out of the sender and `credit` into the receiver; when the
+    /// currencies differ the conversion is booked through [`LedgerAccount::FxClearing`].
+    pub fn transfer(
+        reference: &str,
+        from_user: i32,
+        to_user: i32,
+        debit: Money,
+        credit: Money,
+    ) -> Result<Self, LedgerError> {
+        let mut postings = vec![Posting::new(LedgerAccount::Wallet(from_user), negate(debit)?)];
+        if debit.currency() != credit.currency() {
+            postings.push(Posting::new(LedgerAccount::FxClearing, debit));
+            postings.push(Posting::new(LedgerAccount::FxClearing, negate(credit)?));
+        }
+        postings.push(Posting::new(LedgerAccount::Wallet(to_user), credit));
+        Self::new(reference, "transfer", postings)
+    }
+
+    /// Books a signed change to one wallet against the external account,
+    /// e.g. a top-up (positive) or a withdrawal (negative).
+    pub fn adjustment(reference: &str, user_id: i32, amount: Money) -> Result<Self, LedgerError> {
+        Self::against(reference, "adjustment", user_id, amount, LedgerAccount::External)
+    }
+
+    /// Books a signed change to one wallet against the given system account.
+    pub fn against(
+        reference: &str,
+        description: &str,
+        user_id: i32,
+        amount: Money,
+        counterparty: LedgerAccount,
+    ) -> Result<Self, LedgerError> {
+        Self::new(
+            reference,
+            description,
+            vec![
+                Posting::new(LedgerAccount::Wallet(user_id), amount),
+                Posting::new(counterparty, negate(amount)?),
+            ],
+        )
+    }
+
+    pub fn validate(&self) -> Result<(), LedgerError> {
+        if self.postings.len() < 2 {
+            return Err(LedgerError::TooFewPostings);
+        }
+        let mut totals: BTreeMap<Currency, Money> = BTreeMap::new();
+        for posting in &self.postings {
+            if posting.amount.is_zero() {
+                return Err(LedgerError::ZeroPosting);
+            }
+            let currency = posting.amount.currency();
+            let total = totals.entry(currency).or_insert(Money::zero(currency));
+            *total = total.checked_add(posting.amount)?;
+        }
+        match totals.into_values().find(|total| !total.is_zero()) {
+            Some(left_over) => Err(LedgerError::Unbalanced(left_over)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Sums the postings of one account in one currency, i.e. the balance the ledger implies.
+pub fn account_balance<'a>(
+    postings: impl IntoIterator<Item = &'a Posting>,
+    account: &LedgerAccount,
+    currency: Currency,
+) -> Result<Money, LedgerError> {
+    postings
+        .into_iter()
+        .filter(|p| &p.account == account && p.amount.currency() == currency)
+        .try_fold(Money::zero(currency), |total, p| {
+            total.checked_add(p.amount).map_err(LedgerError::from)
+        })
+}
+
+fn negate(amount: Money) -> Result<Money, LedgerError> {
+    Ok(Money::zero(amount.currency()).checked_sub(amount)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idr(minor: i64) -> Money {
+        Money::new(minor, Currency::IDR)
+    }
+
+    fn usd(minor: i64) -> Money {
+        Money::new(minor, Currency::USD)
+    }
+
+    fn entry(postings: Vec<Posting>) -> JournalEntry {
+        JournalEntry {
+            id: None,
+            reference: "ref-1".to_string(),
+            description: "test".to_string(),
+            postings,
+            created_date: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn balanced_entries_validate() {
+        let same_currency = JournalEntry::transfer("ref-1", 1, 2, idr(5_000), idr(5_000)).unwrap();
+        assert_eq!(same_currency.postings.len(), 2);
+
+        let converted = JournalEntry::transfer("ref-2", 1, 2, idr(160_000), usd(1_000)).unwrap();
+        assert_eq!(converted.postings.len(), 4);
+        assert_eq!(account_balance(&converted.postings, &LedgerAccount::FxClearing, Currency::IDR).unwrap(), idr(160_000));
+        assert_eq!(account_balance(&converted.postings, &LedgerAccount::FxClearing, Currency::USD).unwrap(), usd(-1_000));
+
+        let withdrawal = JournalEntry::adjustment("ref-3", 1, idr(-2_500)).unwrap();
+        assert_eq!(account_balance(&withdrawal.postings, &LedgerAccount::External, Currency::IDR).unwrap(), idr(2_500));
+    }
+
+    #[test]
+    fn unbalanced_entries_are_rejected() {
+        let short = entry(vec![
+            Posting::new(LedgerAccount::Wallet(1), idr(-100)),
+            Posting::new(LedgerAccount::Wallet(2), idr(90)),
+        ]);
+        assert!(matches!(short.validate(), Err(LedgerError::Unbalanced(left_over)) if left_over == idr(-10)));
+    }
+
+    #[test]
+    fn currencies_must_balance_separately() {
+        // equal minor units in different currencies do not offset each other
+        let mixed = entry(vec![
+            Posting::new(LedgerAccount::Wallet(1), idr(-100)),
+            Posting::new(LedgerAccount::Wallet(2), usd(100)),
+        ]);
+        assert!(matches!(mixed.validate(), Err(LedgerError::Unbalanced(_))));
+    }
+
+    #[test]
+    fn degenerate_entries_are_rejected() {
+        let single = entry(vec![Posting::new(LedgerAccount::Wallet(1), idr(100))]);
+        assert!(matches!(single.validate(), Err(LedgerError::TooFewPostings)));
+
+        let zero = entry(vec![
+            Posting::new(LedgerAccount::Wallet(1), idr(0)),
+            Posting::new(LedgerAccount::External, idr(0)),
+        ]);
+        assert!(matches!(zero.validate(), Err(LedgerError::ZeroPosting)));
+    }
+}
@@ -1,5 +1,9 @@
+#![allow(clippy::module_inception)]
+
 pub mod receipt;
 pub mod wallet;
 pub mod transfer;
 pub mod user;
-pub mod base;
\ No newline at end of file
+pub mod base;
+pub mod money;
+pub mod ledger;
\ No newline at end of file
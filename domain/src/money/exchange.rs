@@ -0,0 +1,189 @@
+use crate::money::money::{format_fixed, parse_fixed, Currency, Money};
+use crate::wallet::error::WalletError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// decimal places kept on exchange rates
+pub const RATE_SCALE: u32 = 8;
+const BPS_DENOMINATOR: i128 = 10_000;
+
+/// Fixed-point exchange rate with [`RATE_SCALE`] decimals, serialized as a decimal string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Rate(i64);
+
+impl Rate {
+    pub fn parse(rate: &str) -> Result<Self, WalletError> {
+        let scaled = parse_fixed(rate, RATE_SCALE)?;
+        if scaled <= 0 {
+            return Err(WalletError::MalformedAmount(rate.to_string()));
+        }
+        Ok(Self(scaled))
+    }
+
+    pub fn scaled(&self) -> i64 {
+        self.0
+    }
+
+    /// base units per one quote unit, rounded down
+    pub fn inverse(&self) -> Result<Self, WalletError> {
+        10i64
+            .pow(2 * RATE_SCALE)
+            .checked_div(self.0)
+            .filter(|inverse| *inverse > 0)
+            .map(Self)
+            .ok_or(WalletError::Overflow)
+    }
+}
+
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format_fixed(self.0, RATE_SCALE))
+    }
+}
+
+impl From<Rate> for String {
+    fn from(value: Rate) -> Self {
+        value.to_string()
+    }
+}
+
+impl TryFrom<String> for Rate {
+    type Error = WalletError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Rate::parse(&value)
+    }
+}
+
+/// Mid-market rate for one currency pair plus the spread charged on conversion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExchangeRate {
+    pub base: Currency,
+    pub quote: Currency,
+    /// quote units per one base unit
+    pub rate: Rate,
+    /// spread in basis points, taken off the rate in the provider's favour
+    pub spread_bps: u32,
+}
+
+impl ExchangeRate {
+    pub fn new(base: Currency, quote: Currency, rate: Rate, spread_bps: u32) -> Self {
+        Self { base, quote, rate, spread_bps }
+    }
+
+    /// rate after the spread is taken off
+    pub fn applied_rate(&self) -> Result<Rate, WalletError> {
+        let keep = BPS_DENOMINATOR
+            .checked_sub(self.spread_bps as i128)
+            .filter(|k| *k > 0)
+            .ok_or(WalletError::InvalidSpread(self.spread_bps))?;
+        let applied = self.rate.scaled() as i128 * keep / BPS_DENOMINATOR;
+        i64::try_from(applied)
+            .map(Rate)
+            .map_err(|_| WalletError::Overflow)
+    }
+
+    /// Converts an amount in the base currency into the quote currency,
+    /// rounding down to the quote currency's minor unit.
+    pub fn convert(&self, amount: Money) -> Result<Conversion, WalletError> {
+        if amount.currency() != self.base {
+            return Err(WalletError::CurrencyMismatch(amount.currency(), self.base));
+        }
+        let applied_rate = self.applied_rate()?;
+        let numerator = (amount.minor_units() as i128)
+            .checked_mul(applied_rate.scaled() as i128)
+            .and_then(|n| n.checked_mul(10i128.pow(self.quote.exponent())))
+            .ok_or(WalletError::Overflow)?;
+        let denominator = 10i128.pow(self.base.exponent() + RATE_SCALE);
+        let converted =
+            i64::try_from(numerator / denominator).map_err(|_| WalletError::Overflow)?;
+
+        Ok(Conversion {
+            rate: *self,
+            applied_rate,
+            debit_amount: amount,
+            credit_amount: Money::new(converted, self.quote),
+        })
+    }
+}
+
+/// Outcome of converting a transfer amount, kept on the transfer and its receipt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Conversion {
+    pub rate: ExchangeRate,
+    pub applied_rate: Rate,
+    /// amount taken from the sender, in the sender's currency
+    pub debit_amount: Money,
+    /// amount given to the receiver, in the receiver's currency
+    pub credit_amount: Money,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usd_idr(spread_bps: u32) -> ExchangeRate {
+        ExchangeRate::new(Currency::USD, Currency::IDR, Rate::parse("15650.00").unwrap(), spread_bps)
+    }
+
+    #[test]
+    fn spread_is_taken_off_the_rate() {
+        assert_eq!(usd_idr(0).applied_rate().unwrap(), Rate::parse("15650").unwrap());
+        assert_eq!(usd_idr(50).applied_rate().unwrap(), Rate::parse("15571.75").unwrap());
+        assert!(matches!(usd_idr(10_000).applied_rate(), Err(WalletError::InvalidSpread(10_000))));
+        assert!(matches!(usd_idr(u32::MAX).applied_rate(), Err(WalletError::InvalidSpread(u32::MAX))));
+    }
+
+    #[test]
+    fn converts_usd_to_idr() {
+        let conversion = usd_idr(50).convert(Money::new(1_000, Currency::USD)).unwrap();
+        assert_eq!(conversion.debit_amount, Money::new(1_000, Currency::USD));
+        assert_eq!(conversion.credit_amount, Money::new(15_571_750, Currency::IDR));
+        assert_eq!(conversion.applied_rate, Rate::parse("15571.75").unwrap());
+        assert_eq!(conversion.rate, usd_idr(50));
+    }
+
+    #[test]
+    fn converts_idr_to_usd() {
+        let rate = ExchangeRate::new(Currency::IDR, Currency::USD, Rate::parse("0.0000639").unwrap(), 0);
+        let conversion = rate.convert(Money::new(10_000_000, Currency::IDR)).unwrap();
+        assert_eq!(conversion.credit_amount, Money::new(639, Currency::USD));
+    }
+
+    #[test]
+    fn conversion_rounds_down_to_the_quote_minor_unit() {
+        // 0.01 USD is 155.7175 IDR
+        let conversion = usd_idr(50).convert(Money::new(1, Currency::USD)).unwrap();
+        assert_eq!(conversion.credit_amount, Money::new(15_571, Currency::IDR));
+
+        // 100,001.23 IDR is 6.390078... USD
+        let rate = ExchangeRate::new(Currency::IDR, Currency::USD, Rate::parse("0.0000639").unwrap(), 0);
+        let conversion = rate.convert(Money::new(10_000_123, Currency::IDR)).unwrap();
+        assert_eq!(conversion.credit_amount, Money::new(639, Currency::USD));
+
+        // too little to make one minor unit
+        let conversion = rate.convert(Money::new(1, Currency::IDR)).unwrap();
+        assert!(conversion.credit_amount.is_zero());
+    }
+
+    #[test]
+    fn amount_must_be_in_the_base_currency() {
+        assert!(matches!(
+            usd_idr(0).convert(Money::new(1_000, Currency::IDR)),
+            Err(WalletError::CurrencyMismatch(Currency::IDR, Currency::USD))
+        ));
+    }
+
+    #[test]
+    fn conversion_overflow_is_an_error() {
+        // the result does not fit a Money
+        assert!(matches!(
+            usd_idr(0).convert(Money::new(i64::MAX, Currency::USD)),
+            Err(WalletError::Overflow)
+        ));
+        // the intermediate product does not fit either
+        let rate = ExchangeRate::new(Currency::USD, Currency::IDR, Rate(i64::MAX), 0);
+        assert!(matches!(rate.convert(Money::new(i64::MAX, Currency::USD)), Err(WalletError::Overflow)));
+    }
+}
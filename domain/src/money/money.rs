@@ -0,0 +1,433 @@
+use crate::wallet::error::WalletError;
+use bytes::{BufMut, BytesMut};
+use postgres_types::{accepts, to_sql_checked, FromSql, IsNull, ToSql, Type};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+const NUMERIC_POS: u16 = 0x0000;
+const NUMERIC_NEG: u16 = 0x4000;
+const NUMERIC_NBASE: i128 = 10_000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, ToSql, FromSql)]
+#[postgres(name = "currency")]
+pub enum Currency {
+    #[default]
+    #[postgres(name = "IDR")]
+    IDR,
+    #[postgres(name = "USD")]
+    USD,
+}
+
+impl Currency {
+    /// number of minor units digits (ISO 4217 exponent)
+    pub fn exponent(&self) -> u32 {
+        match self {
+            Currency::IDR => 2,
+            Currency::USD => 2,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Currency::IDR => "IDR",
+            Currency::USD => "USD",
+        }
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl FromStr for Currency {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "IDR" => Ok(Currency::IDR),
+            "USD" => Ok(Currency::USD),
+            other => Err(WalletError::UnsupportedCurrency(other.to_string())),
+        }
+    }
+}
+
+/// Fixed-point amount of money, stored as an integer count of minor units
+/// (e.g. cents) so arithmetic never loses precision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "MoneyRepr", into = "MoneyRepr")]
+pub struct Money {
+    minor_units: i64,
+    currency: Currency,
+}
+
+/// wire format: `{"amount": "1500.25", "currency": "IDR"}`
+#[derive(Serialize, Deserialize)]
+struct MoneyRepr {
+    amount: String,
+    currency: Currency,
+}
+
+impl Money {
+    pub fn new(minor_units: i64, currency: Currency) -> Self {
+        Self { minor_units, currency }
+    }
+
+    pub fn zero(currency: Currency) -> Self {
+        Self::new(0, currency)
+    }
+
+    /// Builds an amount from whole units, e.g. `from_major(500, IDR)` is IDR 500.00
+    pub fn from_major(major_units: i64, currency: Currency) -> Result<Self, WalletError> {
+        major_units
+            .checked_mul(10i64.pow(currency.exponent()))
+            .map(|minor| Self::new(minor, currency))
+            .ok_or(WalletError::Overflow)
+    }
+
+    /// Parses a decimal string such as `"1500.25"`; more fractional digits
+    /// than the currency allows is rejected rather than rounded.
+    pub fn parse(amount: &str, currency: Currency) -> Result<Self, WalletError> {
+        parse_fixed(amount, currency.exponent()).map(|minor| Self::new(minor, currency))
+    }
+
+    pub fn minor_units(&self) -> i64 {
+        self.minor_units
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.minor_units == 0
+    }
+
+    pub fn is_positive(&self) -> bool {
+        self.minor_units > 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.minor_units < 0
+    }
+
+    /// same amount re-labelled with another currency, used when the currency
+    /// is stored in its own column next to the numeric amount
+    pub fn with_currency(self, currency: Currency) -> Self {
+        Self::new(self.minor_units, currency)
+    }
+
+    pub fn checked_add(self, other: Money) -> Result<Money, WalletError> {
+        self.ensure_same_currency(&other)?;
+        self.minor_units
+            .checked_add(other.minor_units)
+            .map(|minor| Self::new(minor, self.currency))
+            .ok_or(WalletError::Overflow)
+    }
+
+    pub fn checked_sub(self, other: Money) -> Result<Money, WalletError> {
+        self.ensure_same_currency(&other)?;
+        self.minor_units
+            .checked_sub(other.minor_units)
+            .map(|minor| Self::new(minor, self.currency))
+            .ok_or(WalletError::Overflow)
+    }
+
+    /// decimal representation without currency, e.g. `"-12.50"`
+    pub fn amount_string(&self) -> String {
+        format_fixed(self.minor_units, self.currency.exponent())
+    }
+
+    fn ensure_same_currency(&self, other: &Money) -> Result<(), WalletError> {
+        if self.currency != other.currency {
+            return Err(WalletError::CurrencyMismatch(self.currency, other.currency));
+        }
+        Ok(())
+    }
+}
+
+/// Parses a decimal string into an integer scaled by `10^exponent`; more
+/// fractional digits than `exponent` is rejected rather than rounded.
+pub(crate) fn parse_fixed(amount: &str, exponent: u32) -> Result<i64, WalletError> {
+    let malformed = || WalletError::MalformedAmount(amount.to_string());
+    let trimmed = amount.trim();
+    let (negative, digits) = match trimmed.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+    };
+    let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+    let exponent = exponent as usize;
+    if (int_part.is_empty() && frac_part.is_empty())
+        || !int_part.chars().all(|c| c.is_ascii_digit())
+        || !frac_part.chars().all(|c| c.is_ascii_digit())
+        || frac_part.len() > exponent
+    {
+        return Err(malformed());
+    }
+
+    let mut value: i64 = 0;
+    let padded = format!("{:0<width$}", frac_part, width = exponent);
+    for c in int_part.chars().chain(padded.chars()) {
+        value = value
+            .checked_mul(10)
+            .and_then(|v| v.checked_add(c.to_digit(10).unwrap_or(0) as i64))
+            .ok_or(WalletError::Overflow)?;
+    }
+    Ok(if negative { -value } else { value })
+}
+
+/// Formats an integer scaled by `10^exponent` as a decimal string, e.g. `"-12.50"`
+pub(crate) fn format_fixed(value: i64, exponent: u32) -> String {
+    let scale = 10u64.pow(exponent);
+    let abs = value.unsigned_abs();
+    let sign = if value < 0 { "-" } else { "" };
+    if exponent == 0 {
+        return format!("{}{}", sign, abs);
+    }
+    format!(
+        "{}{}.{:0width$}",
+        sign,
+        abs / scale,
+        abs % scale,
+        width = exponent as usize
+    )
+}
+
+impl PartialOrd for Money {
+    /// amounts in different currencies are not comparable
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.currency != other.currency {
+            return None;
+        }
+        Some(self.minor_units.cmp(&other.minor_units))
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.currency, self.amount_string())
+    }
+}
+
+impl From<Money> for MoneyRepr {
+    fn from(value: Money) -> Self {
+        Self {
+            amount: value.amount_string(),
+            currency: value.currency,
+        }
+    }
+}
+
+impl TryFrom<MoneyRepr> for Money {
+    type Error = WalletError;
+
+    fn try_from(value: MoneyRepr) -> Result<Self, Self::Error> {
+        Money::parse(&value.amount, value.currency)
+    }
+}
+
+/// Encodes the amount as a Postgres NUMERIC with the currency exponent as scale.
+/// The currency itself is not part of the value and has to live in its own column.
+impl ToSql for Money {
+    fn to_sql(&self, _ty: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        let exponent = self.currency.exponent();
+        // pad the fraction to whole base-10000 digits
+        let frac_groups = exponent.div_ceil(4);
+        let padding = frac_groups * 4 - exponent;
+        let mut value = self.minor_units.unsigned_abs() as u128 * 10u128.pow(padding);
+
+        let mut groups: Vec<i16> = Vec::new();
+        while value > 0 {
+            groups.push((value % NUMERIC_NBASE as u128) as i16);
+            value /= NUMERIC_NBASE as u128;
+        }
+        while groups.len() < frac_groups as usize {
+            groups.push(0);
+        }
+        groups.reverse();
+
+        let mut weight = groups.len() as i16 - frac_groups as i16 - 1;
+        let leading = groups.iter().take_while(|d| **d == 0).count();
+        groups.drain(..leading);
+        weight -= leading as i16;
+        while groups.last() == Some(&0) {
+            groups.pop();
+        }
+        if groups.is_empty() {
+            weight = 0;
+        }
+
+        let sign = if self.minor_units < 0 { NUMERIC_NEG } else { NUMERIC_POS };
+        out.put_i16(groups.len() as i16);
+        out.put_i16(weight);
+        out.put_u16(sign);
+        out.put_u16(exponent as u16);
+        for digit in groups {
+            out.put_i16(digit);
+        }
+        Ok(IsNull::No)
+    }
+
+    accepts!(NUMERIC);
+    to_sql_checked!();
+}
+
+/// Decodes a Postgres NUMERIC into the default currency; callers reading a
+/// separate currency column should follow up with [`Money::with_currency`].
+impl<'a> FromSql<'a> for Money {
+    fn from_sql(_ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        if raw.len() < 8 {
+            return Err("invalid NUMERIC length".into());
+        }
+        let read_i16 = |at: usize| i16::from_be_bytes([raw[at], raw[at + 1]]);
+        let ndigits = read_i16(0);
+        let weight = read_i16(2) as i32;
+        let sign = u16::from_be_bytes([raw[4], raw[5]]);
+        if sign != NUMERIC_POS && sign != NUMERIC_NEG {
+            return Err("NUMERIC NaN or infinity cannot be represented as Money".into());
+        }
+        if ndigits < 0 || raw.len() != 8 + ndigits as usize * 2 {
+            return Err("invalid NUMERIC length".into());
+        }
+
+        let currency = Currency::default();
+        let exponent = currency.exponent() as i32;
+        let mut minor: i128 = 0;
+        for i in 0..ndigits as usize {
+            let digit = read_i16(8 + i * 2) as i128;
+            // power of ten this base-10000 digit contributes to the minor units
+            let power = 4 * (weight - i as i32) + exponent;
+            if power >= 0 {
+                let scaled = 10i128
+                    .checked_pow(power as u32)
+                    .and_then(|p| p.checked_mul(digit))
+                    .ok_or("NUMERIC value overflows Money")?;
+                minor = minor.checked_add(scaled).ok_or("NUMERIC value overflows Money")?;
+            } else {
+                let divisor = 10i128.checked_pow((-power) as u32).unwrap_or(i128::MAX);
+                if digit % divisor != 0 {
+                    return Err("NUMERIC value has more precision than the currency allows".into());
+                }
+                minor += digit / divisor;
+            }
+        }
+        if sign == NUMERIC_NEG {
+            minor = -minor;
+        }
+        let minor = i64::try_from(minor).map_err(|_| "NUMERIC value overflows Money")?;
+        Ok(Money::new(minor, currency))
+    }
+
+    accepts!(NUMERIC);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idr(minor: i64) -> Money {
+        Money::new(minor, Currency::IDR)
+    }
+
+    fn encode(money: Money) -> BytesMut {
+        let mut out = BytesMut::new();
+        money.to_sql(&Type::NUMERIC, &mut out).unwrap();
+        out
+    }
+
+    /// NUMERIC wire format: ndigits, weight, sign, dscale, then base-10000 digits
+    fn numeric(weight: i16, sign: u16, dscale: u16, digits: &[i16]) -> Vec<u8> {
+        let mut out = BytesMut::new();
+        out.put_i16(digits.len() as i16);
+        out.put_i16(weight);
+        out.put_u16(sign);
+        out.put_u16(dscale);
+        for digit in digits {
+            out.put_i16(*digit);
+        }
+        out.to_vec()
+    }
+
+    #[test]
+    fn numeric_round_trips() {
+        let values = [
+            0,
+            1,
+            -1,
+            1_250,
+            -5,
+            999_999,
+            1_000_000,
+            -1_000_000,
+            100_000_000_000,
+            i64::MAX,
+            i64::MIN,
+        ];
+        for minor in values {
+            let decoded = Money::from_sql(&Type::NUMERIC, &encode(idr(minor))).unwrap();
+            assert_eq!(decoded, idr(minor), "round trip of {}", minor);
+        }
+    }
+
+    #[test]
+    fn numeric_encoding_matches_postgres() {
+        // 12.50
+        assert_eq!(encode(idr(1_250)).to_vec(), numeric(0, NUMERIC_POS, 2, &[12, 5000]));
+        // 0.01, the only digit sits below the decimal point
+        assert_eq!(encode(idr(1)).to_vec(), numeric(-1, NUMERIC_POS, 2, &[100]));
+        // 9999.99 and 10000.00 either side of a weight boundary
+        assert_eq!(encode(idr(999_999)).to_vec(), numeric(0, NUMERIC_POS, 2, &[9999, 9900]));
+        assert_eq!(encode(idr(1_000_000)).to_vec(), numeric(1, NUMERIC_POS, 2, &[1]));
+        // -0.05
+        assert_eq!(encode(idr(-5)).to_vec(), numeric(-1, NUMERIC_NEG, 2, &[500]));
+        // zero has no digits
+        assert_eq!(encode(idr(0)).to_vec(), numeric(0, NUMERIC_POS, 2, &[]));
+    }
+
+    #[test]
+    fn numeric_decoding_rejects_unrepresentable_values() {
+        // 0.001 has more precision than IDR
+        assert!(Money::from_sql(&Type::NUMERIC, &numeric(-1, NUMERIC_POS, 3, &[10])).is_err());
+        // NaN
+        assert!(Money::from_sql(&Type::NUMERIC, &numeric(0, 0xC000, 0, &[])).is_err());
+        // 10^20 overflows i64 minor units
+        assert!(Money::from_sql(&Type::NUMERIC, &numeric(5, NUMERIC_POS, 0, &[1])).is_err());
+        // truncated digits
+        assert!(Money::from_sql(&Type::NUMERIC, &numeric(0, NUMERIC_POS, 2, &[12])[..9]).is_err());
+    }
+
+    #[test]
+    fn parse_accepts_decimal_strings() {
+        assert_eq!(Money::parse("1500.25", Currency::IDR).unwrap(), idr(150_025));
+        assert_eq!(Money::parse("-0.5", Currency::IDR).unwrap(), idr(-50));
+        assert_eq!(Money::parse("+3", Currency::IDR).unwrap(), idr(300));
+        assert_eq!(Money::parse(".05", Currency::IDR).unwrap(), idr(5));
+    }
+
+    #[test]
+    fn parse_rejects_malformed_amounts() {
+        for amount in ["1.234", "0.001", "abc", "1.2.3", "", "-", ".", "1e5", "12,50", "--1"] {
+            assert!(
+                matches!(Money::parse(amount, Currency::IDR), Err(WalletError::MalformedAmount(_))),
+                "{:?} should be rejected",
+                amount
+            );
+        }
+        assert!(matches!(
+            Money::parse("999999999999999999999", Currency::IDR),
+            Err(WalletError::Overflow)
+        ));
+    }
+
+    #[test]
+    fn unknown_currencies_are_rejected() {
+        assert!(matches!("EUR".parse::<Currency>(), Err(WalletError::UnsupportedCurrency(_))));
+        assert_eq!("usd".parse::<Currency>().unwrap(), Currency::USD);
+        assert!(serde_json::from_str::<Money>(r#"{"amount": "1.00", "currency": "EUR"}"#).is_err());
+        assert!(serde_json::from_str::<Money>(r#"{"amount": "1.001", "currency": "IDR"}"#).is_err());
+    }
+}
@@ -0,0 +1,16 @@
+use crate::transfer::transfer::TransferStatus;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ReceiptError {
+    #[error("Transfer in status {0:?} is not completed")]
+    NotCompleted(TransferStatus),
+    #[error("Invalid transaction id: {0}")]
+    InvalidTransactionId(String),
+    #[error("Receipt for {0} not found")]
+    ReceiptNotFound(String),
+    #[error("Transfer {0} not found")]
+    TransferNotFound(String),
+    #[error("Receipt for {0} was already issued")]
+    AlreadyIssued(String),
+}
@@ -1 +1,2 @@
-pub mod receipt;
\ No newline at end of file
+pub mod receipt;
+pub mod error;
@@ -1,16 +1,55 @@
 use serde::{Deserialize, Serialize};
 use crate::base::base::AuditMetadata;
-use crate::transfer::transfer::TransferStatus;
+use crate::money::exchange::Conversion;
+use crate::money::money::Money;
+use crate::transfer::transfer::{Transfer, TransferStatus};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "snake_case")]
 pub struct Receipt {
     pub id: Option<i32>,
     pub transaction_id: String,
+    /// transaction id of the reversed transfer when this receipt is for a reversal
+    #[serde(default)]
+    pub original_transaction_id: Option<String>,
     pub user_email: String,
-    pub amount: f64,
+    /// debited account of the transfer
+    #[serde(default)]
+    pub sender: String,
+    /// credited account of the transfer
+    #[serde(default)]
+    pub receiver: String,
+    pub amount: Money,
+    pub conversion: Option<Conversion>,
     pub status: TransferStatus,
     pub execution_time: String,
     pub audit: AuditMetadata
 
-}
\ No newline at end of file
+}
+
+impl Receipt {
+    pub fn for_transfer(transfer: &Transfer, user_email: &str) -> Self {
+        let executed_at = transfer
+            .last_transition()
+            .map(|t| t.at)
+            .unwrap_or(transfer.audit.created_date);
+        Self {
+            id: None,
+            transaction_id: transfer.transaction_id.clone(),
+            original_transaction_id: transfer.reversal_of.clone(),
+            user_email: user_email.to_string(),
+            sender: transfer.account_debit.clone(),
+            receiver: transfer.account_credit.clone(),
+            amount: transfer.amount,
+            conversion: transfer.conversion,
+            status: transfer.status,
+            execution_time: executed_at.to_rfc3339(),
+            audit: AuditMetadata::new(),
+        }
+    }
+
+    /// Only transfers that moved money for good get a receipt.
+    pub fn is_completed(status: TransferStatus) -> bool {
+        matches!(status, TransferStatus::Committed | TransferStatus::Reversed)
+    }
+}
@@ -0,0 +1,39 @@
+use crate::money::money::Money;
+use crate::transfer::schedule::ScheduleStatus;
+use crate::transfer::transfer::TransferStatus;
+use crate::wallet::error::WalletError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TransferError {
+    #[error("Transfer {0} not found")]
+    TransferNotFound(String),
+    #[error("Schedule {0} not found")]
+    ScheduleNotFound(i32),
+    #[error("Transfer amount must be positive, got {0}")]
+    InvalidAmount(Money),
+    #[error("Sender and receiver must differ")]
+    SameParties,
+    #[error("Illegal transfer transition from {0:?} to {1:?}")]
+    IllegalTransition(TransferStatus, TransferStatus),
+    #[error("Transfer in status {0:?} cannot be reversed")]
+    NotReversible(TransferStatus),
+    #[error("Transfer {0} is itself a reversal and cannot be reversed")]
+    ReversalOfReversal(String),
+    #[error("Reversal amount must be positive, got {0}")]
+    InvalidReversalAmount(Money),
+    #[error("Reversal of {0} exceeds the {1} left to reverse")]
+    ReversalExceedsTransfer(Money, Money),
+    #[error("Transfer {0} is no longer {1:?}")]
+    ConcurrentTransition(String, TransferStatus),
+    #[error("Transfer {0} already exists")]
+    DuplicateTransaction(String),
+    #[error("Invalid recipient: {0}")]
+    InvalidRecipient(String),
+    #[error("Invalid schedule: {0}")]
+    InvalidSchedule(String),
+    #[error("Schedule is {0:?} and cannot be changed that way")]
+    ScheduleClosed(ScheduleStatus),
+    #[error(transparent)]
+    Amount(#[from] WalletError),
+}
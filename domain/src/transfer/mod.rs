@@ -1 +1,3 @@
-pub mod transfer;
\ No newline at end of file
+pub mod transfer;
+pub mod error;
+pub mod schedule;
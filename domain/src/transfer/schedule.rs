@@ -0,0 +1,338 @@
+use crate::base::base::{AuditMetadata, Auditable};
+use crate::money::money::Money;
+use crate::transfer::error::TransferError;
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, Utc};
+use postgres_types::{FromSql, ToSql};
+use serde::{Deserialize, Serialize};
+
+/// How often a scheduled transfer repeats, keeping the time of day of its first run.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Recurrence {
+    /// runs a single time
+    Once,
+    Daily,
+    Weekly,
+    /// on the given day, or the last day of shorter months
+    Monthly { day_of_month: u32 },
+}
+
+impl Recurrence {
+    /// occurrence following `previous`, `None` when the schedule is done
+    pub fn next_after(&self, previous: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Recurrence::Once => None,
+            Recurrence::Daily => Some(previous + Duration::days(1)),
+            Recurrence::Weekly => Some(previous + Duration::weeks(1)),
+            Recurrence::Monthly { day_of_month } => {
+                let first = previous.with_day(1)?.checked_add_months(Months::new(1))?;
+                let day = (*day_of_month).clamp(1, days_in_month(first.year(), first.month()));
+                first.with_day(day)
+            }
+        }
+    }
+
+    fn validate(&self) -> Result<(), TransferError> {
+        match self {
+            Recurrence::Monthly { day_of_month } if !(1..=31).contains(day_of_month) => {
+                Err(TransferError::InvalidSchedule(format!(
+                    "day_of_month must be between 1 and 31, got {}",
+                    day_of_month
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    NaiveDate::from_ymd_opt(next_year, next_month, 1)
+        .and_then(|d| d.pred_opt())
+        .map(|d| d.day())
+        .unwrap_or(28)
+}
+
+/// What happens when an occurrence fails: it is retried after
+/// `retry_delay_seconds` until `max_attempts` is reached, then skipped.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub retry_delay_seconds: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            retry_delay_seconds: 3600,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSql, FromSql)]
+#[postgres(name = "schedule_status")]
+pub enum ScheduleStatus {
+    #[postgres(name = "Active")]
+    Active,
+    /// kept but not executed until resumed
+    #[postgres(name = "Paused")]
+    Paused,
+    /// a schedule with no occurrences left
+    #[postgres(name = "Completed")]
+    Completed,
+    #[postgres(name = "Cancelled")]
+    Cancelled,
+}
+
+impl ScheduleStatus {
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, ScheduleStatus::Completed | ScheduleStatus::Cancelled)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSql, FromSql)]
+#[postgres(name = "execution_outcome")]
+pub enum ExecutionOutcome {
+    /// the transfer was committed
+    #[postgres(name = "Committed")]
+    Committed,
+    /// the transfer was started and is finished by saga recovery
+    #[postgres(name = "Pending")]
+    Pending,
+    /// the attempt failed and will be retried
+    #[postgres(name = "Retrying")]
+    Retrying,
+    /// the last allowed attempt failed, the occurrence was skipped
+    #[postgres(name = "Skipped")]
+    Skipped,
+}
+
+/// Outcome of one attempt at one occurrence of a schedule.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ScheduleExecution {
+    pub scheduled_for: DateTime<Utc>,
+    pub attempt: u32,
+    /// transfer started by the attempt, if it got that far
+    pub transaction_id: Option<String>,
+    pub outcome: ExecutionOutcome,
+    pub message: String,
+    pub at: DateTime<Utc>,
+}
+
+/// A standing order sending `amount` from one user to another on a recurrence.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScheduledTransfer {
+    pub id: Option<i32>,
+    pub from_id: i32,
+    pub to_id: i32,
+    pub amount: Money,
+    pub recurrence: Recurrence,
+    pub policy: RetryPolicy,
+    /// occurrence to execute next
+    pub next_run_at: DateTime<Utc>,
+    /// failed attempts at `next_run_at` so far
+    pub attempts: u32,
+    /// when the failed occurrence is tried again
+    pub retry_at: Option<DateTime<Utc>>,
+    pub status: ScheduleStatus,
+    pub audit: AuditMetadata,
+}
+
+impl ScheduledTransfer {
+    pub fn new(
+        from_id: i32,
+        to_id: i32,
+        amount: Money,
+        recurrence: Recurrence,
+        first_run_at: DateTime<Utc>,
+        policy: RetryPolicy,
+    ) -> Result<Self, TransferError> {
+        let schedule = Self {
+            id: None,
+            from_id,
+            to_id,
+            amount,
+            recurrence,
+            policy,
+            next_run_at: first_run_at,
+            attempts: 0,
+            retry_at: None,
+            status: ScheduleStatus::Active,
+            audit: AuditMetadata::new(),
+        };
+        schedule.validate()?;
+        Ok(schedule)
+    }
+
+    pub fn validate(&self) -> Result<(), TransferError> {
+        if !self.amount.is_positive() {
+            return Err(TransferError::InvalidSchedule(format!(
+                "amount must be positive, got {}",
+                self.amount
+            )));
+        }
+        if self.from_id == self.to_id {
+            return Err(TransferError::InvalidSchedule(
+                "sender and receiver must differ".to_string(),
+            ));
+        }
+        if self.policy.max_attempts == 0 {
+            return Err(TransferError::InvalidSchedule(
+                "max_attempts must be at least 1".to_string(),
+            ));
+        }
+        self.recurrence.validate()
+    }
+
+    /// when the executor should pick the schedule up next
+    pub fn due_at(&self) -> DateTime<Utc> {
+        self.retry_at.unwrap_or(self.next_run_at)
+    }
+
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.status == ScheduleStatus::Active && self.due_at() <= now
+    }
+
+    /// transaction id of the current attempt, the same on every retry of a
+    /// crashed executor so an attempt never starts two transfers
+    pub fn attempt_transaction_id(&self) -> String {
+        format!(
+            "sched-{}-{}-{}",
+            self.id.unwrap_or_default(),
+            self.next_run_at.timestamp(),
+            self.attempts + 1
+        )
+    }
+
+    /// Records a started transfer and moves on to the next occurrence.
+    pub fn record_started(&mut self, transaction_id: &str, committed: bool, now: DateTime<Utc>) -> ScheduleExecution {
+        let outcome = if committed {
+            ExecutionOutcome::Committed
+        } else {
+            ExecutionOutcome::Pending
+        };
+        self.execution(Some(transaction_id), outcome, "transfer started", now)
+    }
+
+    /// Records a failed attempt; retries it per policy or skips the occurrence.
+    pub fn record_failure(&mut self, transaction_id: Option<&str>, message: &str, now: DateTime<Utc>) -> ScheduleExecution {
+        if self.attempts + 1 < self.policy.max_attempts {
+            let execution = ScheduleExecution {
+                scheduled_for: self.next_run_at,
+                attempt: self.attempts + 1,
+                transaction_id: transaction_id.map(str::to_string),
+                outcome: ExecutionOutcome::Retrying,
+                message: message.to_string(),
+                at: now,
+            };
+            self.attempts += 1;
+            self.retry_at = Some(now + Duration::seconds(self.policy.retry_delay_seconds as i64));
+            self.audit.touch();
+            return execution;
+        }
+        self.execution(transaction_id, ExecutionOutcome::Skipped, message, now)
+    }
+
+    pub fn pause(&mut self) -> Result<(), TransferError> {
+        self.change_status(ScheduleStatus::Active, ScheduleStatus::Paused)
+    }
+
+    pub fn resume(&mut self) -> Result<(), TransferError> {
+        self.change_status(ScheduleStatus::Paused, ScheduleStatus::Active)
+    }
+
+    pub fn cancel(&mut self) -> Result<(), TransferError> {
+        if self.status.is_terminal() {
+            return Err(TransferError::ScheduleClosed(self.status));
+        }
+        self.status = ScheduleStatus::Cancelled;
+        self.audit.touch();
+        Ok(())
+    }
+
+    fn change_status(&mut self, from: ScheduleStatus, to: ScheduleStatus) -> Result<(), TransferError> {
+        if self.status != from {
+            return Err(TransferError::ScheduleClosed(self.status));
+        }
+        self.status = to;
+        self.audit.touch();
+        Ok(())
+    }
+
+    /// closes the current occurrence and advances to the next one
+    fn execution(
+        &mut self,
+        transaction_id: Option<&str>,
+        outcome: ExecutionOutcome,
+        message: &str,
+        now: DateTime<Utc>,
+    ) -> ScheduleExecution {
+        let execution = ScheduleExecution {
+            scheduled_for: self.next_run_at,
+            attempt: self.attempts + 1,
+            transaction_id: transaction_id.map(str::to_string),
+            outcome,
+            message: message.to_string(),
+            at: now,
+        };
+        self.attempts = 0;
+        self.retry_at = None;
+        // occurrences missed while the executor was down are not replayed
+        let mut next = self.recurrence.next_after(self.next_run_at);
+        while let Some(at) = next.filter(|at| *at <= now) {
+            next = self.recurrence.next_after(at);
+        }
+        match next {
+            Some(at) => self.next_run_at = at,
+            None => self.status = ScheduleStatus::Completed,
+        }
+        self.audit.touch();
+        execution
+    }
+}
+
+impl Auditable for ScheduledTransfer {
+    fn audit(&self) -> &AuditMetadata { &self.audit }
+    fn audit_mut(&mut self) -> &mut AuditMetadata { &mut self.audit }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 9, 30, 0).unwrap()
+    }
+
+    #[test]
+    fn once_has_no_next_occurrence() {
+        assert_eq!(Recurrence::Once.next_after(at(2024, 1, 31)), None);
+    }
+
+    #[test]
+    fn daily_and_weekly_keep_the_time_of_day() {
+        assert_eq!(Recurrence::Daily.next_after(at(2024, 12, 31)), Some(at(2025, 1, 1)));
+        assert_eq!(Recurrence::Weekly.next_after(at(2024, 2, 26)), Some(at(2024, 3, 4)));
+    }
+
+    #[test]
+    fn monthly_clamps_to_the_end_of_shorter_months() {
+        let monthly = Recurrence::Monthly { day_of_month: 31 };
+        assert_eq!(monthly.next_after(at(2024, 1, 31)), Some(at(2024, 2, 29)));
+        assert_eq!(monthly.next_after(at(2023, 1, 31)), Some(at(2023, 2, 28)));
+        assert_eq!(monthly.next_after(at(2024, 3, 31)), Some(at(2024, 4, 30)));
+        assert_eq!(monthly.next_after(at(2024, 12, 31)), Some(at(2025, 1, 31)));
+    }
+
+    #[test]
+    fn monthly_returns_to_its_day_after_a_short_month() {
+        let monthly = Recurrence::Monthly { day_of_month: 31 };
+        let february = monthly.next_after(at(2024, 1, 31)).unwrap();
+        assert_eq!(monthly.next_after(february), Some(at(2024, 3, 31)));
+
+        let monthly = Recurrence::Monthly { day_of_month: 15 };
+        assert_eq!(monthly.next_after(at(2024, 1, 15)), Some(at(2024, 2, 15)));
+    }
+}
@@ -1,19 +1,95 @@
 use crate::base::base::{AuditMetadata, Auditable};
+use crate::money::exchange::{Conversion, ExchangeRate};
+use crate::money::money::Money;
+use crate::transfer::error::TransferError;
+use crate::wallet::error::WalletError;
+use chrono::{DateTime, Utc};
+use postgres_types::{FromSql, ToSql};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+/// Lifecycle of a transfer:
+///
+/// ```text
+/// Initiated -> Reserved -> Committed -> Reversed
+///     |           |
+///     |           +--> NeedsIntervention
+///     |           |
+///     +-----------+--> Failed | Expired
+/// ```
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSql, FromSql)]
+#[postgres(name = "transfer_status")]
 pub enum TransferStatus {
-    Pending,
-    Success,
+    /// accepted, no money moved yet
+    #[postgres(name = "Initiated")]
+    Initiated,
+    /// funds taken from the sender, receiver not credited yet
+    #[postgres(name = "Reserved")]
+    Reserved,
+    /// receiver credited, the transfer is complete
+    #[postgres(name = "Committed")]
+    Committed,
+    #[postgres(name = "Failed")]
     Failed,
+    /// a committed transfer that was undone
+    #[postgres(name = "Reversed")]
+    Reversed,
+    /// never completed within its deadline
+    #[postgres(name = "Expired")]
+    Expired,
+    /// the credit and the refund of the sender were both rejected, the debited
+    /// funds have to be settled by an operator
+    #[postgres(name = "NeedsIntervention")]
+    NeedsIntervention,
+}
+
+impl TransferStatus {
+    pub fn can_transition_to(&self, next: TransferStatus) -> bool {
+        use TransferStatus::*;
+        matches!(
+            (self, next),
+            (Initiated, Reserved | Failed | Expired)
+                | (Reserved, Committed | Failed | Expired | NeedsIntervention)
+                | (Committed, Reversed)
+        )
+    }
+
+    /// no further transitions are possible
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            TransferStatus::Failed
+                | TransferStatus::Reversed
+                | TransferStatus::Expired
+                | TransferStatus::NeedsIntervention
+        )
+    }
+}
+
+/// One recorded status change of a transfer.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TransferTransition {
+    pub from: TransferStatus,
+    pub to: TransferStatus,
+    pub reason: String,
+    pub at: DateTime<Utc>,
 }
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Transfer {
     pub id: Option<i32>,
+    /// public reference shared with the ledger and the receipt
+    pub transaction_id: String,
     pub account_debit: String,
     pub account_credit: String,
-    pub amount: f64,
+    pub amount: Money,
+    /// set when the receiver is credited in another currency than `amount`
+    pub conversion: Option<Conversion>,
+    /// transaction id of the transfer this one compensates, if it is a reversal
+    #[serde(default)]
+    pub reversal_of: Option<String>,
     pub status: TransferStatus,
+    /// every status change, oldest first
+    pub transitions: Vec<TransferTransition>,
     pub audit: AuditMetadata,
 }
 
@@ -21,36 +97,257 @@ impl Transfer {
     pub fn new(
         account_debet: &str,
         account_credit: &str,
-        amount: f64,
-        status: TransferStatus,
+        amount: Money,
         audit: AuditMetadata,
     ) -> Self {
         Self {
             id: None,
+            transaction_id: Uuid::new_v4().to_string(),
             account_debit: account_debet.to_string(),
             account_credit: account_credit.to_string(),
             amount,
-            status,
+            conversion: None,
+            reversal_of: None,
+            status: TransferStatus::Initiated,
+            transitions: Vec::new(),
             audit,
         }
     }
 
-    pub fn mark_success(&mut self) {
-        self.status = TransferStatus::Success;
-        self.audit.touch();
+    /// replaces the random transaction id with one the caller derived, e.g.
+    /// from an idempotency key
+    pub fn with_transaction_id(mut self, transaction_id: String) -> Self {
+        self.transaction_id = transaction_id;
+        self
     }
 
-    pub fn mark_failed(&mut self) {
-        self.status = TransferStatus::Failed;
-        self.audit.touch();
+    pub fn with_conversion(mut self, conversion: Conversion) -> Self {
+        self.conversion = Some(conversion);
+        self
+    }
+
+    /// amount the receiver is credited with
+    pub fn credit_amount(&self) -> Money {
+        self.conversion
+            .map(|c| c.credit_amount)
+            .unwrap_or(self.amount)
     }
 
-    pub fn mark_pending(&mut self) {
-        self.status = TransferStatus::Pending;
+    /// part of the credited amount not yet taken back by `already_reversed`
+    pub fn reversible_amount(&self, already_reversed: Money) -> Result<Money, TransferError> {
+        Ok(self.credit_amount().checked_sub(already_reversed)?)
+    }
+
+    /// Builds the compensating transfer that takes `amount` back from the
+    /// receiver and refunds the sender, linked to this transfer by `reversal_of`.
+    ///
+    /// `amount` is in the credited currency and defaults to everything left
+    /// to reverse; `already_reversed` is the total of earlier reversals that
+    /// did not fail. For converted transfers the sender is refunded
+    /// proportionally at the original rate, rounded down; the reversal is
+    /// quoted from the credited currency at the inverse of the applied rate,
+    /// with no spread of its own.
+    pub fn reversal(
+        &self,
+        amount: Option<Money>,
+        already_reversed: Money,
+    ) -> Result<Transfer, TransferError> {
+        if let Some(original) = &self.reversal_of {
+            return Err(TransferError::ReversalOfReversal(original.clone()));
+        }
+        if self.status != TransferStatus::Committed {
+            return Err(TransferError::NotReversible(self.status));
+        }
+        let remaining = self.reversible_amount(already_reversed)?;
+        let amount = amount.unwrap_or(remaining);
+        if !amount.is_positive() {
+            return Err(TransferError::InvalidReversalAmount(amount));
+        }
+        if amount.checked_sub(remaining)?.is_positive() {
+            return Err(TransferError::ReversalExceedsTransfer(amount, remaining));
+        }
+
+        let mut reversal = Transfer::new(
+            &self.account_credit,
+            &self.account_debit,
+            amount,
+            AuditMetadata::new(),
+        );
+        reversal.reversal_of = Some(self.transaction_id.clone());
+        if let Some(conversion) = self.conversion {
+            let refund = conversion.debit_amount.minor_units() as i128 * amount.minor_units() as i128
+                / conversion.credit_amount.minor_units() as i128;
+            let refund = i64::try_from(refund).map_err(|_| WalletError::Overflow)?;
+            let applied_rate = conversion.applied_rate.inverse()?;
+            reversal.conversion = Some(Conversion {
+                rate: ExchangeRate::new(conversion.rate.quote, conversion.rate.base, applied_rate, 0),
+                applied_rate,
+                debit_amount: amount,
+                credit_amount: Money::new(refund, conversion.debit_amount.currency()),
+            });
+        }
+        Ok(reversal)
+    }
+
+    /// Moves the transfer to `next`, recording when and why.
+    /// Illegal moves leave the transfer untouched.
+    pub fn transition(&mut self, next: TransferStatus, reason: &str) -> Result<(), TransferError> {
+        if !self.status.can_transition_to(next) {
+            return Err(TransferError::IllegalTransition(self.status, next));
+        }
+        self.transitions.push(TransferTransition {
+            from: self.status,
+            to: next,
+            reason: reason.to_string(),
+            at: Utc::now(),
+        });
+        self.status = next;
         self.audit.touch();
+        Ok(())
+    }
+
+    pub fn mark_reserved(&mut self, reason: &str) -> Result<(), TransferError> {
+        self.transition(TransferStatus::Reserved, reason)
+    }
+
+    pub fn mark_committed(&mut self, reason: &str) -> Result<(), TransferError> {
+        self.transition(TransferStatus::Committed, reason)
+    }
+
+    pub fn mark_failed(&mut self, reason: &str) -> Result<(), TransferError> {
+        self.transition(TransferStatus::Failed, reason)
+    }
+
+    pub fn mark_reversed(&mut self, reason: &str) -> Result<(), TransferError> {
+        self.transition(TransferStatus::Reversed, reason)
+    }
+
+    pub fn mark_expired(&mut self, reason: &str) -> Result<(), TransferError> {
+        self.transition(TransferStatus::Expired, reason)
+    }
+
+    pub fn mark_needs_intervention(&mut self, reason: &str) -> Result<(), TransferError> {
+        self.transition(TransferStatus::NeedsIntervention, reason)
+    }
+
+    /// latest recorded transition, if any
+    pub fn last_transition(&self) -> Option<&TransferTransition> {
+        self.transitions.last()
     }
 }
 impl Auditable for Transfer {
     fn audit(&self) -> &AuditMetadata { &self.audit }
     fn audit_mut(&mut self) -> &mut AuditMetadata { &mut self.audit }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::exchange::Rate;
+    use crate::money::money::Currency;
+    use TransferStatus::*;
+
+    const ALL: [TransferStatus; 7] = [Initiated, Reserved, Committed, Failed, Reversed, Expired, NeedsIntervention];
+
+    #[test]
+    fn transitions_follow_the_lifecycle() {
+        let allowed = [
+            (Initiated, Reserved),
+            (Initiated, Failed),
+            (Initiated, Expired),
+            (Reserved, Committed),
+            (Reserved, Failed),
+            (Reserved, Expired),
+            (Reserved, NeedsIntervention),
+            (Committed, Reversed),
+        ];
+        for from in ALL {
+            for to in ALL {
+                assert_eq!(
+                    from.can_transition_to(to),
+                    allowed.contains(&(from, to)),
+                    "{:?} -> {:?}",
+                    from,
+                    to
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn terminal_states_have_no_way_out() {
+        for from in ALL {
+            let has_next = ALL.iter().any(|to| from.can_transition_to(*to));
+            assert_eq!(from.is_terminal(), !has_next, "{:?}", from);
+        }
+        // committed can still be reversed
+        assert!(!Committed.is_terminal());
+    }
+
+    #[test]
+    fn only_a_reserved_transfer_needs_intervention() {
+        let mut transfer = Transfer::new("880000000013", "880000000021", Money::new(100, Currency::IDR), AuditMetadata::new());
+        assert!(matches!(
+            transfer.mark_needs_intervention("refund rejected"),
+            Err(TransferError::IllegalTransition(Initiated, NeedsIntervention))
+        ));
+        transfer.mark_reserved("sender debited").unwrap();
+        transfer.mark_needs_intervention("refund rejected").unwrap();
+        assert_eq!(transfer.status, NeedsIntervention);
+        assert!(transfer.mark_failed("late failure").is_err());
+    }
+
+    fn committed(amount: Money) -> Transfer {
+        let mut transfer = Transfer::new("1", "2", amount, AuditMetadata::new());
+        transfer.mark_reserved("sender debited").unwrap();
+        transfer.mark_committed("receiver credited").unwrap();
+        transfer
+    }
+
+    #[test]
+    fn partial_reversals_never_exceed_the_transfer() {
+        let transfer = committed(Money::new(10_000, Currency::IDR));
+
+        let reversal = transfer.reversal(Some(Money::new(4_000, Currency::IDR)), Money::zero(Currency::IDR)).unwrap();
+        assert_eq!(reversal.account_debit, "2");
+        assert_eq!(reversal.account_credit, "1");
+        assert_eq!(reversal.amount, Money::new(4_000, Currency::IDR));
+        assert_eq!(reversal.reversal_of.as_deref(), Some(transfer.transaction_id.as_str()));
+        assert!(reversal.conversion.is_none());
+
+        let rest = transfer.reversal(None, Money::new(4_000, Currency::IDR)).unwrap();
+        assert_eq!(rest.amount, Money::new(6_000, Currency::IDR));
+        assert!(matches!(
+            transfer.reversal(Some(Money::new(6_001, Currency::IDR)), Money::new(4_000, Currency::IDR)),
+            Err(TransferError::ReversalExceedsTransfer(_, _))
+        ));
+        assert!(matches!(
+            transfer.reversal(None, Money::new(10_000, Currency::IDR)),
+            Err(TransferError::InvalidReversalAmount(_))
+        ));
+        assert!(matches!(
+            reversal.reversal(None, Money::zero(Currency::IDR)),
+            Err(TransferError::ReversalOfReversal(_))
+        ));
+    }
+
+    #[test]
+    fn converted_reversal_is_quoted_from_the_credited_currency() {
+        let rate = ExchangeRate::new(Currency::IDR, Currency::USD, Rate::parse("0.00006").unwrap(), 100);
+        // 1,000,000.00 IDR at 0.0000594 after the spread
+        let conversion = rate.convert(Money::new(100_000_000, Currency::IDR)).unwrap();
+        assert_eq!(conversion.credit_amount, Money::new(5_940, Currency::USD));
+        let transfer = committed(Money::new(100_000_000, Currency::IDR)).with_conversion(conversion);
+
+        let reversal = transfer.reversal(Some(Money::new(2_970, Currency::USD)), Money::zero(Currency::USD)).unwrap();
+
+        let refund = reversal.conversion.unwrap();
+        assert_eq!(refund.debit_amount, Money::new(2_970, Currency::USD));
+        assert_eq!(refund.credit_amount, Money::new(50_000_000, Currency::IDR));
+        assert_eq!(refund.rate.base, Currency::USD);
+        assert_eq!(refund.rate.quote, Currency::IDR);
+        assert_eq!(refund.rate.spread_bps, 0);
+        assert_eq!(refund.applied_rate, Rate::parse("16835.01683501").unwrap());
+        assert_eq!(refund.rate.rate, refund.applied_rate);
+        assert_eq!(reversal.credit_amount(), Money::new(50_000_000, Currency::IDR));
+    }
+}
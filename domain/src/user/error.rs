@@ -0,0 +1,19 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum UserError {
+    #[error("User {0} not found")]
+    UserNotFound(i32),
+    #[error("Email {0} is already registered")]
+    EmailTaken(String),
+    #[error("Invalid email: {0}")]
+    InvalidEmail(String),
+    #[error("Name must not be empty")]
+    EmptyName,
+    #[error("{0} must be at most {1} characters")]
+    TooLong(&'static str, usize),
+    #[error("User {0} is inactive")]
+    UserInactive(i32),
+    #[error("User {0} is already active")]
+    AlreadyActive(i32),
+}
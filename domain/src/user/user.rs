@@ -1,35 +1,139 @@
 use postgres_types::{FromSql, ToSql};
 use crate::base::base::{AuditMetadata, Auditable};
+use crate::user::error::UserError;
 use serde::{Deserialize, Serialize};
 
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSql, FromSql)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, ToSql, FromSql)]
 #[postgres(name = "user_status")]
 pub enum UserStatus {
+    #[default]
     #[postgres(name = "Active")]
     Active,
+    /// deactivated, kept for history but no longer allowed to transact
     #[postgres(name = "Inactive")]
     Inactive,
+    /// signed up, waiting for the email to be verified
+    #[postgres(name = "Pending")]
+    Pending,
 }
 
+/// Verification level of a user, picks the default spending limits of their wallets.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default, ToSql, FromSql)]
+#[postgres(name = "user_tier")]
+pub enum UserTier {
+    #[default]
+    #[postgres(name = "Basic")]
+    Basic,
+    #[postgres(name = "Verified")]
+    Verified,
+    #[postgres(name = "Premium")]
+    Premium,
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct User {
     pub id: Option<i32>,
     pub email: String,
     pub name: String,
+    #[serde(default)]
+    pub status: UserStatus,
     pub audit: AuditMetadata,
 }
 
 impl User {
+    /// longest email and name the user table stores
+    pub const MAX_EMAIL_LENGTH: usize = 254;
+    pub const MAX_NAME_LENGTH: usize = 128;
+
     pub fn new(email: &str, name: &str) -> Self {
         Self {
             id: None,
-            email: email.into(),
-            name: name.into(),
+            email: normalize_email(email),
+            name: name.trim().into(),
+            status: UserStatus::Active,
             audit: AuditMetadata::new(),
         }
     }
+
+    /// A user that signed up and still has to verify their email.
+    pub fn pending(email: &str, name: &str) -> Self {
+        Self {
+            status: UserStatus::Pending,
+            ..Self::new(email, name)
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.status == UserStatus::Active
+    }
+
+    /// Checks the email has a local part and a dotted domain, and the name is
+    /// set, both within the lengths the user table stores.
+    pub fn validate(&self) -> Result<(), UserError> {
+        if self.email.chars().count() > Self::MAX_EMAIL_LENGTH {
+            return Err(UserError::TooLong("email", Self::MAX_EMAIL_LENGTH));
+        }
+        if self.name.chars().count() > Self::MAX_NAME_LENGTH {
+            return Err(UserError::TooLong("name", Self::MAX_NAME_LENGTH));
+        }
+        let valid_email = match self.email.split_once('@') {
+            Some((local, domain)) => {
+                !local.is_empty()
+                    && !domain.contains('@')
+                    && domain.split('.').count() > 1
+                    && domain.split('.').all(|part| !part.is_empty())
+                    && !self.email.chars().any(char::is_whitespace)
+            }
+            None => false,
+        };
+        if !valid_email {
+            return Err(UserError::InvalidEmail(self.email.clone()));
+        }
+        if self.name.is_empty() {
+            return Err(UserError::EmptyName);
+        }
+        Ok(())
+    }
+
+    /// Changes the email and/or name of an active user.
+    pub fn update(&mut self, email: Option<&str>, name: Option<&str>) -> Result<(), UserError> {
+        if !self.is_active() {
+            return Err(UserError::UserInactive(self.id.unwrap_or_default()));
+        }
+        if let Some(email) = email {
+            self.email = normalize_email(email);
+        }
+        if let Some(name) = name {
+            self.name = name.trim().into();
+        }
+        self.validate()?;
+        self.audit.touch();
+        Ok(())
+    }
+
+    pub fn deactivate(&mut self) -> Result<(), UserError> {
+        if !self.is_active() {
+            return Err(UserError::UserInactive(self.id.unwrap_or_default()));
+        }
+        self.status = UserStatus::Inactive;
+        self.audit.touch();
+        Ok(())
+    }
+
+    pub fn activate(&mut self) -> Result<(), UserError> {
+        if self.is_active() {
+            return Err(UserError::AlreadyActive(self.id.unwrap_or_default()));
+        }
+        self.status = UserStatus::Active;
+        self.audit.touch();
+        Ok(())
+    }
+}
+
+/// emails are compared case-insensitively, so they are stored lowercase
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
 }
 
 impl Auditable for User {
@@ -40,3 +144,20 @@ impl Auditable for User {
         &mut self.audit
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn email_and_name_fit_their_columns() {
+        let local = "a".repeat(User::MAX_EMAIL_LENGTH - "@x.io".len());
+        let longest = User::new(&format!("{local}@x.io"), &"n".repeat(User::MAX_NAME_LENGTH));
+        assert!(longest.validate().is_ok());
+
+        let email = User::new(&format!("a{local}@x.io"), "name");
+        assert!(matches!(email.validate(), Err(UserError::TooLong("email", 254))));
+        let name = User::new("a@x.io", &"é".repeat(User::MAX_NAME_LENGTH + 1));
+        assert!(matches!(name.validate(), Err(UserError::TooLong("name", 128))));
+    }
+}
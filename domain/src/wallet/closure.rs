@@ -0,0 +1,40 @@
+use crate::ledger::ledger::LedgerAccount;
+use crate::money::money::Money;
+use crate::wallet::wallet::StatusReason;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Where the remaining balance of a closing wallet is swept to.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SweepDestination {
+    /// wallet of the user nominated by the owner
+    Wallet(i32),
+    /// system suspense account, kept there until claimed
+    Suspense,
+}
+
+impl SweepDestination {
+    pub fn account(&self) -> LedgerAccount {
+        match self {
+            SweepDestination::Wallet(user_id) => LedgerAccount::Wallet(*user_id),
+            SweepDestination::Suspense => LedgerAccount::Suspense,
+        }
+    }
+}
+
+/// Proof of a wallet closure: what was left in each pocket and where it went.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ClosureReceipt {
+    /// prefix of the ledger references of the sweep entries
+    pub reference: String,
+    pub user_id: i32,
+    pub destination: SweepDestination,
+    /// balance of every non-empty pocket, all moved to `destination`
+    pub swept: Vec<Money>,
+    pub reason: StatusReason,
+    #[serde(default)]
+    pub note: Option<String>,
+    /// subject of whoever closed the wallet
+    pub actor: String,
+    pub closed_at: DateTime<Utc>,
+}
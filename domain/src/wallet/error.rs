@@ -1,9 +1,68 @@
+use crate::money::money::{Currency, Money};
+use crate::wallet::limit::LimitRule;
+use crate::wallet::wallet::WalletStatus;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum WalletError {
+    #[error("Wallet not found for user {0}")]
+    WalletNotFound(i32),
+    #[error("User {0} not found")]
+    UserNotFound(i32),
+    #[error("User {0} is inactive")]
+    UserInactive(i32),
     #[error("Insufficient balance: attempted to debit {0}, but only {1} available")]
-    InsufficientBalance(f64, f64),
+    InsufficientBalance(Money, Money),
     #[error("Invalid amount: {0}")]
-    InvalidAmount(f64),
-}
\ No newline at end of file
+    InvalidAmount(Money),
+    #[error("Malformed amount: {0}")]
+    MalformedAmount(String),
+    #[error("Unsupported currency: {0}")]
+    UnsupportedCurrency(String),
+    #[error("Currency mismatch: {0} and {1}")]
+    CurrencyMismatch(Currency, Currency),
+    #[error("Wallet has no {0} balance")]
+    CurrencyNotHeld(Currency),
+    #[error("Invalid spread: {0} bps")]
+    InvalidSpread(u32),
+    #[error("Capture of {0} exceeds the held {1}")]
+    CaptureExceedsHold(Money, Money),
+    #[error("Hold {0} not found")]
+    HoldNotFound(String),
+    #[error("Hold {0} is no longer active")]
+    HoldNotActive(String),
+    #[error("Hold {0} has expired")]
+    HoldExpired(String),
+    #[error("Hold {0} has not expired yet")]
+    HoldNotExpired(String),
+    #[error("Hold expiry must be between 1 and {1} seconds, got {0}")]
+    InvalidHoldExpiry(i64, i64),
+    #[error("Spending limit exceeded: {0}")]
+    LimitExceeded(LimitRule),
+    #[error("Wallet is {0:?} and cannot send money")]
+    DebitsBlocked(WalletStatus),
+    #[error("Wallet is {0:?} and cannot receive money")]
+    CreditsBlocked(WalletStatus),
+    #[error("Illegal wallet status transition from {0:?} to {1:?}")]
+    IllegalStatusTransition(WalletStatus, WalletStatus),
+    #[error("Status of wallet {0} changed concurrently")]
+    StatusConflict(i32),
+    #[error("Wallet {0} still has funds on hold")]
+    HoldsOutstanding(i32),
+    #[error("Wallet {0} has {1} pending transfer(s)")]
+    PendingTransfers(i32, usize),
+    #[error("Transfer {0} was already applied")]
+    AlreadyApplied(String),
+    #[error("Wallet {0} cannot be swept into itself")]
+    InvalidSweepTarget(i32),
+    #[error("No wallet with account number {0}")]
+    NorekNotFound(String),
+    #[error("Invalid account number {0:?}: {1}")]
+    InvalidNorek(String, &'static str),
+    #[error("Invalid account number format: {0}")]
+    InvalidNorekFormat(String),
+    #[error("Account number serial {0} does not fit the configured length")]
+    NorekExhausted(u64),
+    #[error("Amount overflow")]
+    Overflow,
+}
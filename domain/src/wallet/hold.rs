@@ -0,0 +1,184 @@
+use crate::base::base::{AuditMetadata, Auditable};
+use crate::money::money::Money;
+use crate::wallet::error::WalletError;
+use chrono::{DateTime, TimeDelta, Utc};
+use postgres_types::{FromSql, ToSql};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSql, FromSql)]
+#[postgres(name = "hold_status")]
+pub enum HoldStatus {
+    #[postgres(name = "Active")]
+    Active,
+    #[postgres(name = "Captured")]
+    Captured,
+    #[postgres(name = "Released")]
+    Released,
+    #[postgres(name = "Expired")]
+    Expired,
+}
+
+/// Funds reserved on a wallet until they are captured, released or the hold expires.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Hold {
+    pub id: Option<i32>,
+    /// public reference used to capture or release the hold
+    pub reference: String,
+    pub user_id: i32,
+    pub amount: Money,
+    /// set once the hold is captured, at most `amount`
+    pub captured_amount: Option<Money>,
+    pub status: HoldStatus,
+    pub expires_at: DateTime<Utc>,
+    pub audit: AuditMetadata,
+}
+
+impl Hold {
+    /// longest a hold may reserve funds, 30 days
+    pub const MAX_EXPIRY_SECONDS: i64 = 30 * 24 * 60 * 60;
+
+    /// When a hold placed at `now` for `expires_in_seconds` expires.
+    pub fn expiry(now: DateTime<Utc>, expires_in_seconds: i64) -> Result<DateTime<Utc>, WalletError> {
+        let invalid = || WalletError::InvalidHoldExpiry(expires_in_seconds, Self::MAX_EXPIRY_SECONDS);
+        if !(1..=Self::MAX_EXPIRY_SECONDS).contains(&expires_in_seconds) {
+            return Err(invalid());
+        }
+        TimeDelta::try_seconds(expires_in_seconds)
+            .and_then(|delta| now.checked_add_signed(delta))
+            .ok_or_else(invalid)
+    }
+
+    pub fn new(user_id: i32, amount: Money, expires_at: DateTime<Utc>) -> Self {
+        Self {
+            id: None,
+            reference: Uuid::new_v4().to_string(),
+            user_id,
+            amount,
+            captured_amount: None,
+            status: HoldStatus::Active,
+            expires_at,
+            audit: AuditMetadata::new(),
+        }
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at <= now
+    }
+
+    /// Captures `amount` (at most the held amount); the remainder is released.
+    pub fn capture(&mut self, amount: Money, now: DateTime<Utc>) -> Result<(), WalletError> {
+        self.ensure_active()?;
+        if self.is_expired(now) {
+            return Err(WalletError::HoldExpired(self.reference.clone()));
+        }
+        if !amount.is_positive() {
+            return Err(WalletError::InvalidAmount(amount));
+        }
+        if amount.checked_sub(self.amount)?.is_positive() {
+            return Err(WalletError::CaptureExceedsHold(amount, self.amount));
+        }
+        self.captured_amount = Some(amount);
+        self.status = HoldStatus::Captured;
+        self.audit.touch();
+        Ok(())
+    }
+
+    pub fn release(&mut self) -> Result<(), WalletError> {
+        self.ensure_active()?;
+        self.status = HoldStatus::Released;
+        self.audit.touch();
+        Ok(())
+    }
+
+    /// Ends an active hold past its `expires_at` without capturing anything.
+    pub fn expire(&mut self, now: DateTime<Utc>) -> Result<(), WalletError> {
+        self.ensure_active()?;
+        if !self.is_expired(now) {
+            return Err(WalletError::HoldNotExpired(self.reference.clone()));
+        }
+        self.status = HoldStatus::Expired;
+        self.audit.touch();
+        Ok(())
+    }
+
+    fn ensure_active(&self) -> Result<(), WalletError> {
+        if self.status != HoldStatus::Active {
+            return Err(WalletError::HoldNotActive(self.reference.clone()));
+        }
+        Ok(())
+    }
+}
+
+impl Auditable for Hold {
+    fn audit(&self) -> &AuditMetadata { &self.audit }
+    fn audit_mut(&mut self) -> &mut AuditMetadata { &mut self.audit }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::money::Currency;
+    use chrono::Duration;
+
+    fn idr(major: i64) -> Money {
+        Money::from_major(major, Currency::IDR).unwrap()
+    }
+
+    fn hold(now: DateTime<Utc>) -> Hold {
+        Hold::new(1, idr(100), now + Duration::minutes(5))
+    }
+
+    #[test]
+    fn capture_up_to_the_held_amount() {
+        let now = Utc::now();
+        let mut partial = hold(now);
+        partial.capture(idr(40), now).unwrap();
+        assert_eq!(partial.status, HoldStatus::Captured);
+        assert_eq!(partial.captured_amount, Some(idr(40)));
+
+        let mut over = hold(now);
+        assert!(matches!(over.capture(idr(101), now), Err(WalletError::CaptureExceedsHold(_, _))));
+        assert!(matches!(over.capture(idr(0), now), Err(WalletError::InvalidAmount(_))));
+        assert_eq!(over.status, HoldStatus::Active);
+    }
+
+    #[test]
+    fn finished_holds_cannot_be_finished_again() {
+        let now = Utc::now();
+        let mut released = hold(now);
+        released.release().unwrap();
+        assert_eq!(released.status, HoldStatus::Released);
+        assert!(matches!(released.capture(idr(1), now), Err(WalletError::HoldNotActive(_))));
+        assert!(matches!(released.release(), Err(WalletError::HoldNotActive(_))));
+
+        let mut captured = hold(now);
+        captured.capture(idr(100), now).unwrap();
+        assert!(matches!(captured.release(), Err(WalletError::HoldNotActive(_))));
+    }
+
+    #[test]
+    fn expiry_ends_only_holds_past_their_time() {
+        let now = Utc::now();
+        let mut active = hold(now);
+        assert!(matches!(active.expire(now), Err(WalletError::HoldNotExpired(_))));
+
+        let later = now + Duration::minutes(5);
+        assert!(matches!(active.capture(idr(1), later), Err(WalletError::HoldExpired(_))));
+        active.expire(later).unwrap();
+        assert_eq!(active.status, HoldStatus::Expired);
+        assert!(active.captured_amount.is_none());
+    }
+
+    #[test]
+    fn expiry_is_bounded() {
+        let now = Utc::now();
+        assert_eq!(Hold::expiry(now, 60).unwrap(), now + Duration::seconds(60));
+        assert!(Hold::expiry(now, Hold::MAX_EXPIRY_SECONDS).is_ok());
+        for seconds in [0, -1, Hold::MAX_EXPIRY_SECONDS + 1, i64::MAX, i64::MIN] {
+            assert!(matches!(Hold::expiry(now, seconds), Err(WalletError::InvalidHoldExpiry(_, _))));
+        }
+        // in range, but past the last representable instant
+        assert!(Hold::expiry(DateTime::<Utc>::MAX_UTC, 60).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+use crate::money::money::{Currency, Money};
+use crate::user::user::UserTier;
+use crate::wallet::error::WalletError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A spending rule a transfer can be rejected by.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitRule {
+    MaxSingleTransfer,
+    DailyOutgoing,
+    MonthlyOutgoing,
+    HourlyTransferCount,
+}
+
+impl LimitRule {
+    /// stable name of the rule, used in error messages
+    pub fn code(&self) -> &'static str {
+        match self {
+            LimitRule::MaxSingleTransfer => "max_single_transfer",
+            LimitRule::DailyOutgoing => "daily_outgoing",
+            LimitRule::MonthlyOutgoing => "monthly_outgoing",
+            LimitRule::HourlyTransferCount => "hourly_transfer_count",
+        }
+    }
+}
+
+impl fmt::Display for LimitRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// Outgoing limits of one currency pocket; `None` leaves a rule unlimited.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SpendingLimits {
+    pub currency: Currency,
+    #[serde(default)]
+    pub max_single_transfer: Option<Money>,
+    #[serde(default)]
+    pub daily_outgoing: Option<Money>,
+    #[serde(default)]
+    pub monthly_outgoing: Option<Money>,
+    #[serde(default)]
+    pub max_transfers_per_hour: Option<u32>,
+}
+
+/// Default limits for every wallet of a user tier, e.g.
+/// `{"tier": "Basic", "limits": {"currency": "IDR", "daily_outgoing": {...}}}`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TierLimits {
+    pub tier: UserTier,
+    pub limits: SpendingLimits,
+}
+
+/// What a pocket already sent in the current hour, day and month.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct SpendingCounters {
+    pub hourly_count: u32,
+    pub daily_outgoing: Money,
+    pub monthly_outgoing: Money,
+}
+
+impl SpendingCounters {
+    pub fn zero(currency: Currency) -> Self {
+        Self {
+            hourly_count: 0,
+            daily_outgoing: Money::zero(currency),
+            monthly_outgoing: Money::zero(currency),
+        }
+    }
+}
+
+impl SpendingLimits {
+    pub fn unlimited(currency: Currency) -> Self {
+        Self {
+            currency,
+            max_single_transfer: None,
+            daily_outgoing: None,
+            monthly_outgoing: None,
+            max_transfers_per_hour: None,
+        }
+    }
+
+    /// these limits with every rule set in `overrides` taking precedence
+    pub fn overridden_by(self, overrides: &SpendingLimits) -> Self {
+        Self {
+            currency: self.currency,
+            max_single_transfer: overrides.max_single_transfer.or(self.max_single_transfer),
+            daily_outgoing: overrides.daily_outgoing.or(self.daily_outgoing),
+            monthly_outgoing: overrides.monthly_outgoing.or(self.monthly_outgoing),
+            max_transfers_per_hour: overrides.max_transfers_per_hour.or(self.max_transfers_per_hour),
+        }
+    }
+
+    /// Checks one more outgoing transfer of `amount` against the limits,
+    /// naming the first rule it would break.
+    pub fn check(&self, amount: Money, counters: &SpendingCounters) -> Result<(), WalletError> {
+        if let Some(max) = self.max_single_transfer
+            && amount.checked_sub(max)?.is_positive()
+        {
+            return Err(WalletError::LimitExceeded(LimitRule::MaxSingleTransfer));
+        }
+        if let Some(max) = self.max_transfers_per_hour
+            && counters.hourly_count >= max
+        {
+            return Err(WalletError::LimitExceeded(LimitRule::HourlyTransferCount));
+        }
+        if let Some(max) = self.daily_outgoing
+            && counters.daily_outgoing.checked_add(amount)?.checked_sub(max)?.is_positive()
+        {
+            return Err(WalletError::LimitExceeded(LimitRule::DailyOutgoing));
+        }
+        if let Some(max) = self.monthly_outgoing
+            && counters.monthly_outgoing.checked_add(amount)?.checked_sub(max)?.is_positive()
+        {
+            return Err(WalletError::LimitExceeded(LimitRule::MonthlyOutgoing));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idr(major: i64) -> Money {
+        Money::from_major(major, Currency::IDR).unwrap()
+    }
+
+    fn limits() -> SpendingLimits {
+        SpendingLimits {
+            currency: Currency::IDR,
+            max_single_transfer: Some(idr(1_000)),
+            daily_outgoing: Some(idr(2_000)),
+            monthly_outgoing: Some(idr(5_000)),
+            max_transfers_per_hour: Some(3),
+        }
+    }
+
+    fn counters(hourly_count: u32, daily: i64, monthly: i64) -> SpendingCounters {
+        SpendingCounters {
+            hourly_count,
+            daily_outgoing: idr(daily),
+            monthly_outgoing: idr(monthly),
+        }
+    }
+
+    fn broken_rule(result: Result<(), WalletError>) -> Option<LimitRule> {
+        match result {
+            Ok(()) => None,
+            Err(WalletError::LimitExceeded(rule)) => Some(rule),
+            Err(e) => panic!("unexpected error {e}"),
+        }
+    }
+
+    #[test]
+    fn amounts_up_to_each_limit_pass() {
+        let limits = limits();
+        assert_eq!(broken_rule(limits.check(idr(1_000), &counters(2, 1_000, 4_000))), None);
+        assert_eq!(broken_rule(SpendingLimits::unlimited(Currency::IDR).check(idr(1_000_000), &counters(99, 0, 0))), None);
+    }
+
+    #[test]
+    fn first_broken_rule_is_named() {
+        let limits = limits();
+        let rule = |amount, counters| broken_rule(limits.check(amount, &counters));
+        assert_eq!(rule(idr(1_001), counters(0, 0, 0)), Some(LimitRule::MaxSingleTransfer));
+        assert_eq!(rule(idr(1), counters(3, 0, 0)), Some(LimitRule::HourlyTransferCount));
+        assert_eq!(rule(idr(500), counters(0, 1_501, 1_501)), Some(LimitRule::DailyOutgoing));
+        assert_eq!(rule(idr(500), counters(0, 0, 4_501)), Some(LimitRule::MonthlyOutgoing));
+        // single transfer is checked before the running totals
+        assert_eq!(rule(idr(3_000), counters(3, 2_000, 5_000)), Some(LimitRule::MaxSingleTransfer));
+    }
+
+    #[test]
+    fn overrides_replace_only_the_rules_they_set() {
+        let overrides = SpendingLimits {
+            daily_outgoing: Some(idr(10_000)),
+            ..SpendingLimits::unlimited(Currency::IDR)
+        };
+        let merged = limits().overridden_by(&overrides);
+        assert_eq!(merged.daily_outgoing, Some(idr(10_000)));
+        assert_eq!(merged.max_single_transfer, Some(idr(1_000)));
+        assert_eq!(merged.max_transfers_per_hour, Some(3));
+        assert_eq!(broken_rule(merged.check(idr(1_000), &counters(0, 3_000, 3_000))), None);
+    }
+
+    #[test]
+    fn other_currency_is_rejected() {
+        let usd = Money::from_major(1, Currency::USD).unwrap();
+        assert!(matches!(limits().check(usd, &counters(0, 0, 0)), Err(WalletError::CurrencyMismatch(_, _))));
+    }
+}
@@ -1,2 +1,6 @@
 pub mod wallet;
-mod error;
\ No newline at end of file
+pub mod error;
+pub mod hold;
+pub mod limit;
+pub mod closure;
+pub mod norek;
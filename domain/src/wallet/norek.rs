@@ -0,0 +1,192 @@
+use crate::wallet::error::WalletError;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Check digit scheme appended to every account number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CheckDigit {
+    /// one digit, catches any single wrong digit and most swapped neighbours
+    #[default]
+    Luhn,
+    /// two digits (ISO 7064 MOD 97-10, as in IBAN), also catches most double errors
+    Mod97,
+}
+
+impl CheckDigit {
+    pub fn digits(&self) -> usize {
+        match self {
+            CheckDigit::Luhn => 1,
+            CheckDigit::Mod97 => 2,
+        }
+    }
+
+    /// check digits for `payload`, which must be all ASCII digits
+    fn compute(&self, payload: &str) -> String {
+        match self {
+            CheckDigit::Luhn => {
+                let sum: u32 = payload
+                    .bytes()
+                    .rev()
+                    .map(|b| u32::from(b - b'0'))
+                    .enumerate()
+                    .map(|(i, d)| match i % 2 {
+                        0 if d * 2 > 9 => d * 2 - 9,
+                        0 => d * 2,
+                        _ => d,
+                    })
+                    .sum();
+                ((10 - sum % 10) % 10).to_string()
+            }
+            CheckDigit::Mod97 => format!("{:02}", 98 - mod97(payload) * 100 % 97),
+        }
+    }
+}
+
+impl FromStr for CheckDigit {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "luhn" => Ok(CheckDigit::Luhn),
+            "mod97" | "mod-97" => Ok(CheckDigit::Mod97),
+            other => Err(WalletError::InvalidNorekFormat(format!("unknown check digit scheme {other:?}"))),
+        }
+    }
+}
+
+/// remainder of a decimal digit string divided by 97, without overflow
+fn mod97(digits: &str) -> u32 {
+    digits
+        .bytes()
+        .fold(0, |rem, b| (rem * 10 + u32::from(b - b'0')) % 97)
+}
+
+/// Layout of account numbers (norek): `prefix`, a zero-padded serial and the
+/// check digits, `length` digits in total.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NorekFormat {
+    prefix: String,
+    length: usize,
+    check: CheckDigit,
+}
+
+impl NorekFormat {
+    /// size of the norek column
+    const MAX_LENGTH: usize = 32;
+
+    pub fn new(prefix: &str, length: usize, check: CheckDigit) -> Result<Self, WalletError> {
+        if !prefix.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(WalletError::InvalidNorekFormat(format!("prefix {prefix:?} is not numeric")));
+        }
+        if length > Self::MAX_LENGTH {
+            return Err(WalletError::InvalidNorekFormat(format!(
+                "length {length} is over the maximum of {}",
+                Self::MAX_LENGTH
+            )));
+        }
+        let format = Self { prefix: prefix.to_string(), length, check };
+        if !(1..=18).contains(&format.serial_width()) {
+            return Err(WalletError::InvalidNorekFormat(format!(
+                "length {length} leaves no room for a serial after prefix {prefix:?} and {} check digit(s)",
+                check.digits()
+            )));
+        }
+        Ok(format)
+    }
+
+    /// digits between the prefix and the check digits
+    fn serial_width(&self) -> usize {
+        self.length
+            .saturating_sub(self.prefix.len() + self.check.digits())
+    }
+
+    /// Account number for `serial`, e.g. the next value of a database sequence.
+    /// Distinct serials always give distinct numbers.
+    pub fn generate(&self, serial: u64) -> Result<String, WalletError> {
+        let width = self.serial_width();
+        if serial >= 10u64.pow(width as u32) {
+            return Err(WalletError::NorekExhausted(serial));
+        }
+        let payload = format!("{}{:0width$}", self.prefix, serial);
+        let check = self.check.compute(&payload);
+        Ok(payload + &check)
+    }
+
+    /// Checks a number typed in by a user or sent by another system and
+    /// returns it normalized; spaces and dashes between digits are ignored.
+    pub fn validate(&self, input: &str) -> Result<String, WalletError> {
+        let norek: String = input
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-'))
+            .collect();
+        if norek.is_empty() || !norek.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(WalletError::InvalidNorek(input.to_string(), "not a number"));
+        }
+        if norek.len() != self.length {
+            return Err(WalletError::InvalidNorek(input.to_string(), "wrong length"));
+        }
+        if !norek.starts_with(&self.prefix) {
+            return Err(WalletError::InvalidNorek(input.to_string(), "unknown prefix"));
+        }
+        let (payload, check) = norek.split_at(self.length - self.check.digits());
+        if self.check.compute(payload) != check {
+            return Err(WalletError::InvalidNorek(input.to_string(), "check digit mismatch"));
+        }
+        Ok(norek)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn luhn_matches_known_numbers() {
+        // classic Luhn example and a common test card number
+        assert_eq!(CheckDigit::Luhn.compute("7992739871"), "3");
+        assert_eq!(CheckDigit::Luhn.compute("411111111111111"), "1");
+    }
+
+    #[test]
+    fn mod97_matches_iban() {
+        // GB82 WEST 1234 5698 7654 32, letters converted and the country code moved to the end
+        let bban = "32142829123456987654321611";
+        assert_eq!(CheckDigit::Mod97.compute(bban), "82");
+    }
+
+    #[test]
+    fn generated_numbers_validate() {
+        for check in [CheckDigit::Luhn, CheckDigit::Mod97] {
+            let format = NorekFormat::new("88", 12, check).unwrap();
+            for serial in [0, 1, 42, 999_999] {
+                let norek = format.generate(serial).unwrap();
+                assert_eq!(norek.len(), 12);
+                assert!(norek.starts_with("88"));
+                assert_eq!(format.validate(&norek).unwrap(), norek);
+            }
+        }
+    }
+
+    #[test]
+    fn validate_normalizes_and_rejects() {
+        let format = NorekFormat::new("88", 12, CheckDigit::Luhn).unwrap();
+        let norek = format.generate(123_456).unwrap();
+        let spaced = format!("{} {}-{}", &norek[..4], &norek[4..8], &norek[8..]);
+        assert_eq!(format.validate(&spaced).unwrap(), norek);
+
+        let mut typo = norek.clone().into_bytes();
+        typo[5] = if typo[5] == b'9' { b'0' } else { typo[5] + 1 };
+        let typo = String::from_utf8(typo).unwrap();
+        assert!(matches!(format.validate(&typo), Err(WalletError::InvalidNorek(_, "check digit mismatch"))));
+        assert!(matches!(format.validate(&norek[1..]), Err(WalletError::InvalidNorek(_, "wrong length"))));
+        assert!(matches!(format.validate("77abc"), Err(WalletError::InvalidNorek(_, "not a number"))));
+    }
+
+    #[test]
+    fn serial_overflow_is_refused() {
+        let format = NorekFormat::new("88", 6, CheckDigit::Mod97).unwrap();
+        assert!(format.generate(99).is_ok());
+        assert!(matches!(format.generate(100), Err(WalletError::NorekExhausted(100))));
+        assert!(NorekFormat::new("88", 4, CheckDigit::Mod97).is_err());
+    }
+}
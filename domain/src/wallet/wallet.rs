@@ -1,62 +1,563 @@
 use crate::base::base::{AuditMetadata, Auditable};
+use crate::money::money::{Currency, Money};
+use crate::wallet::closure::{ClosureReceipt, SweepDestination};
 use crate::wallet::error::WalletError;
+use chrono::{DateTime, Utc};
 use postgres_types::{FromSql, ToSql};
 use serde::{Deserialize, Serialize};
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSql, FromSql)]
+/// Lifecycle of a wallet:
+///
+/// ```text
+/// Active         -> Frozen | Dormant | PendingClosure
+/// Frozen         -> Active
+/// Dormant        -> Active | Frozen | PendingClosure
+/// PendingClosure -> Active | Closed
+/// Inactive       -> PendingClosure
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSql, FromSql)]
 #[postgres(name = "wallet_status")]
 pub enum WalletStatus {
     #[postgres(name = "Active")]
     Active,
+    /// deleted before the closure flow existed; nothing but refunds gets in
+    /// or out until it is closed properly, which sweeps what it still holds
     #[postgres(name = "Inactive")]
     Inactive,
+    /// under review: money may come in but not go out
+    #[postgres(name = "Frozen")]
+    Frozen,
+    /// unused for a long time, debits need the wallet reactivated first
+    #[postgres(name = "Dormant")]
+    Dormant,
+    /// closure requested, only refunds of earlier debits are accepted
+    #[postgres(name = "PendingClosure")]
+    PendingClosure,
+    #[postgres(name = "Closed")]
+    Closed,
 }
+
+impl WalletStatus {
+    pub fn can_transition_to(&self, next: WalletStatus) -> bool {
+        use WalletStatus::*;
+        matches!(
+            (self, next),
+            (Active, Frozen | Dormant | PendingClosure)
+                | (Frozen, Active)
+                | (Dormant, Active | Frozen | PendingClosure)
+                | (PendingClosure, Active | Closed)
+                | (Inactive, PendingClosure)
+        )
+    }
+
+    /// no further transitions are possible
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, WalletStatus::Closed)
+    }
+
+    pub fn allows_debit(&self) -> bool {
+        matches!(self, WalletStatus::Active)
+    }
+
+    pub fn allows_credit(&self) -> bool {
+        matches!(self, WalletStatus::Active | WalletStatus::Frozen | WalletStatus::Dormant)
+    }
+
+    /// a refund gives back money the wallet already sent, so it is only
+    /// refused once the wallet is gone
+    pub fn allows_refund(&self) -> bool {
+        !self.is_terminal()
+    }
+
+    pub fn ensure_debit_allowed(&self) -> Result<(), WalletError> {
+        if !self.allows_debit() {
+            return Err(WalletError::DebitsBlocked(*self));
+        }
+        Ok(())
+    }
+
+    pub fn ensure_credit_allowed(&self) -> Result<(), WalletError> {
+        if !self.allows_credit() {
+            return Err(WalletError::CreditsBlocked(*self));
+        }
+        Ok(())
+    }
+
+    pub fn ensure_refund_allowed(&self) -> Result<(), WalletError> {
+        if !self.allows_refund() {
+            return Err(WalletError::CreditsBlocked(*self));
+        }
+        Ok(())
+    }
+}
+
+/// Why a wallet changed status, kept with every transition for the audit trail.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, ToSql, FromSql)]
+#[postgres(name = "wallet_status_reason")]
+pub enum StatusReason {
+    #[default]
+    #[postgres(name = "CustomerRequest")]
+    CustomerRequest,
+    #[postgres(name = "SuspectedFraud")]
+    SuspectedFraud,
+    #[postgres(name = "ComplianceReview")]
+    ComplianceReview,
+    #[postgres(name = "LegalOrder")]
+    LegalOrder,
+    #[postgres(name = "Inactivity")]
+    Inactivity,
+    /// the review or order behind an earlier freeze was lifted
+    #[postgres(name = "ReviewCleared")]
+    ReviewCleared,
+    #[postgres(name = "Other")]
+    Other,
+}
+
+/// One recorded status change of a wallet.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct WalletStatusTransition {
+    pub user_id: i32,
+    pub from: WalletStatus,
+    pub to: WalletStatus,
+    pub reason: StatusReason,
+    #[serde(default)]
+    pub note: Option<String>,
+    /// subject of whoever made the change
+    pub actor: String,
+    pub at: DateTime<Utc>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Wallet {
     pub id: Option<i32>,
     pub norek: String,
     pub user_id: i32,
-    pub balance: f64,
+    /// one sub-balance (pocket) per currency the wallet holds, i.e. the ledger balance
+    pub balances: BTreeMap<Currency, Money>,
+    /// part of each pocket reserved by active holds
+    #[serde(default)]
+    pub held: BTreeMap<Currency, Money>,
     pub status: WalletStatus,
     pub audit: AuditMetadata,
 }
 impl Wallet {
-    pub fn new(id: Option<i32>, norek: String, user_id: i32, inital_balance: f64, audit: AuditMetadata) -> Self {
+    pub fn new(id: Option<i32>, norek: String, user_id: i32, inital_balance: Money, audit: AuditMetadata) -> Self {
         Self {
             id,
             norek,
             user_id,
-            balance: inital_balance,
+            balances: BTreeMap::from([(inital_balance.currency(), inital_balance)]),
+            held: BTreeMap::new(),
             status: WalletStatus::Active,
             audit
         }
     }
 
-    pub fn credit (&mut self, amount: f64) -> Result<(), WalletError> {
-        if amount <= 0.0 {
+    /// balance of the given currency pocket, if the wallet holds that currency
+    pub fn balance(&self, currency: Currency) -> Option<Money> {
+        self.balances.get(&currency).copied()
+    }
+
+    /// amount of the pocket reserved by active holds
+    pub fn held_balance(&self, currency: Currency) -> Money {
+        self.held
+            .get(&currency)
+            .copied()
+            .unwrap_or(Money::zero(currency))
+    }
+
+    /// balance minus active holds, what can still be debited or reserved
+    pub fn available_balance(&self, currency: Currency) -> Option<Money> {
+        let balance = self.balance(currency)?;
+        balance.checked_sub(self.held_balance(currency)).ok()
+    }
+
+    pub fn currencies(&self) -> impl Iterator<Item = Currency> + '_ {
+        self.balances.keys().copied()
+    }
+
+    /// opens an empty pocket for the currency, no-op when it already exists
+    pub fn open_pocket(&mut self, currency: Currency) {
+        if let Entry::Vacant(pocket) = self.balances.entry(currency) {
+            pocket.insert(Money::zero(currency));
+            self.audit.touch();
+        }
+    }
+
+    pub fn credit (&mut self, amount: Money) -> Result<(), WalletError> {
+        if !amount.is_positive() {
             return Err(WalletError::InvalidAmount(amount));
         }
-        self.balance += amount;
+        self.status.ensure_credit_allowed()?;
+        let pocket = self.pocket_mut(amount.currency())?;
+        *pocket = pocket.checked_add(amount)?;
         self.audit.touch();
         Ok(())
     }
 
-    pub fn debit(&mut self, amount: f64) -> Result<(), WalletError> {
-        if amount <= 0.0 {
+    pub fn debit(&mut self, amount: Money) -> Result<(), WalletError> {
+        if !amount.is_positive() {
             return Err(WalletError::InvalidAmount(amount));
         }
+        self.status.ensure_debit_allowed()?;
+
+        self.ensure_available(amount)?;
+        let pocket = self.pocket_mut(amount.currency())?;
+        *pocket = pocket.checked_sub(amount)?;
+        self.audit.touch();
+        Ok(())
+    }
 
-        if self.balance < amount {
-            return Err(WalletError::InsufficientBalance(amount, self.balance));
+    /// Reserves `amount` of the available balance; the ledger balance is unchanged.
+    pub fn place_hold(&mut self, amount: Money) -> Result<(), WalletError> {
+        if !amount.is_positive() {
+            return Err(WalletError::InvalidAmount(amount));
         }
+        self.status.ensure_debit_allowed()?;
+        self.ensure_available(amount)?;
+        let held = self.held_balance(amount.currency()).checked_add(amount)?;
+        self.held.insert(amount.currency(), held);
+        self.audit.touch();
+        Ok(())
+    }
 
-        self.balance -= amount;
+    /// Gives a reserved amount back to the available balance.
+    pub fn release_hold(&mut self, amount: Money) -> Result<(), WalletError> {
+        if !amount.is_positive() {
+            return Err(WalletError::InvalidAmount(amount));
+        }
+        let held = self.held_balance(amount.currency()).checked_sub(amount)?;
+        if held.is_negative() {
+            return Err(WalletError::InvalidAmount(amount));
+        }
+        self.held.insert(amount.currency(), held);
+        self.audit.touch();
+        Ok(())
+    }
+
+    /// Ends a hold of `held` by debiting `captured` (at most `held`) and
+    /// releasing the rest. On error the wallet, and the hold, are unchanged.
+    pub fn capture_hold(&mut self, held: Money, captured: Money) -> Result<(), WalletError> {
+        if !captured.is_positive() {
+            return Err(WalletError::InvalidAmount(captured));
+        }
+        if captured.checked_sub(held)?.is_positive() {
+            return Err(WalletError::CaptureExceedsHold(captured, held));
+        }
+        // the debit may only use the released funds once they are available,
+        // so it runs on a copy that replaces the wallet when both steps pass
+        let mut captured_wallet = self.clone();
+        captured_wallet.release_hold(held)?;
+        captured_wallet.debit(captured)?;
+        *self = captured_wallet;
+        Ok(())
+    }
+
+    /// Moves the wallet to `next`, returning the transition to record.
+    pub fn transition(
+        &mut self,
+        next: WalletStatus,
+        reason: StatusReason,
+        note: Option<String>,
+        actor: &str,
+    ) -> Result<WalletStatusTransition, WalletError> {
+        if !self.status.can_transition_to(next) {
+            return Err(WalletError::IllegalStatusTransition(self.status, next));
+        }
+        let transition = WalletStatusTransition {
+            user_id: self.user_id,
+            from: self.status,
+            to: next,
+            reason,
+            note,
+            actor: actor.to_string(),
+            at: Utc::now(),
+        };
+        self.status = next;
         self.audit.touch();
+        Ok(transition)
+    }
+
+    /// Blocks debits and new holds; credits still land.
+    pub fn freeze(&mut self, reason: StatusReason, note: Option<String>, actor: &str) -> Result<WalletStatusTransition, WalletError> {
+        self.transition(WalletStatus::Frozen, reason, note, actor)
+    }
+
+    pub fn unfreeze(&mut self, reason: StatusReason, note: Option<String>, actor: &str) -> Result<WalletStatusTransition, WalletError> {
+        if self.status != WalletStatus::Frozen {
+            return Err(WalletError::IllegalStatusTransition(self.status, WalletStatus::Active));
+        }
+        self.transition(WalletStatus::Active, reason, note, actor)
+    }
+
+    /// Stops all debits and credits but refunds while the closure is checked.
+    pub fn begin_closure(&mut self, reason: StatusReason, note: Option<String>, actor: &str) -> Result<WalletStatusTransition, WalletError> {
+        self.transition(WalletStatus::PendingClosure, reason, note, actor)
+    }
+
+    /// Empties every pocket into `destination` and closes the wallet for good.
+    /// Refused while any funds are on hold.
+    pub fn close(
+        &mut self,
+        destination: SweepDestination,
+        reason: StatusReason,
+        note: Option<String>,
+        actor: &str,
+    ) -> Result<(WalletStatusTransition, ClosureReceipt), WalletError> {
+        if destination == SweepDestination::Wallet(self.user_id) {
+            return Err(WalletError::InvalidSweepTarget(self.user_id));
+        }
+        self.ensure_no_holds()?;
+        let transition = self.transition(WalletStatus::Closed, reason, note.clone(), actor)?;
+        let swept = self
+            .balances
+            .values_mut()
+            .filter(|balance| !balance.is_zero())
+            .map(|balance| std::mem::replace(balance, Money::zero(balance.currency())))
+            .collect();
+        let receipt = ClosureReceipt {
+            reference: format!("closure-{}", Uuid::new_v4()),
+            user_id: self.user_id,
+            destination,
+            swept,
+            reason,
+            note,
+            actor: actor.to_string(),
+            closed_at: transition.at,
+        };
+        Ok((transition, receipt))
+    }
+
+    pub fn ensure_no_holds(&self) -> Result<(), WalletError> {
+        if self.held.values().any(|held| !held.is_zero()) {
+            return Err(WalletError::HoldsOutstanding(self.user_id));
+        }
         Ok(())
     }
+
+    fn ensure_available(&self, amount: Money) -> Result<(), WalletError> {
+        let available = self
+            .available_balance(amount.currency())
+            .ok_or(WalletError::CurrencyNotHeld(amount.currency()))?;
+        if available.checked_sub(amount)?.is_negative() {
+            return Err(WalletError::InsufficientBalance(amount, available));
+        }
+        Ok(())
+    }
+
+    fn pocket_mut(&mut self, currency: Currency) -> Result<&mut Money, WalletError> {
+        self.balances
+            .get_mut(&currency)
+            .ok_or(WalletError::CurrencyNotHeld(currency))
+    }
 }
 
 impl Auditable for Wallet {
     fn audit(&self) -> &AuditMetadata { &self.audit }
     fn audit_mut(&mut self) -> &mut AuditMetadata { &mut self.audit }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idr(major: i64) -> Money {
+        Money::from_major(major, Currency::IDR).unwrap()
+    }
+
+    fn wallet(balance: i64) -> Wallet {
+        Wallet::new(Some(1), "880000000013".to_string(), 1, idr(balance), AuditMetadata::new())
+    }
+
+    #[test]
+    fn holds_reserve_the_available_balance() {
+        let mut wallet = wallet(100);
+        wallet.place_hold(idr(70)).unwrap();
+        assert_eq!(wallet.balance(Currency::IDR), Some(idr(100)));
+        assert_eq!(wallet.available_balance(Currency::IDR), Some(idr(30)));
+        assert!(matches!(wallet.place_hold(idr(31)), Err(WalletError::InsufficientBalance(_, _))));
+        assert!(matches!(wallet.debit(idr(31)), Err(WalletError::InsufficientBalance(_, _))));
+
+        wallet.release_hold(idr(70)).unwrap();
+        assert_eq!(wallet.available_balance(Currency::IDR), Some(idr(100)));
+        assert!(matches!(wallet.release_hold(idr(1)), Err(WalletError::InvalidAmount(_))));
+    }
+
+    #[test]
+    fn hold_amounts_must_be_positive() {
+        let mut wallet = wallet(100);
+        wallet.place_hold(idr(70)).unwrap();
+        for amount in [idr(0), idr(-10)] {
+            assert!(matches!(wallet.place_hold(amount), Err(WalletError::InvalidAmount(_))));
+            assert!(matches!(wallet.release_hold(amount), Err(WalletError::InvalidAmount(_))));
+        }
+        // a negative release would have raised the hold above the balance
+        assert_eq!(wallet.held_balance(Currency::IDR), idr(70));
+        assert_eq!(wallet.available_balance(Currency::IDR), Some(idr(30)));
+    }
+
+    #[test]
+    fn capture_debits_and_releases_the_rest() {
+        let mut wallet = wallet(100);
+        wallet.place_hold(idr(100)).unwrap();
+        wallet.capture_hold(idr(100), idr(60)).unwrap();
+        assert_eq!(wallet.balance(Currency::IDR), Some(idr(40)));
+        assert_eq!(wallet.held_balance(Currency::IDR), idr(0));
+    }
+
+    #[test]
+    fn failed_capture_keeps_the_hold() {
+        let mut wallet = wallet(100);
+        wallet.place_hold(idr(50)).unwrap();
+        wallet.status = WalletStatus::Frozen;
+        assert!(matches!(wallet.capture_hold(idr(50), idr(50)), Err(WalletError::DebitsBlocked(_))));
+        assert_eq!(wallet.held_balance(Currency::IDR), idr(50));
+        assert_eq!(wallet.balance(Currency::IDR), Some(idr(100)));
+
+        wallet.status = WalletStatus::Active;
+        assert!(matches!(wallet.capture_hold(idr(50), idr(51)), Err(WalletError::CaptureExceedsHold(_, _))));
+        assert_eq!(wallet.held_balance(Currency::IDR), idr(50));
+    }
+
+    const ALL: [WalletStatus; 6] = [
+        WalletStatus::Active,
+        WalletStatus::Inactive,
+        WalletStatus::Frozen,
+        WalletStatus::Dormant,
+        WalletStatus::PendingClosure,
+        WalletStatus::Closed,
+    ];
+
+    #[test]
+    fn status_transitions() {
+        use WalletStatus::*;
+        let allowed = [
+            (Active, Frozen),
+            (Active, Dormant),
+            (Active, PendingClosure),
+            (Frozen, Active),
+            (Dormant, Active),
+            (Dormant, Frozen),
+            (Dormant, PendingClosure),
+            (PendingClosure, Active),
+            (PendingClosure, Closed),
+            (Inactive, PendingClosure),
+        ];
+        for from in ALL {
+            for to in ALL {
+                assert_eq!(
+                    from.can_transition_to(to),
+                    allowed.contains(&(from, to)),
+                    "{:?} -> {:?}",
+                    from,
+                    to
+                );
+            }
+        }
+        // closed is terminal
+        assert!(Closed.is_terminal());
+        assert!(ALL.iter().all(|to| !Closed.can_transition_to(*to)));
+    }
+
+    #[test]
+    fn money_movement_per_status() {
+        use WalletStatus::*;
+        // (status, debit, credit, refund)
+        let table = [
+            (Active, true, true, true),
+            (Inactive, false, false, true),
+            (Frozen, false, true, true),
+            (Dormant, false, true, true),
+            (PendingClosure, false, false, true),
+            (Closed, false, false, false),
+        ];
+        for (status, debit, credit, refund) in table {
+            assert_eq!(status.allows_debit(), debit, "debit in {:?}", status);
+            assert_eq!(status.allows_credit(), credit, "credit in {:?}", status);
+            assert_eq!(status.allows_refund(), refund, "refund in {:?}", status);
+        }
+    }
+
+    #[test]
+    fn frozen_wallet_is_credit_only() {
+        let mut wallet = wallet(100);
+        wallet.freeze(StatusReason::default(), None, "ops").unwrap();
+        assert!(matches!(wallet.debit(idr(1)), Err(WalletError::DebitsBlocked(WalletStatus::Frozen))));
+        wallet.credit(idr(50)).unwrap();
+        assert_eq!(wallet.balance(Currency::IDR), Some(idr(150)));
+    }
+
+    fn pending_closure(balance: i64) -> Wallet {
+        let mut wallet = wallet(balance);
+        wallet.open_pocket(Currency::USD);
+        wallet.credit(Money::new(250, Currency::USD)).unwrap();
+        wallet.begin_closure(StatusReason::CustomerRequest, None, "1").unwrap();
+        wallet
+    }
+
+    #[test]
+    fn closure_is_refused_while_funds_are_on_hold() {
+        let mut wallet = wallet(100);
+        wallet.place_hold(idr(30)).unwrap();
+        wallet.begin_closure(StatusReason::CustomerRequest, None, "1").unwrap();
+
+        let result = wallet.close(SweepDestination::Suspense, StatusReason::CustomerRequest, None, "1");
+
+        assert!(matches!(result, Err(WalletError::HoldsOutstanding(1))));
+        assert_eq!(wallet.status, WalletStatus::PendingClosure);
+        assert_eq!(wallet.balance(Currency::IDR), Some(idr(100)));
+    }
+
+    #[test]
+    fn closure_sweeps_every_pocket_to_suspense() {
+        let mut wallet = pending_closure(100);
+
+        let (transition, receipt) = wallet
+            .close(SweepDestination::Suspense, StatusReason::CustomerRequest, Some("moving abroad".to_string()), "1")
+            .unwrap();
+
+        assert_eq!(receipt.destination, SweepDestination::Suspense);
+        assert_eq!(receipt.swept, vec![idr(100), Money::new(250, Currency::USD)]);
+        assert_eq!(receipt.closed_at, transition.at);
+        assert_eq!(transition.from, WalletStatus::PendingClosure);
+        assert_eq!(transition.to, WalletStatus::Closed);
+        assert_eq!(wallet.balance(Currency::IDR), Some(idr(0)));
+        assert_eq!(wallet.balance(Currency::USD), Some(Money::zero(Currency::USD)));
+    }
+
+    #[test]
+    fn empty_pockets_are_not_swept() {
+        let mut wallet = pending_closure(0);
+        let (_, receipt) = wallet
+            .close(SweepDestination::Wallet(2), StatusReason::CustomerRequest, None, "1")
+            .unwrap();
+        assert_eq!(receipt.swept, vec![Money::new(250, Currency::USD)]);
+    }
+
+    #[test]
+    fn closed_wallet_is_terminal() {
+        let mut wallet = pending_closure(100);
+        wallet.close(SweepDestination::Suspense, StatusReason::CustomerRequest, None, "1").unwrap();
+
+        assert!(wallet.status.is_terminal());
+        assert!(matches!(wallet.credit(idr(1)), Err(WalletError::CreditsBlocked(WalletStatus::Closed))));
+        assert!(wallet.status.ensure_refund_allowed().is_err());
+        assert!(matches!(
+            wallet.close(SweepDestination::Suspense, StatusReason::CustomerRequest, None, "1"),
+            Err(WalletError::IllegalStatusTransition(WalletStatus::Closed, WalletStatus::Closed))
+        ));
+        assert!(wallet.begin_closure(StatusReason::CustomerRequest, None, "1").is_err());
+    }
+
+    #[test]
+    fn wallet_cannot_be_swept_into_itself() {
+        let mut wallet = pending_closure(100);
+        assert!(matches!(
+            wallet.close(SweepDestination::Wallet(1), StatusReason::CustomerRequest, None, "1"),
+            Err(WalletError::InvalidSweepTarget(1))
+        ));
+        assert_eq!(wallet.status, WalletStatus::PendingClosure);
+    }
+}
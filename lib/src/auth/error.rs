@@ -0,0 +1,79 @@
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use http::{header, StatusCode};
+use thiserror::Error;
+
+use crate::trace::trace_id::current_trace_id;
+
+#[derive(Debug, Error)]
+pub enum AuthError {
+    #[error("Missing bearer token")]
+    MissingToken,
+
+    #[error("Invalid token: {0}")]
+    InvalidToken(String),
+
+    #[error("Token has expired")]
+    TokenExpired,
+
+    /// authenticated, but not allowed to act on the resource
+    #[error("{0}")]
+    Forbidden(String),
+
+    #[error("Invalid auth configuration: {0}")]
+    InvalidConfig(String),
+}
+
+impl AuthError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AuthError::MissingToken | AuthError::InvalidToken(_) | AuthError::TokenExpired => {
+                StatusCode::UNAUTHORIZED
+            }
+            AuthError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AuthError::InvalidConfig(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AuthError::MissingToken | AuthError::InvalidToken(_) => "UNAUTHORIZED",
+            AuthError::TokenExpired => "TOKEN_EXPIRED",
+            AuthError::Forbidden(_) => "FORBIDDEN",
+            AuthError::InvalidConfig(_) => "INTERNAL_ERROR",
+        }
+    }
+
+    /// message safe to show to clients; why a token was rejected is only logged
+    pub fn public_message(&self) -> String {
+        match self {
+            AuthError::InvalidToken(_) => "Invalid token".to_string(),
+            AuthError::InvalidConfig(_) => "Internal server error".to_string(),
+            other => other.to_string(),
+        }
+    }
+}
+
+/// Rejection of the [`AuthUser`](crate::auth::extractor::AuthUser) extractor,
+/// shaped like a `BaseResponse` error.
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        if status.is_server_error() {
+            tracing::error!("{}: {}", self.code(), self);
+        } else {
+            tracing::warn!("{}: {}", self.code(), self);
+        }
+        let body = serde_json::json!({
+            "trace_id": current_trace_id(),
+            "message": self.public_message(),
+            "error_code": self.code(),
+            "data": null,
+        });
+        if status == StatusCode::UNAUTHORIZED {
+            (status, [(header::WWW_AUTHENTICATE, "Bearer")], Json(body)).into_response()
+        } else {
+            (status, Json(body)).into_response()
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use axum::extract::FromRequestParts;
+use http::header::AUTHORIZATION;
+use http::request::Parts;
+use http::HeaderMap;
+
+use crate::auth::error::AuthError;
+use crate::auth::jwt::{jwt_auth, Claims};
+
+/// scope that grants admin rights on every resource
+pub const ADMIN_SCOPE: &str = "admin";
+
+/// Caller of the request, from a valid `Authorization: Bearer` token.
+///
+/// Rejects the request with 401 when the token is missing or invalid, so a
+/// handler taking an `AuthUser` only runs for authenticated callers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthUser {
+    pub subject: String,
+    pub scopes: Vec<String>,
+}
+
+impl AuthUser {
+    pub fn from_claims(claims: &Claims) -> Self {
+        Self {
+            subject: claims.sub.clone(),
+            scopes: claims.scopes().map(str::to_string).collect(),
+        }
+    }
+
+    /// the user id of user tokens, `None` for service tokens
+    pub fn user_id(&self) -> Option<i32> {
+        self.subject.parse().ok()
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+
+    /// `admin`, or `{resource}:admin` for admins of one resource
+    pub fn is_admin(&self, resource: &str) -> bool {
+        self.has_scope(ADMIN_SCOPE) || self.has_scope(&format!("{}:{}", resource, ADMIN_SCOPE))
+    }
+
+    /// Lets the caller act on `user_id`'s `resource` when it is theirs, or
+    /// when they administer the resource.
+    pub fn ensure_owner(&self, resource: &str, user_id: i32) -> Result<(), AuthError> {
+        if self.user_id() == Some(user_id) || self.is_admin(resource) {
+            Ok(())
+        } else {
+            Err(AuthError::Forbidden(format!("Not allowed to access the {} of user {}", resource, user_id)))
+        }
+    }
+
+    pub fn ensure_admin(&self, resource: &str) -> Result<(), AuthError> {
+        if self.is_admin(resource) {
+            Ok(())
+        } else {
+            Err(AuthError::Forbidden(format!("Requires the {}:{} scope", resource, ADMIN_SCOPE)))
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for AuthUser {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let auth = jwt_auth()
+            .ok_or_else(|| AuthError::InvalidConfig("auth is not initialized".to_string()))?;
+        let token = bearer_token(&parts.headers).ok_or(AuthError::MissingToken)?;
+        let claims = auth.validate(token)?;
+        Ok(Self::from_claims(&claims))
+    }
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    let token = token.trim();
+    (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then_some(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn caller(subject: &str, scopes: &[&str]) -> AuthUser {
+        AuthUser {
+            subject: subject.to_string(),
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn owners_and_admins_are_allowed() {
+        assert!(caller("7", &[]).ensure_owner("wallet", 7).is_ok());
+        assert!(caller("7", &[]).ensure_owner("wallet", 8).is_err());
+        assert!(caller("transfer_service", &["wallet:admin"]).ensure_owner("wallet", 8).is_ok());
+        assert!(caller("ops", &["admin"]).ensure_admin("wallet").is_ok());
+        assert!(caller("ops", &["user:admin"]).ensure_owner("wallet", 8).is_err());
+    }
+
+    #[test]
+    fn reads_bearer_tokens_only() {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, "Bearer abc".parse().unwrap());
+        assert_eq!(bearer_token(&headers), Some("abc"));
+        headers.insert(AUTHORIZATION, "Basic abc".parse().unwrap());
+        assert_eq!(bearer_token(&headers), None);
+    }
+}
@@ -0,0 +1,488 @@
+use chrono::Utc;
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::jwk::{AlgorithmParameters, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::auth::error::AuthError;
+
+static AUTH: OnceCell<JwtAuth> = OnceCell::new();
+
+/// the JWKS file is not re-read more often than this when an unknown key id
+/// shows up, so garbage tokens cannot make every request hit the disk
+const MIN_JWKS_RELOAD: Duration = Duration::from_secs(5);
+
+/// Claims of the access tokens issued and accepted by the services.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Claims {
+    /// user id for users, service name for service tokens
+    pub sub: String,
+    /// space separated, e.g. `wallet:admin`
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub scope: String,
+    pub iat: i64,
+    pub exp: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+}
+
+impl Claims {
+    pub fn scopes(&self) -> impl Iterator<Item = &str> {
+        self.scope.split_whitespace()
+    }
+}
+
+/// JWT settings of a service.
+#[derive(Debug, Clone)]
+pub struct JwtConfig {
+    /// algorithm of the tokens this service issues, HS256 or RS256
+    pub algorithm: Algorithm,
+    /// shared HS256 secret; tokens without a key id are checked against it
+    pub secret: Option<String>,
+    /// PEM private key signing RS256 tokens
+    pub private_key_file: Option<String>,
+    /// `kid` of the tokens this service issues, required for RS256
+    pub key_id: Option<String>,
+    /// keys accepted by key id; rotated by editing the file
+    pub jwks_file: Option<String>,
+    /// how often the JWKS file is re-read
+    pub jwks_refresh: Duration,
+    pub issuer: Option<String>,
+    pub audience: Option<String>,
+    /// lifetime of issued tokens
+    pub ttl: Duration,
+    /// subject of the token this service sends on outgoing calls
+    pub service_name: Option<String>,
+    /// scopes of that token; without them no token is sent
+    pub service_scopes: Option<String>,
+}
+
+impl JwtConfig {
+    pub fn from_env() -> Result<Self, AuthError> {
+        dotenvy::dotenv().ok();
+        let var = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+        let seconds = |key: &str, default: u64| {
+            Duration::from_secs(var(key).and_then(|v| v.parse().ok()).unwrap_or(default))
+        };
+        let algorithm = match var("JWT_ALGORITHM").as_deref() {
+            None | Some("HS256") => Algorithm::HS256,
+            Some("RS256") => Algorithm::RS256,
+            Some(other) => {
+                return Err(AuthError::InvalidConfig(format!("unsupported JWT_ALGORITHM {}", other)));
+            }
+        };
+        Ok(Self {
+            algorithm,
+            secret: var("JWT_SECRET"),
+            private_key_file: var("JWT_PRIVATE_KEY_FILE"),
+            key_id: var("JWT_KEY_ID"),
+            jwks_file: var("JWT_JWKS_FILE"),
+            jwks_refresh: seconds("JWT_JWKS_REFRESH_SECONDS", 60),
+            issuer: var("JWT_ISSUER"),
+            audience: var("JWT_AUDIENCE"),
+            ttl: seconds("JWT_TTL_SECONDS", 900),
+            service_name: var("SERVICE_NAME").map(|name| name.to_lowercase()),
+            service_scopes: var("JWT_SERVICE_SCOPES"),
+        })
+    }
+}
+
+/// A key tokens are verified with, and the only algorithm it is used for.
+#[derive(Clone)]
+struct VerifyingKey {
+    algorithm: Algorithm,
+    key: DecodingKey,
+}
+
+struct KeySet {
+    keys: HashMap<String, VerifyingKey>,
+    loaded_at: Instant,
+}
+
+/// Issues and validates the access tokens of the services.
+///
+/// Tokens carrying a `kid` are checked against the JWKS file, which is
+/// re-read every `jwks_refresh` and when an unknown key id shows up, so keys
+/// are rotated by adding the new key to the file, switching the issuers over
+/// and dropping the old key once its tokens have expired.
+pub struct JwtAuth {
+    config: JwtConfig,
+    signing: Option<EncodingKey>,
+    secret: Option<VerifyingKey>,
+    jwks: RwLock<KeySet>,
+    /// token sent on outgoing calls, with its expiry
+    service_token: Mutex<Option<(String, i64)>>,
+}
+
+impl JwtAuth {
+    pub fn new(config: JwtConfig) -> Result<Self, AuthError> {
+        let signing = match config.algorithm {
+            Algorithm::HS256 => config
+                .secret
+                .as_ref()
+                .map(|secret| EncodingKey::from_secret(secret.as_bytes())),
+            Algorithm::RS256 => match config.private_key_file {
+                Some(ref path) => {
+                    if config.key_id.is_none() {
+                        return Err(AuthError::InvalidConfig("RS256 needs JWT_KEY_ID".to_string()));
+                    }
+                    let pem = std::fs::read(path)
+                        .map_err(|e| AuthError::InvalidConfig(format!("{}: {}", path, e)))?;
+                    Some(
+                        EncodingKey::from_rsa_pem(&pem)
+                            .map_err(|e| AuthError::InvalidConfig(format!("{}: {}", path, e)))?,
+                    )
+                }
+                None => None,
+            },
+            other => {
+                return Err(AuthError::InvalidConfig(format!("unsupported algorithm {:?}", other)));
+            }
+        };
+        let secret = config.secret.as_ref().map(|secret| VerifyingKey {
+            algorithm: Algorithm::HS256,
+            key: DecodingKey::from_secret(secret.as_bytes()),
+        });
+        let keys = match config.jwks_file {
+            Some(ref path) => load_jwks(path)?,
+            None => HashMap::new(),
+        };
+        if secret.is_none() && keys.is_empty() {
+            return Err(AuthError::InvalidConfig(
+                "no verification key, set JWT_SECRET or JWT_JWKS_FILE".to_string(),
+            ));
+        }
+        Ok(Self {
+            config,
+            signing,
+            secret,
+            jwks: RwLock::new(KeySet {
+                keys,
+                loaded_at: Instant::now(),
+            }),
+            service_token: Mutex::new(None),
+        })
+    }
+
+    pub fn from_env() -> Result<Self, AuthError> {
+        Self::new(JwtConfig::from_env()?)
+    }
+
+    pub fn config(&self) -> &JwtConfig {
+        &self.config
+    }
+
+    /// Token for `subject` with `scopes`, valid for the configured ttl.
+    pub fn issue(&self, subject: &str, scopes: &[&str]) -> Result<String, AuthError> {
+        self.issue_claims(subject, scopes).map(|(token, _)| token)
+    }
+
+    fn issue_claims(&self, subject: &str, scopes: &[&str]) -> Result<(String, i64), AuthError> {
+        let signing = self.signing.as_ref().ok_or_else(|| {
+            AuthError::InvalidConfig("no signing key configured".to_string())
+        })?;
+        let now = Utc::now().timestamp();
+        let claims = Claims {
+            sub: subject.to_string(),
+            scope: scopes.join(" "),
+            iat: now,
+            exp: now + self.config.ttl.as_secs() as i64,
+            iss: self.config.issuer.clone(),
+            aud: self.config.audience.clone(),
+        };
+        let mut header = Header::new(self.config.algorithm);
+        header.kid = self.config.key_id.clone();
+        let token = jsonwebtoken::encode(&header, &claims, signing)
+            .map_err(|e| AuthError::InvalidConfig(e.to_string()))?;
+        Ok((token, claims.exp))
+    }
+
+    /// Checks the signature, expiry, issuer and audience of `token`.
+    pub fn validate(&self, token: &str) -> Result<Claims, AuthError> {
+        let header = jsonwebtoken::decode_header(token)
+            .map_err(|e| AuthError::InvalidToken(e.to_string()))?;
+        let key = self.key_for(&header)?;
+        // a key is only ever used with its own algorithm, so an RS256 public
+        // key cannot be passed off as an HS256 secret
+        if key.algorithm != header.alg {
+            return Err(AuthError::InvalidToken(format!(
+                "algorithm {:?} does not match the key",
+                header.alg
+            )));
+        }
+
+        let mut validation = Validation::new(key.algorithm);
+        validation.set_required_spec_claims(&["exp", "sub"]);
+        if let Some(ref issuer) = self.config.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        match self.config.audience {
+            Some(ref audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+        jsonwebtoken::decode::<Claims>(token, &key.key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| match e.kind() {
+                ErrorKind::ExpiredSignature => AuthError::TokenExpired,
+                _ => AuthError::InvalidToken(e.to_string()),
+            })
+    }
+
+    fn key_for(&self, header: &Header) -> Result<VerifyingKey, AuthError> {
+        let Some(ref kid) = header.kid else {
+            return self
+                .secret
+                .clone()
+                .ok_or_else(|| AuthError::InvalidToken("missing key id".to_string()));
+        };
+        if let Some(key) = self.jwks_key(kid, false) {
+            return Ok(key);
+        }
+        if let Some(key) = self.jwks_key(kid, true) {
+            return Ok(key);
+        }
+        // HS256 tokens of this service carry its key id without being in the JWKS
+        match self.secret {
+            Some(ref secret) if self.config.key_id.as_ref() == Some(kid) => Ok(secret.clone()),
+            _ => Err(AuthError::InvalidToken(format!("unknown key id {}", kid))),
+        }
+    }
+
+    /// Key `kid` of the JWKS, re-reading the file when it is due, or when
+    /// `unknown` and it was not read just now.
+    fn jwks_key(&self, kid: &str, unknown: bool) -> Option<VerifyingKey> {
+        let path = self.config.jwks_file.as_ref()?;
+        let due = {
+            let set = self.jwks.read().unwrap_or_else(|e| e.into_inner());
+            let age = set.loaded_at.elapsed();
+            if unknown { age >= MIN_JWKS_RELOAD } else { age >= self.config.jwks_refresh }
+        };
+        if due {
+            let mut set = self.jwks.write().unwrap_or_else(|e| e.into_inner());
+            match load_jwks(path) {
+                Ok(keys) => set.keys = keys,
+                // keep the keys we have rather than rejecting every token
+                Err(e) => warn!("failed to reload JWKS: {}", e),
+            }
+            set.loaded_at = Instant::now();
+        }
+        let set = self.jwks.read().unwrap_or_else(|e| e.into_inner());
+        set.keys.get(kid).cloned()
+    }
+
+    /// Token this service sends on outgoing calls, renewed once past half its
+    /// lifetime; `None` when no service scopes or signing key are configured.
+    pub fn service_token(&self) -> Option<String> {
+        let scopes = self.config.service_scopes.as_ref()?;
+        self.signing.as_ref()?;
+        let now = Utc::now().timestamp();
+        let half_life = self.config.ttl.as_secs() as i64 / 2;
+        let mut cached = self.service_token.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((ref token, exp)) = *cached
+            && exp - now > half_life
+        {
+            return Some(token.clone());
+        }
+        let subject = self.config.service_name.as_deref().unwrap_or("service");
+        let scopes: Vec<&str> = scopes.split_whitespace().collect();
+        match self.issue_claims(subject, &scopes) {
+            Ok((token, exp)) => {
+                *cached = Some((token.clone(), exp));
+                Some(token)
+            }
+            Err(e) => {
+                warn!("failed to issue service token: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// Verification keys of a JWKS file by key id; RSA keys verify RS256 and
+/// symmetric (`oct`) keys HS256, keys without a `kid` are ignored.
+fn load_jwks(path: &str) -> Result<HashMap<String, VerifyingKey>, AuthError> {
+    let invalid = |e: String| AuthError::InvalidConfig(format!("{}: {}", path, e));
+    let content = std::fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+    let set: JwkSet = serde_json::from_str(&content).map_err(|e| invalid(e.to_string()))?;
+
+    let mut keys = HashMap::new();
+    for jwk in &set.keys {
+        let Some(ref kid) = jwk.common.key_id else {
+            warn!("ignoring JWKS key without kid in {}", path);
+            continue;
+        };
+        let algorithm = match jwk.algorithm {
+            AlgorithmParameters::RSA(_) => Algorithm::RS256,
+            AlgorithmParameters::OctetKey(_) => Algorithm::HS256,
+            _ => {
+                warn!("ignoring JWKS key {} of unsupported type in {}", kid, path);
+                continue;
+            }
+        };
+        let key = DecodingKey::from_jwk(jwk).map_err(|e| invalid(format!("{}: {}", kid, e)))?;
+        keys.insert(kid.clone(), VerifyingKey { algorithm, key });
+    }
+    Ok(keys)
+}
+
+/// Loads the JWT configuration; a service that authenticates requests or
+/// signs its outgoing calls stops at startup when it is invalid.
+pub fn init_auth() {
+    let auth = AUTH.get_or_init(|| {
+        JwtAuth::from_env().expect("Failed to load JWT configuration")
+    });
+    info!(
+        "✅ JWT auth configured ({:?}, signing: {}, service token: {})",
+        auth.config.algorithm,
+        auth.signing.is_some(),
+        auth.config.service_scopes.is_some()
+    );
+}
+
+/// The auth of this service, `None` until [`init_auth`] ran.
+pub fn jwt_auth() -> Option<&'static JwtAuth> {
+    AUTH.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RSA_PRIVATE_KEY: &str = include_str!("testdata/rsa_private.pem");
+
+    fn config() -> JwtConfig {
+        JwtConfig {
+            algorithm: Algorithm::HS256,
+            secret: Some("test-secret".to_string()),
+            private_key_file: None,
+            key_id: None,
+            jwks_file: None,
+            jwks_refresh: Duration::from_secs(60),
+            issuer: Some("simplewallet".to_string()),
+            audience: None,
+            ttl: Duration::from_secs(60),
+            service_name: Some("transfer_service".to_string()),
+            service_scopes: Some("wallet:admin".to_string()),
+        }
+    }
+
+    fn write_temp(name: &str, content: &str) -> String {
+        let path = std::env::temp_dir().join(format!("{}-{}", uuid::Uuid::new_v4(), name));
+        std::fs::write(&path, content).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn hs256_tokens_round_trip() {
+        let auth = JwtAuth::new(config()).unwrap();
+        let claims = auth.validate(&auth.issue("42", &["wallet:read"]).unwrap()).unwrap();
+        assert_eq!(claims.sub, "42");
+        assert_eq!(claims.scopes().collect::<Vec<_>>(), vec!["wallet:read"]);
+    }
+
+    #[test]
+    fn rejects_other_secrets_issuers_and_expired_tokens() {
+        let auth = JwtAuth::new(config()).unwrap();
+        let other = JwtAuth::new(JwtConfig {
+            secret: Some("other".to_string()),
+            ..config()
+        })
+        .unwrap();
+        assert!(matches!(auth.validate(&other.issue("42", &[]).unwrap()), Err(AuthError::InvalidToken(_))));
+
+        let foreign = JwtAuth::new(JwtConfig {
+            issuer: Some("someone-else".to_string()),
+            ..config()
+        })
+        .unwrap();
+        assert!(matches!(auth.validate(&foreign.issue("42", &[]).unwrap()), Err(AuthError::InvalidToken(_))));
+
+        let now = Utc::now().timestamp();
+        let expired = Claims {
+            sub: "42".to_string(),
+            scope: String::new(),
+            iat: now - 600,
+            exp: now - 300,
+            iss: Some("simplewallet".to_string()),
+            aud: None,
+        };
+        let token = jsonwebtoken::encode(
+            &Header::new(Algorithm::HS256),
+            &expired,
+            &EncodingKey::from_secret(b"test-secret"),
+        )
+        .unwrap();
+        assert!(matches!(auth.validate(&token), Err(AuthError::TokenExpired)));
+    }
+
+    #[test]
+    fn rs256_tokens_are_checked_against_the_jwks() {
+        let key_file = write_temp("rsa.pem", RSA_PRIVATE_KEY);
+        let jwks_file = write_temp("jwks.json", include_str!("testdata/jwks.json"));
+        let issuer = JwtAuth::new(JwtConfig {
+            algorithm: Algorithm::RS256,
+            secret: None,
+            private_key_file: Some(key_file),
+            key_id: Some("key-2026-10".to_string()),
+            jwks_file: Some(jwks_file.clone()),
+            ..config()
+        })
+        .unwrap();
+        let token = issuer.issue("42", &[]).unwrap();
+
+        // a service holding only the public keys
+        let verifier = JwtAuth::new(JwtConfig {
+            secret: None,
+            jwks_file: Some(jwks_file.clone()),
+            ..config()
+        })
+        .unwrap();
+        assert_eq!(verifier.validate(&token).unwrap().sub, "42");
+
+        // the key is rotated out of the JWKS and the refresh is due
+        std::fs::write(&jwks_file, r#"{"keys": []}"#).unwrap();
+        verifier.jwks.write().unwrap().loaded_at = Instant::now() - Duration::from_secs(60);
+        assert!(matches!(verifier.validate(&token), Err(AuthError::InvalidToken(_))));
+    }
+
+    #[test]
+    fn tokens_cannot_switch_algorithm() {
+        // an HS256 token claiming the RSA key id, signed with its public key bytes
+        let jwks_file = write_temp("jwks.json", include_str!("testdata/jwks.json"));
+        let verifier = JwtAuth::new(JwtConfig {
+            jwks_file: Some(jwks_file),
+            ..config()
+        })
+        .unwrap();
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some("key-2026-10".to_string());
+        let claims = Claims {
+            sub: "1".to_string(),
+            scope: "admin".to_string(),
+            iat: Utc::now().timestamp(),
+            exp: Utc::now().timestamp() + 60,
+            iss: Some("simplewallet".to_string()),
+            aud: None,
+        };
+        let token =
+            jsonwebtoken::encode(&header, &claims, &EncodingKey::from_secret(b"anything")).unwrap();
+        assert!(matches!(verifier.validate(&token), Err(AuthError::InvalidToken(_))));
+    }
+
+    #[test]
+    fn service_tokens_are_cached() {
+        let auth = JwtAuth::new(config()).unwrap();
+        let token = auth.service_token().unwrap();
+        assert_eq!(auth.service_token().unwrap(), token);
+        let claims = auth.validate(&token).unwrap();
+        assert_eq!(claims.sub, "transfer_service");
+        assert_eq!(claims.scope, "wallet:admin");
+    }
+}
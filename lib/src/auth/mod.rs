@@ -0,0 +1,3 @@
+pub mod error;
+pub mod extractor;
+pub mod jwt;
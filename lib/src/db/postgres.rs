@@ -12,6 +12,7 @@ pub fn init_pool() -> &'static Pool {
         cfg.host = std::env::var("DB_HOST").ok();
         cfg.port = std::env::var("DB_PORT").ok().and_then(|p| p.parse().ok());
         cfg.dbname = std::env::var("DB_NAME").ok();
+        cfg.user = std::env::var("DB_USER").ok();
         cfg.password = std::env::var("DB_PASSWORD").ok();
 
         if let Ok(max_pool) = std::env::var("DB_MAX_POOL").and_then(|v| {
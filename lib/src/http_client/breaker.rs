@@ -0,0 +1,276 @@
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::http_client::error::HttpClientError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BreakerConfig {
+    /// consecutive failures that open the circuit
+    pub failure_threshold: u32,
+    /// how long an open circuit fails fast before letting a probe through
+    pub open_duration: Duration,
+}
+
+impl Default for BreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_duration: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    /// calls go through
+    Closed,
+    /// calls fail fast with [`HttpClientError::CircuitOpen`]
+    Open,
+    /// a single probe call is let through to test the service
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: BreakerState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    probe_in_flight: bool,
+}
+
+/// Circuit breaker of one downstream service.
+///
+/// Transport errors and 5xx responses count as failures; after
+/// `failure_threshold` of them in a row the circuit opens. Once
+/// `open_duration` has passed one probe is allowed: its success closes the
+/// circuit, its failure opens it again.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    service: String,
+    config: BreakerConfig,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(service: &str, config: BreakerConfig) -> Self {
+        Self {
+            service: service.to_string(),
+            config,
+            inner: Mutex::new(Inner {
+                state: BreakerState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+                probe_in_flight: false,
+            }),
+        }
+    }
+
+    pub fn state(&self) -> BreakerState {
+        let mut inner = self.lock();
+        self.refresh(&mut inner);
+        inner.state
+    }
+
+    /// Admits a call, or fails fast while the circuit is open. The outcome of
+    /// the call is reported through the returned permit.
+    pub fn try_acquire(&self) -> Result<BreakerPermit<'_>, HttpClientError> {
+        let mut inner = self.lock();
+        self.refresh(&mut inner);
+        match inner.state {
+            BreakerState::Closed => Ok(BreakerPermit::new(self, false)),
+            BreakerState::HalfOpen if !inner.probe_in_flight => {
+                inner.probe_in_flight = true;
+                Ok(BreakerPermit::new(self, true))
+            }
+            _ => Err(HttpClientError::CircuitOpen(self.service.clone())),
+        }
+    }
+
+    pub fn record_success(&self) {
+        let mut inner = self.lock();
+        if inner.state != BreakerState::Closed {
+            tracing::info!("circuit for {} closed", self.service);
+        }
+        inner.state = BreakerState::Closed;
+        inner.consecutive_failures = 0;
+        inner.opened_at = None;
+        inner.probe_in_flight = false;
+    }
+
+    pub fn record_failure(&self) {
+        let mut inner = self.lock();
+        inner.consecutive_failures += 1;
+        let trips = inner.state == BreakerState::HalfOpen
+            || inner.consecutive_failures >= self.config.failure_threshold;
+        if trips && inner.state != BreakerState::Open {
+            tracing::warn!(
+                "circuit for {} opened after {} failures",
+                self.service,
+                inner.consecutive_failures
+            );
+            inner.state = BreakerState::Open;
+            inner.opened_at = Some(Instant::now());
+        }
+        inner.probe_in_flight = false;
+    }
+
+    /// a probe that ended without an outcome lets the next call probe instead
+    fn release_probe(&self) {
+        let mut inner = self.lock();
+        if inner.state == BreakerState::HalfOpen {
+            inner.probe_in_flight = false;
+        }
+    }
+
+    /// an open circuit turns half-open once `open_duration` has passed
+    fn refresh(&self, inner: &mut Inner) {
+        if inner.state == BreakerState::Open
+            && inner
+                .opened_at
+                .is_some_and(|at| at.elapsed() >= self.config.open_duration)
+        {
+            inner.state = BreakerState::HalfOpen;
+            inner.probe_in_flight = false;
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Admission of one call by a [`CircuitBreaker`].
+///
+/// Dropping the permit without recording an outcome, e.g. when the request
+/// future is cancelled, frees the half-open probe slot so the circuit does
+/// not stay half-open with a probe that never finishes.
+#[must_use = "record the outcome of the call on the permit"]
+#[derive(Debug)]
+pub struct BreakerPermit<'a> {
+    breaker: &'a CircuitBreaker,
+    probe: bool,
+    settled: bool,
+}
+
+impl<'a> BreakerPermit<'a> {
+    fn new(breaker: &'a CircuitBreaker, probe: bool) -> Self {
+        Self {
+            breaker,
+            probe,
+            settled: false,
+        }
+    }
+
+    pub fn record_success(mut self) {
+        self.settled = true;
+        self.breaker.record_success();
+    }
+
+    pub fn record_failure(mut self) {
+        self.settled = true;
+        self.breaker.record_failure();
+    }
+}
+
+impl Drop for BreakerPermit<'_> {
+    fn drop(&mut self) {
+        if self.probe && !self.settled {
+            self.breaker.release_probe();
+        }
+    }
+}
+
+static BREAKERS: Lazy<Mutex<HashMap<String, Arc<CircuitBreaker>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Breaker of `service`, created with `config` on first use.
+pub fn breaker_for(service: &str, config: BreakerConfig) -> Arc<CircuitBreaker> {
+    let mut breakers = BREAKERS.lock().unwrap_or_else(|e| e.into_inner());
+    breakers
+        .entry(service.to_lowercase())
+        .or_insert_with(|| Arc::new(CircuitBreaker::new(service, config)))
+        .clone()
+}
+
+/// Current state of every breaker created so far, keyed by service.
+pub fn breaker_states() -> HashMap<String, BreakerState> {
+    let breakers = BREAKERS.lock().unwrap_or_else(|e| e.into_inner());
+    breakers
+        .iter()
+        .map(|(service, breaker)| (service.clone(), breaker.state()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(open_duration: Duration) -> CircuitBreaker {
+        CircuitBreaker::new(
+            "test",
+            BreakerConfig {
+                failure_threshold: 2,
+                open_duration,
+            },
+        )
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let breaker = breaker(Duration::from_secs(60));
+        breaker.record_failure();
+        breaker.record_success();
+        breaker.record_failure();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        breaker.record_failure();
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert!(matches!(
+            breaker.try_acquire(),
+            Err(HttpClientError::CircuitOpen(_))
+        ));
+    }
+
+    #[test]
+    fn half_open_lets_one_probe_through() {
+        let breaker = breaker(Duration::ZERO);
+        breaker.record_failure();
+        breaker.record_failure();
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        let probe = breaker.try_acquire().unwrap();
+        assert!(breaker.try_acquire().is_err());
+        probe.record_success();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+
+    #[test]
+    fn dropped_probe_frees_the_slot() {
+        let breaker = breaker(Duration::ZERO);
+        breaker.record_failure();
+        breaker.record_failure();
+        let probe = breaker.try_acquire().unwrap();
+        assert!(breaker.try_acquire().is_err());
+        drop(probe);
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        breaker.try_acquire().unwrap().record_success();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+
+    #[test]
+    fn failed_probe_reopens() {
+        let breaker = CircuitBreaker::new(
+            "test",
+            BreakerConfig {
+                failure_threshold: 1,
+                open_duration: Duration::from_millis(20),
+            },
+        );
+        breaker.record_failure();
+        std::thread::sleep(Duration::from_millis(30));
+        breaker.try_acquire().unwrap().record_failure();
+        assert_eq!(breaker.state(), BreakerState::Open);
+    }
+}
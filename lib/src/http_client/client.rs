@@ -1,133 +1,277 @@
 use anyhow::Result;
-use lazy_static::lazy_static;
-use once_cell::sync::OnceCell;
-use reqwest::{Client, Response};
+use once_cell::sync::{Lazy, OnceCell};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use reqwest::{Certificate, Client, RequestBuilder, Response};
 use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
-use crate::http_client::config::HttpClientConfig;
+use crate::auth::jwt::jwt_auth;
+use crate::http_client::breaker::{breaker_for, BreakerState, CircuitBreaker};
+use crate::http_client::config::{HttpClientConfig, ServiceClientConfig};
 use crate::http_client::error::HttpClientError;
+use crate::http_client::retry::{is_retryable_status, RetryPolicy};
+use crate::trace::trace_id::outgoing_headers;
 static CONFIG: OnceCell<HttpClientConfig> = OnceCell::new();
 
-lazy_static! {
-    static ref HTTP_CLIENT: Client = {
-        let cfg = CONFIG.get_or_init(HttpClientConfig::from_env);
-        info!(
-            "Initializing global HTTP client with multi-service config: {:?}",
-            cfg.base_url
-        );
+static CLIENTS: Lazy<RwLock<HashMap<String, Arc<ServiceClient>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// The client used to call one service, with that service's settings and
+/// circuit breaker.
+#[derive(Debug)]
+pub struct ServiceClient {
+    name: String,
+    config: ServiceClientConfig,
+    client: Client,
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl ServiceClient {
+    pub fn new(name: &str, config: ServiceClientConfig) -> Result<Self, HttpClientError> {
+        let invalid = |e: String| HttpClientError::InvalidConfig(format!("{}: {}", name, e));
+
+        let mut headers = HeaderMap::new();
+        if let Some(ref auth_header) = config.auth_header {
+            let value = HeaderValue::from_str(auth_header).map_err(|e| invalid(e.to_string()))?;
+            headers.insert(AUTHORIZATION, value);
+        }
+        for (key, value) in &config.default_headers {
+            let key = HeaderName::from_bytes(key.as_bytes()).map_err(|e| invalid(e.to_string()))?;
+            let value = HeaderValue::from_str(value).map_err(|e| invalid(e.to_string()))?;
+            headers.insert(key, value);
+        }
 
         let mut builder = Client::builder()
-            .timeout(Duration::from_secs(cfg.timeout_seconds))
-            .pool_idle_timeout(Duration::from_secs(cfg.pool_idle_timeout_seconds))
-            .pool_max_idle_per_host(cfg.max_idle_connections);
-
-        if let Some(ref auth_header) = cfg.default_header_auth {
-            builder = builder.default_headers({
-                let mut headers = reqwest::header::HeaderMap::new();
-                headers.insert(
-                    reqwest::header::AUTHORIZATION,
-                    reqwest::header::HeaderValue::from_str(auth_header).unwrap(),
-                );
-                headers
-            });
+            .timeout(Duration::from_secs(config.timeout_seconds))
+            .pool_idle_timeout(Duration::from_secs(config.pool_idle_timeout_seconds))
+            .pool_max_idle_per_host(config.max_idle_connections)
+            .default_headers(headers)
+            .danger_accept_invalid_certs(config.tls.accept_invalid_certs);
+        if let Some(ref path) = config.tls.ca_cert_file {
+            let pem = std::fs::read(path).map_err(|e| invalid(format!("{}: {}", path, e)))?;
+            let cert = Certificate::from_pem(&pem).map_err(|e| invalid(e.to_string()))?;
+            builder = builder.add_root_certificate(cert);
         }
+        let client = builder.build().map_err(|e| invalid(e.to_string()))?;
+
+        Ok(Self {
+            name: name.to_lowercase(),
+            breaker: breaker_for(name, config.breaker),
+            config,
+            client,
+        })
+    }
 
-        builder.build().expect("Failed to build global HTTP client")
-    };
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn config(&self) -> &ServiceClientConfig {
+        &self.config
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    pub fn breaker_state(&self) -> BreakerState {
+        self.breaker.state()
+    }
+
+    /// Full URL of `endpoint`, which may already be absolute.
+    pub fn url(&self, endpoint: &str) -> String {
+        match self.config.base_url {
+            Some(ref base) if !endpoint.starts_with("http") => format!(
+                "{}/{}",
+                base.trim_end_matches('/'),
+                endpoint.trim_start_matches('/')
+            ),
+            _ => endpoint.to_string(),
+        }
+    }
+
+    /// Adds this service's own token when the client has no configured
+    /// `Authorization` header and the service signs its calls.
+    fn authorized(&self, builder: RequestBuilder) -> RequestBuilder {
+        if self.config.auth_header.is_some() {
+            return builder;
+        }
+        match jwt_auth().and_then(|auth| auth.service_token()) {
+            Some(token) => builder.bearer_auth(token),
+            None => builder,
+        }
+    }
+
+    /// Sends through the breaker, retrying idempotent calls per the policy.
+    async fn send<F>(&self, idempotent: bool, request: F) -> Result<Response, HttpClientError>
+    where
+        F: Fn(&Client) -> RequestBuilder,
+    {
+        let policy = if idempotent { self.config.retry } else { RetryPolicy::none() };
+        execute(&self.breaker, &policy, || self.authorized(request(&self.client))).await
+    }
+}
+
+fn config() -> &'static HttpClientConfig {
+    CONFIG.get_or_init(HttpClientConfig::from_env)
 }
 
+/// Loads the configuration and builds the client of every configured
+/// service, so a misconfigured one stops the service at startup.
 pub fn init_http_client() {
-    let _ = CONFIG.set(HttpClientConfig::from_env());
-    info!("✅ HTTP client configuration loaded");
+    let cfg = config();
+    for name in cfg.services.keys() {
+        service_client(name);
+    }
+    info!(
+        "✅ HTTP client configuration loaded for {:?}",
+        cfg.services.keys().collect::<Vec<_>>()
+    );
 }
 
-pub fn client() -> &'static Client {
-    &HTTP_CLIENT
+/// Client of `service`, built on first use; services without their own
+/// settings get the defaults.
+pub fn service_client(service: &str) -> Arc<ServiceClient> {
+    let key = service.to_lowercase();
+    if let Some(client) = CLIENTS.read().unwrap_or_else(|e| e.into_inner()).get(&key) {
+        return client.clone();
+    }
+    let mut clients = CLIENTS.write().unwrap_or_else(|e| e.into_inner());
+    clients
+        .entry(key)
+        .or_insert_with(|| {
+            let client = ServiceClient::new(service, config().service(service).clone())
+                .expect("Failed to build HTTP client");
+            info!("Initialized HTTP client for {}: {:?}", service, client.config.base_url);
+            Arc::new(client)
+        })
+        .clone()
 }
 
-/// GET JSON from full or relative URL
+/// GET JSON from full or relative URL, retried on transient failures
 pub async fn get_json<T: DeserializeOwned>(service: &str, path: &str) -> Result<T> {
-    let full_url = resolve_url(service, path);
+    let service = service_client(service);
+    let full_url = service.url(path);
     info!("GET {}", full_url);
-    let res = client()
-        .get(&full_url)
-        .send()
-        .await
-        .map_err(|e| HttpClientError::RequestFailed(e.to_string()))?;
+    let res = service.send(true, |client| client.get(&full_url)).await?;
     handle_response(res).await
 }
 
-/// POST JSON
+/// POST JSON, attempted once since a POST may not be safe to repeat
 pub async fn post_json<T: DeserializeOwned, B: Serialize>(
     service: &str,
     path: &str,
     body: &B,
 ) -> Result<T> {
-    let full_url = resolve_url(service, path);
+    let service = service_client(service);
+    let full_url = service.url(path);
+    info!("POST {}", full_url);
+    let res = service.send(false, |client| client.post(&full_url).json(body)).await?;
+    handle_response(res).await
+}
+
+/// POST JSON to an endpoint that is idempotent, e.g. keyed by a reference,
+/// retried like the other idempotent methods
+pub async fn post_json_idempotent<T: DeserializeOwned, B: Serialize>(
+    service: &str,
+    path: &str,
+    body: &B,
+) -> Result<T> {
+    let service = service_client(service);
+    let full_url = service.url(path);
     info!("POST {}", full_url);
-    let res = client()
-        .post(&full_url)
-        .json(body)
-        .send()
-        .await
-        .map_err(|e| HttpClientError::RequestFailed(e.to_string()))?;
+    let res = service.send(true, |client| client.post(&full_url).json(body)).await?;
     handle_response(res).await
 }
 
-/// PUT JSON
+/// PUT JSON, retried on transient failures
 pub async fn put_json<T: DeserializeOwned, B: Serialize>(
     service: &str,
     path: &str,
     body: &B,
 ) -> Result<T> {
-    let full_url = resolve_url(service, path);
+    let service = service_client(service);
+    let full_url = service.url(path);
     info!("PUT {}", full_url);
-    let res = client()
-        .put(&full_url)
-        .json(body)
-        .send()
-        .await
-        .map_err(|e| HttpClientError::RequestFailed(e.to_string()))?;
+    let res = service.send(true, |client| client.put(&full_url).json(body)).await?;
     handle_response(res).await
 }
 
-/// DELETE
+/// DELETE, retried on transient failures
 pub async fn delete(service: &str, path: &str) -> Result<()> {
-    let full_url = resolve_url(service, path);
+    let service = service_client(service);
+    let full_url = service.url(path);
     info!("DELETE {}", full_url);
-    let res = client()
-        .delete(&full_url)
-        .send()
-        .await
-        .map_err(|e| HttpClientError::RequestFailed(e.to_string()))?;
-    if res.status().is_success() {
+    let res = service.send(true, |client| client.delete(&full_url)).await?;
+    let status = res.status();
+    if status.is_success() {
         Ok(())
     } else {
-        Err(anyhow::anyhow!(
-            "Delete failed with status {}",
-            res.status().as_u16()
-        ))
+        let text = res.text().await.unwrap_or_default();
+        error!("HTTP error {}: {}", status.as_u16(), text);
+        Err(HttpClientError::from_response(status.as_u16(), text).into())
     }
 }
 
-/// Helper to resolve service base URL dynamically
-fn resolve_url(service: &str, endpoint: &str) -> String {
-    let cfg = CONFIG.get_or_init(HttpClientConfig::from_env);
-    if endpoint.starts_with("http") {
-        endpoint.to_string()
-    } else if let Some(base) = cfg.resolve_base_url(service) {
-        format!(
-            "{}/{}",
-            base.trim_end_matches('/'),
-            endpoint.trim_start_matches('/')
-        )
-    } else {
-        endpoint.to_string() // fallback
+/// State of the circuit breaker of `service`.
+pub fn breaker_state(service: &str) -> BreakerState {
+    service_client(service).breaker_state()
+}
+
+/// Retries transport errors and retryable statuses per `policy`. The last
+/// retryable response is returned as is once the attempts run out.
+async fn execute<F>(
+    breaker: &CircuitBreaker,
+    policy: &RetryPolicy,
+    request: F,
+) -> Result<Response, HttpClientError>
+where
+    F: Fn() -> RequestBuilder,
+{
+    let mut attempt = 1;
+    loop {
+        let permit = breaker.try_acquire()?;
+        let failure = match traced(request()).send().await {
+            Ok(res) => {
+                let status = res.status();
+                if status.is_server_error() {
+                    permit.record_failure();
+                } else {
+                    permit.record_success();
+                }
+                if !is_retryable_status(status) || attempt >= policy.max_attempts {
+                    return Ok(res);
+                }
+                format!("status {}", status.as_u16())
+            }
+            Err(e) => {
+                permit.record_failure();
+                if attempt >= policy.max_attempts {
+                    return Err(HttpClientError::RequestFailed(e.to_string()));
+                }
+                e.to_string()
+            }
+        };
+        let delay = policy.backoff(attempt);
+        warn!(
+            "attempt {}/{} failed ({}), retrying in {:?}",
+            attempt, policy.max_attempts, failure, delay
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
     }
 }
 
+/// Forwards the current trace id so the callee logs under the same trace
+fn traced(builder: RequestBuilder) -> RequestBuilder {
+    outgoing_headers()
+        .into_iter()
+        .fold(builder, |builder, (name, value)| builder.header(name, value))
+}
+
 /// Deserialize and error-check JSON response
 async fn handle_response<T: DeserializeOwned>(res: Response) -> Result<T> {
     let status = res.status();
@@ -138,6 +282,140 @@ async fn handle_response<T: DeserializeOwned>(res: Response) -> Result<T> {
             .map_err(|e| HttpClientError::DeserializeFailed(e.to_string()).into())
     } else {
         error!("HTTP error {}: {}", status.as_u16(), text);
-        Err(HttpClientError::UnexpectedStatus(status.as_u16()).into())
+        Err(HttpClientError::from_response(status.as_u16(), text).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_client::breaker::BreakerConfig;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serves `statuses` in order, repeating the last one, and counts hits.
+    async fn mock_server(statuses: Vec<u16>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    return;
+                };
+                let hit = counter.fetch_add(1, Ordering::SeqCst);
+                let status = statuses[hit.min(statuses.len() - 1)];
+                let mut buf = [0u8; 4096];
+                let _ = socket.read(&mut buf).await;
+                let body = "{}";
+                let response = format!(
+                    "HTTP/1.1 {} X\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        (url, hits)
+    }
+
+    fn fast_retries(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+        }
+    }
+
+    fn breaker(failure_threshold: u32) -> CircuitBreaker {
+        CircuitBreaker::new(
+            "mock",
+            BreakerConfig {
+                failure_threshold,
+                open_duration: Duration::from_secs(60),
+            },
+        )
+    }
+
+    #[test]
+    fn service_client_resolves_relative_paths() {
+        let config = ServiceClientConfig {
+            base_url: Some("http://wallet:8087/".to_string()),
+            ..Default::default()
+        };
+        let service = ServiceClient::new("url-test", config).unwrap();
+        assert_eq!(service.url("/wallet/debit"), "http://wallet:8087/wallet/debit");
+        assert_eq!(service.url("http://other/x"), "http://other/x");
+    }
+
+    #[test]
+    fn invalid_default_header_is_rejected() {
+        let config = ServiceClientConfig {
+            default_headers: HashMap::from([("bad header".to_string(), "v".to_string())]),
+            ..Default::default()
+        };
+        assert!(matches!(
+            ServiceClient::new("header-test", config),
+            Err(HttpClientError::InvalidConfig(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn retries_transient_failures_until_success() {
+        let (url, hits) = mock_server(vec![503, 502, 200]).await;
+        let breaker = breaker(10);
+        let res = execute(&breaker, &fast_retries(3), || Client::new().get(&url))
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 200);
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let (url, hits) = mock_server(vec![404]).await;
+        let res = execute(&breaker(10), &fast_retries(3), || Client::new().get(&url))
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 404);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn single_attempt_without_retry_policy() {
+        let (url, hits) = mock_server(vec![503, 200]).await;
+        let res = execute(&breaker(10), &RetryPolicy::none(), || Client::new().post(&url))
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 503);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn open_circuit_fails_fast() {
+        let (url, hits) = mock_server(vec![500]).await;
+        let breaker = breaker(2);
+        let res = execute(&breaker, &fast_retries(5), || Client::new().get(&url)).await;
+        assert!(matches!(res, Err(HttpClientError::CircuitOpen(_))));
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+        assert_eq!(breaker.state(), BreakerState::Open);
+
+        let res = execute(&breaker, &fast_retries(1), || Client::new().get(&url)).await;
+        assert!(matches!(res, Err(HttpClientError::CircuitOpen(_))));
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn connection_errors_are_retried_and_reported() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        drop(listener);
+        let breaker = breaker(10);
+        let res = execute(&breaker, &fast_retries(2), || Client::new().get(&url)).await;
+        assert!(matches!(res, Err(HttpClientError::RequestFailed(_))));
     }
 }
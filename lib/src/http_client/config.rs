@@ -1,56 +1,262 @@
+use serde::Deserialize;
 use std::collections::HashMap;
+use std::time::Duration;
 
+use crate::http_client::breaker::BreakerConfig;
+use crate::http_client::retry::RetryPolicy;
+
+/// TLS settings of one client.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TlsConfig {
+    /// extra PEM root certificate, e.g. an internal CA
+    pub ca_cert_file: Option<String>,
+    /// skips certificate validation, only meant for local setups
+    pub accept_invalid_certs: bool,
+}
+
+/// Settings of the client used to call one service.
 #[derive(Debug, Clone)]
-pub struct HttpClientConfig {
-    pub base_url: HashMap<String, String>,
+pub struct ServiceClientConfig {
+    pub base_url: Option<String>,
     pub timeout_seconds: u64,
     pub max_idle_connections: usize,
     pub pool_idle_timeout_seconds: u64,
-    pub default_header_auth: Option<String>,
+    /// value of the `Authorization` header sent on every call
+    pub auth_header: Option<String>,
+    pub default_headers: HashMap<String, String>,
+    pub tls: TlsConfig,
+    /// retries of idempotent calls
+    pub retry: RetryPolicy,
+    pub breaker: BreakerConfig,
+}
+
+impl Default for ServiceClientConfig {
+    fn default() -> Self {
+        Self {
+            base_url: None,
+            timeout_seconds: 10,
+            max_idle_connections: 10,
+            pool_idle_timeout_seconds: 30,
+            auth_header: None,
+            default_headers: HashMap::new(),
+            tls: TlsConfig::default(),
+            retry: RetryPolicy::default(),
+            breaker: BreakerConfig::default(),
+        }
+    }
+}
+
+/// Partial settings of a service, from the clients file or the environment;
+/// whatever is left out keeps the default.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ServiceClientOverrides {
+    pub base_url: Option<String>,
+    pub timeout_seconds: Option<u64>,
+    pub max_idle_connections: Option<usize>,
+    pub pool_idle_timeout_seconds: Option<u64>,
+    pub auth_header: Option<String>,
+    pub headers: HashMap<String, String>,
+    pub ca_cert_file: Option<String>,
+    pub accept_invalid_certs: Option<bool>,
+    pub retry_attempts: Option<u32>,
+    pub retry_base_delay_ms: Option<u64>,
+    pub retry_max_delay_ms: Option<u64>,
+    pub breaker_failures: Option<u32>,
+    pub breaker_open_seconds: Option<u64>,
+}
+
+impl ServiceClientOverrides {
+    /// Overrides read from `{prefix}_*` variables, e.g. `WALLET_SERVICE_URL`
+    /// and `WALLET_SERVICE_TIMEOUT_SECONDS` for the `WALLET_SERVICE` prefix.
+    fn from_env(prefix: &str) -> Self {
+        let key = |name: &str| format!("{}_{}", prefix, name);
+        let var = |name: &str| std::env::var(key(name)).ok();
+        Self {
+            base_url: var("URL"),
+            timeout_seconds: env_parsed(&key("TIMEOUT_SECONDS")),
+            max_idle_connections: env_parsed(&key("MAX_IDLE_CONNECTIONS")),
+            pool_idle_timeout_seconds: env_parsed(&key("POOL_IDLE_TIMEOUT_SECONDS")),
+            auth_header: var("AUTH_HEADER"),
+            headers: var("HEADERS").map(|v| parse_headers(&v)).unwrap_or_default(),
+            ca_cert_file: var("CA_CERT_FILE"),
+            accept_invalid_certs: env_parsed(&key("ACCEPT_INVALID_CERTS")),
+            retry_attempts: env_parsed(&key("RETRY_ATTEMPTS")),
+            retry_base_delay_ms: env_parsed(&key("RETRY_BASE_DELAY_MS")),
+            retry_max_delay_ms: env_parsed(&key("RETRY_MAX_DELAY_MS")),
+            breaker_failures: env_parsed(&key("BREAKER_FAILURES")),
+            breaker_open_seconds: env_parsed(&key("BREAKER_OPEN_SECONDS")),
+        }
+    }
+
+    fn apply(self, config: &mut ServiceClientConfig) {
+        if self.base_url.is_some() {
+            config.base_url = self.base_url;
+        }
+        if let Some(v) = self.timeout_seconds {
+            config.timeout_seconds = v;
+        }
+        if let Some(v) = self.max_idle_connections {
+            config.max_idle_connections = v;
+        }
+        if let Some(v) = self.pool_idle_timeout_seconds {
+            config.pool_idle_timeout_seconds = v;
+        }
+        if self.auth_header.is_some() {
+            config.auth_header = self.auth_header;
+        }
+        config.default_headers.extend(self.headers);
+        if self.ca_cert_file.is_some() {
+            config.tls.ca_cert_file = self.ca_cert_file;
+        }
+        if let Some(v) = self.accept_invalid_certs {
+            config.tls.accept_invalid_certs = v;
+        }
+        if let Some(v) = self.retry_attempts {
+            config.retry.max_attempts = v.max(1);
+        }
+        if let Some(v) = self.retry_base_delay_ms {
+            config.retry.base_delay = Duration::from_millis(v);
+        }
+        if let Some(v) = self.retry_max_delay_ms {
+            config.retry.max_delay = Duration::from_millis(v);
+        }
+        if let Some(v) = self.breaker_failures {
+            config.breaker.failure_threshold = v.max(1);
+        }
+        if let Some(v) = self.breaker_open_seconds {
+            config.breaker.open_duration = Duration::from_secs(v);
+        }
+    }
+}
+
+/// Client settings of every known service plus the defaults used for the
+/// rest.
+///
+/// Each service starts from the defaults, then takes its entry in the JSON
+/// file named by `HTTP_CLIENTS_FILE`, then its `{NAME}_SERVICE_*` variables.
+#[derive(Debug, Clone, Default)]
+pub struct HttpClientConfig {
+    pub defaults: ServiceClientConfig,
+    /// keyed by lowercase service name, e.g. `wallet`
+    pub services: HashMap<String, ServiceClientConfig>,
 }
 
 impl HttpClientConfig {
     pub fn from_env() -> Self {
         dotenvy::dotenv().ok();
 
-        let timeout_seconds = std::env::var("TIMEOUT_SECONDS")
-            .ok()
-            .and_then(|v| v.parse().ok())
-            .unwrap_or(10);
-
-        let max_idle_connections = std::env::var("MAX_IDLE_CONNECTIONS")
-            .ok()
-            .and_then(|v| v.parse().ok())
-            .unwrap_or(10);
-
-        let pool_idle_timeout_seconds = std::env::var("POOL_IDLE_TIMEOUT_SECONDS")
-            .ok()
-            .and_then(|v| v.parse().ok())
-            .unwrap_or(30);
-        let default_header_auth = std::env::var("DEFAULT_HEADER_AUTH");
-
-        // Load multiple base URLs (e.g., USER_SERVICE_URL, TRANSFER_SERVICE_URL)
-        let mut base_urls = HashMap::new();
-        for (key, value) in std::env::vars() {
-            if key.ends_with("_SERVICE_URL") {
-                base_urls.insert(key.to_lowercase(), value);
+        let mut defaults = ServiceClientConfig::default();
+        let global = ServiceClientOverrides {
+            timeout_seconds: env_parsed("TIMEOUT_SECONDS"),
+            max_idle_connections: env_parsed("MAX_IDLE_CONNECTIONS"),
+            pool_idle_timeout_seconds: env_parsed("POOL_IDLE_TIMEOUT_SECONDS"),
+            auth_header: std::env::var("DEFAULT_HEADER_AUTH").ok(),
+            retry_attempts: env_parsed("HTTP_CLIENT_RETRY_ATTEMPTS"),
+            retry_base_delay_ms: env_parsed("HTTP_CLIENT_RETRY_BASE_DELAY_MS"),
+            retry_max_delay_ms: env_parsed("HTTP_CLIENT_RETRY_MAX_DELAY_MS"),
+            breaker_failures: env_parsed("HTTP_CLIENT_BREAKER_FAILURES"),
+            breaker_open_seconds: env_parsed("HTTP_CLIENT_BREAKER_OPEN_SECONDS"),
+            ..Default::default()
+        };
+        global.apply(&mut defaults);
+
+        let file = match std::env::var("HTTP_CLIENTS_FILE") {
+            Ok(path) => Self::read_file(&path),
+            Err(_) => HashMap::new(),
+        };
+
+        // services named in the file or by a *_SERVICE_URL variable
+        let mut names: Vec<String> = file.keys().map(|k| k.to_lowercase()).collect();
+        for (key, _) in std::env::vars() {
+            if let Some(name) = key.strip_suffix("_SERVICE_URL") {
+                names.push(name.to_lowercase());
             }
         }
+        names.sort();
+        names.dedup();
 
-        Self {
-            base_url: base_urls,
-            timeout_seconds,
-            max_idle_connections,
-            pool_idle_timeout_seconds,
-            default_header_auth: match default_header_auth.ok() {
-                None => None,
-                Some(d) => Some(d),
-            },
+        let mut file = file
+            .into_iter()
+            .map(|(k, v)| (k.to_lowercase(), v))
+            .collect::<HashMap<_, _>>();
+        let services = names
+            .into_iter()
+            .map(|name| {
+                let mut config = defaults.clone();
+                if let Some(overrides) = file.remove(&name) {
+                    overrides.apply(&mut config);
+                }
+                ServiceClientOverrides::from_env(&format!("{}_SERVICE", name.to_uppercase()))
+                    .apply(&mut config);
+                (name, config)
+            })
+            .collect();
+
+        Self { defaults, services }
+    }
+
+    fn read_file(path: &str) -> HashMap<String, ServiceClientOverrides> {
+        let parsed = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()));
+        match parsed {
+            Ok(services) => services,
+            Err(e) => {
+                tracing::error!("failed to load HTTP clients file {}: {}", path, e);
+                HashMap::new()
+            }
         }
     }
 
+    /// settings of `service`, the defaults when it is not configured
+    pub fn service(&self, service: &str) -> &ServiceClientConfig {
+        self.services
+            .get(&service.to_lowercase())
+            .unwrap_or(&self.defaults)
+    }
+
     pub fn resolve_base_url(&self, key: &str) -> Option<String> {
-        let key = format!("{}_service_url", key.to_lowercase());
-        self.base_url.get(&key).cloned()
+        self.service(key).base_url.clone()
+    }
+}
+
+fn env_parsed<T: std::str::FromStr>(key: &str) -> Option<T> {
+    std::env::var(key).ok().and_then(|v| v.parse().ok())
+}
+
+/// `name=value` pairs separated by commas
+fn parse_headers(value: &str) -> HashMap<String, String> {
+    value
+        .split(',')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .filter(|(name, _)| !name.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_only_replace_what_they_set() {
+        let mut config = ServiceClientConfig::default();
+        let overrides: ServiceClientOverrides = serde_json::from_str(
+            r#"{"timeout_seconds": 2, "headers": {"x-api-key": "k"}, "retry_attempts": 0}"#,
+        )
+        .unwrap();
+        overrides.apply(&mut config);
+        assert_eq!(config.timeout_seconds, 2);
+        assert_eq!(config.max_idle_connections, 10);
+        assert_eq!(config.default_headers.get("x-api-key").map(String::as_str), Some("k"));
+        assert_eq!(config.retry.max_attempts, 1);
+    }
+
+    #[test]
+    fn headers_are_parsed_from_pairs() {
+        let headers = parse_headers("x-api-key=abc, x-tenant = t1,broken");
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers["x-tenant"], "t1");
     }
 }
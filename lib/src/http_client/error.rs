@@ -1,5 +1,8 @@
+use reqwest::StatusCode;
 use thiserror::Error;
 
+use crate::http_client::retry::is_retryable_status;
+
 #[derive(Debug, Error)]
 pub enum HttpClientError {
     #[error("HTTP request failed: {0}")]
@@ -8,6 +11,118 @@ pub enum HttpClientError {
     #[error("Deserialization failed: {0}")]
     DeserializeFailed(String),
 
-    #[error("Unexpected status code: {0}")]
-    UnexpectedStatus(u16),
-}
\ No newline at end of file
+    /// The service answered with a non-success status. `message` and `code`
+    /// come from its `BaseResponse` body when it sent one.
+    #[error(
+        "Unexpected status code: {status}{}",
+        message.as_ref().map(|m| format!(" ({m})")).unwrap_or_default()
+    )]
+    UnexpectedStatus {
+        status: u16,
+        message: Option<String>,
+        code: Option<String>,
+        body: String,
+    },
+
+    #[error("Circuit open for service {0}")]
+    CircuitOpen(String),
+
+    #[error("Invalid HTTP client configuration: {0}")]
+    InvalidConfig(String),
+}
+
+impl HttpClientError {
+    /// Error for a non-success response, keeping the body and whatever
+    /// `message` and `error_code` it carries.
+    pub fn from_response(status: u16, body: String) -> Self {
+        let parsed = serde_json::from_str::<serde_json::Value>(&body).ok();
+        let field = |name: &str| {
+            parsed
+                .as_ref()
+                .and_then(|v| v.get(name))
+                .and_then(|v| v.as_str())
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        HttpClientError::UnexpectedStatus {
+            status,
+            message: field("message"),
+            code: field("error_code"),
+            body,
+        }
+    }
+
+    /// status of the upstream response, if one was received
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            HttpClientError::UnexpectedStatus { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// `error_code` of the upstream response body
+    pub fn error_code(&self) -> Option<&str> {
+        match self {
+            HttpClientError::UnexpectedStatus { code, .. } => code.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// `message` of the upstream response body
+    pub fn upstream_message(&self) -> Option<&str> {
+        match self {
+            HttpClientError::UnexpectedStatus { message, .. } => message.as_deref(),
+            _ => None,
+        }
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        self.status() == Some(404)
+    }
+
+    /// the service rejected the request itself; repeating it will not help
+    pub fn is_client_error(&self) -> bool {
+        self.status().is_some_and(|s| (400..500).contains(&s)) && !self.is_retryable()
+    }
+
+    /// A later attempt may succeed: the call did not get through, the
+    /// circuit is open, or the status is a transient one.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            HttpClientError::RequestFailed(_) | HttpClientError::CircuitOpen(_) => true,
+            HttpClientError::UnexpectedStatus { status, .. } => StatusCode::from_u16(*status)
+                .map(is_retryable_status)
+                .unwrap_or(false),
+            HttpClientError::DeserializeFailed(_) | HttpClientError::InvalidConfig(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_base_response_details() {
+        let body = r#"{"trace_id":"t","message":"Wallet not found for user 7","error_code":"NOT_FOUND","data":null}"#;
+        let e = HttpClientError::from_response(404, body.to_string());
+        assert!(e.is_not_found());
+        assert!(e.is_client_error());
+        assert!(!e.is_retryable());
+        assert_eq!(e.error_code(), Some("NOT_FOUND"));
+        assert_eq!(e.upstream_message(), Some("Wallet not found for user 7"));
+        assert_eq!(
+            e.to_string(),
+            "Unexpected status code: 404 (Wallet not found for user 7)"
+        );
+    }
+
+    #[test]
+    fn non_json_bodies_are_kept_raw() {
+        let e = HttpClientError::from_response(503, "upstream connect error".to_string());
+        assert!(e.is_retryable());
+        assert!(!e.is_client_error());
+        assert_eq!(e.error_code(), None);
+        assert!(matches!(e, HttpClientError::UnexpectedStatus { ref body, .. } if body == "upstream connect error"));
+    }
+}
@@ -1,3 +1,5 @@
+pub mod breaker;
 pub mod client;
 pub mod error;
-pub mod config;
\ No newline at end of file
+pub mod config;
+pub mod retry;
\ No newline at end of file
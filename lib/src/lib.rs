@@ -1,3 +1,5 @@
+pub mod auth;
 pub mod db;
 pub mod http_client;
-pub mod log;
\ No newline at end of file
+pub mod log;
+pub mod trace;
\ No newline at end of file
@@ -15,7 +15,8 @@ pub fn init(service_name: &str) {
                     .with_env_filter(env_filter)
                     .json()
                     .flatten_event(true)
-                    .with_current_span(false)
+                    .with_current_span(true)
+                    .with_span_list(false)
                     .with_target(false)
                     .init();
             }
@@ -0,0 +1,248 @@
+use http::{HeaderMap, HeaderValue, Request, Response};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+use tracing::Instrument;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+tokio::task_local! {
+    static TRACE_ID: String;
+}
+
+/// Trace id of the request being handled, `""` outside of one.
+pub fn current_trace_id() -> String {
+    TRACE_ID.try_with(Clone::clone).unwrap_or_default()
+}
+
+/// A fresh id in the W3C trace-id format (32 lowercase hex digits).
+pub fn new_trace_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+/// Runs `fut` with `trace_id` as the current trace id, for work that does not
+/// start from an HTTP request such as background jobs.
+pub async fn with_trace_id<F: Future>(trace_id: String, fut: F) -> F::Output {
+    let span = tracing::info_span!("job", trace_id = %trace_id);
+    TRACE_ID.scope(trace_id, fut.instrument(span)).await
+}
+
+/// Headers an outgoing call carries to continue the current trace.
+///
+/// `traceparent` is only sent when the id is a valid W3C trace-id; every
+/// outgoing call gets its own parent span id.
+pub fn outgoing_headers() -> Vec<(&'static str, String)> {
+    let trace_id = current_trace_id();
+    if trace_id.is_empty() {
+        return Vec::new();
+    }
+    let mut headers = Vec::with_capacity(2);
+    if is_trace_id(&trace_id) {
+        let span_id = &new_trace_id()[..16];
+        headers.push((TRACEPARENT_HEADER, format!("00-{}-{}-01", trace_id, span_id)));
+    }
+    headers.push((REQUEST_ID_HEADER, trace_id));
+    headers
+}
+
+/// Trace id of an incoming request: `X-Request-Id` when present, the trace-id
+/// of a `traceparent` otherwise, or a new one.
+pub fn incoming_trace_id(headers: &HeaderMap) -> String {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+    };
+    if let Some(id) = header(REQUEST_ID_HEADER).filter(|id| id.len() <= 128) {
+        return id.to_string();
+    }
+    header(TRACEPARENT_HEADER)
+        .and_then(parse_traceparent)
+        .unwrap_or_else(new_trace_id)
+}
+
+/// trace-id of a `version-traceid-parentid-flags` header
+fn parse_traceparent(value: &str) -> Option<String> {
+    let mut parts = value.split('-');
+    let version = parts.next()?;
+    let trace_id = parts.next()?;
+    let parent_id = parts.next()?;
+    parts.next()?;
+    if version.len() != 2 || parent_id.len() != 16 || !is_trace_id(trace_id) {
+        return None;
+    }
+    Some(trace_id.to_string())
+}
+
+fn is_trace_id(value: &str) -> bool {
+    value.len() == 32
+        && value.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+        && value.bytes().any(|b| b != b'0')
+}
+
+/// Tower layer giving every request a trace id: it is recorded on the
+/// request's tracing span, readable through [`current_trace_id`] while the
+/// request is handled and echoed in the `X-Request-Id` response header.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TraceIdLayer;
+
+impl<S> Layer<S> for TraceIdLayer {
+    type Service = TraceIdService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TraceIdService { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TraceIdService<S> {
+    inner: S,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for TraceIdService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        // the clone may not be ready, keep the one that was polled
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let trace_id = incoming_trace_id(request.headers());
+        let span = tracing::info_span!(
+            "request",
+            trace_id = %trace_id,
+            method = %request.method(),
+            path = %request.uri().path(),
+        );
+        let header = HeaderValue::from_str(&trace_id).ok();
+        Box::pin(TRACE_ID.scope(
+            trace_id,
+            async move {
+                let mut response = inner.call(request).await?;
+                if let Some(header) = header {
+                    response.headers_mut().insert(REQUEST_ID_HEADER, header);
+                }
+                Ok(response)
+            }
+            .instrument(span),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::routing::get;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    const TRACE: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn parses_a_valid_traceparent() {
+        let value = format!("00-{}-00f067aa0ba902b7-01", TRACE);
+        assert_eq!(parse_traceparent(&value).as_deref(), Some(TRACE));
+    }
+
+    #[test]
+    fn rejects_malformed_traceparents() {
+        for value in [
+            "",
+            "00",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "000-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e473-00f067aa0ba902b7-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+        ] {
+            assert_eq!(parse_traceparent(value), None, "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn trace_ids_are_32_lowercase_hex_digits_not_all_zero() {
+        assert!(is_trace_id(TRACE));
+        assert!(is_trace_id(&new_trace_id()));
+        assert!(!is_trace_id("00000000000000000000000000000000"));
+        assert!(!is_trace_id("4bf92f3577b34da6a3ce929d0e0e473g"));
+        assert!(!is_trace_id(&TRACE[..31]));
+    }
+
+    #[test]
+    fn request_id_wins_over_traceparent() {
+        let traceparent = format!("00-{}-00f067aa0ba902b7-01", TRACE);
+        let both = headers(&[(REQUEST_ID_HEADER, " req-1 "), (TRACEPARENT_HEADER, &traceparent)]);
+        assert_eq!(incoming_trace_id(&both), "req-1");
+        assert_eq!(incoming_trace_id(&headers(&[(TRACEPARENT_HEADER, &traceparent)])), TRACE);
+    }
+
+    #[test]
+    fn oversized_or_missing_ids_get_a_new_one() {
+        let oversized = "a".repeat(129);
+        let id = incoming_trace_id(&headers(&[(REQUEST_ID_HEADER, &oversized)]));
+        assert!(is_trace_id(&id));
+        assert_eq!(incoming_trace_id(&headers(&[(REQUEST_ID_HEADER, &"a".repeat(128))])).len(), 128);
+
+        let id = incoming_trace_id(&headers(&[(TRACEPARENT_HEADER, "garbage")]));
+        assert!(is_trace_id(&id));
+        assert!(is_trace_id(&incoming_trace_id(&HeaderMap::new())));
+    }
+
+    #[tokio::test]
+    async fn layer_echoes_the_trace_id_it_handled_the_request_with() {
+        let app = Router::new()
+            .route("/", get(|| async { current_trace_id() }))
+            .layer(TraceIdLayer);
+        let request = Request::builder()
+            .uri("/")
+            .header(REQUEST_ID_HEADER, "req-1")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "req-1");
+        let body = axum::body::to_bytes(response.into_body(), 1024).await.unwrap();
+        assert_eq!(body, "req-1");
+        assert_eq!(current_trace_id(), "");
+    }
+
+    #[tokio::test]
+    async fn outgoing_calls_continue_the_trace() {
+        let headers = with_trace_id(TRACE.to_string(), async { outgoing_headers() }).await;
+        let traceparent = &headers.iter().find(|(name, _)| *name == TRACEPARENT_HEADER).unwrap().1;
+        assert_eq!(parse_traceparent(traceparent).as_deref(), Some(TRACE));
+        assert!(headers.contains(&(REQUEST_ID_HEADER, TRACE.to_string())));
+
+        // ids that are not W3C trace-ids only travel as X-Request-Id
+        let headers = with_trace_id("req-1".to_string(), async { outgoing_headers() }).await;
+        assert_eq!(headers, vec![(REQUEST_ID_HEADER, "req-1".to_string())]);
+        assert!(outgoing_headers().is_empty());
+    }
+}
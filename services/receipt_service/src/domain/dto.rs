@@ -0,0 +1,9 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct ReceiptRequest {
+    /// transfer to issue the receipt for, loaded from transfer-service
+    pub transaction_id: String,
+    #[serde(default)]
+    pub user_email: String,
+}
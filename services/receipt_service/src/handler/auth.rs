@@ -0,0 +1,13 @@
+use crate::handler::receipt::error_response;
+use axum::http::StatusCode;
+use axum::Json;
+use domain::base::base::BaseResponse;
+use lib::auth::error::AuthError;
+
+/// resource name of the `receipt:admin` scope, which may issue and read any receipt
+pub const RECEIPT: &str = "receipt";
+
+/// Response of a caller that may not act on the receipt.
+pub fn denied<T>(e: AuthError) -> (StatusCode, Json<BaseResponse<T>>) {
+    error_response(e.into())
+}
@@ -0,0 +1,3 @@
+pub async fn health_check() -> &'static str {
+    "Pong! Receipt service is healthy!"
+}
@@ -0,0 +1,125 @@
+use crate::app::AppState;
+use crate::domain::dto::ReceiptRequest;
+use crate::handler::auth::{denied, RECEIPT};
+use crate::usecase::receipt::ReceiptGenerator;
+use axum::extract::{Path, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use domain::base::base::BaseResponse;
+use domain::receipt::error::ReceiptError;
+use domain::receipt::receipt::Receipt;
+use lib::auth::error::AuthError;
+use lib::auth::extractor::AuthUser;
+use lib::http_client::error::HttpClientError;
+use lib::trace::trace_id::current_trace_id;
+
+/// Generates the PDF receipt of a completed transfer
+/// request :
+///   - transaction id of a Committed or Reversed transfer
+///   - user email (optional)
+///
+/// The transfer is loaded from transfer-service, never taken from the caller,
+/// and a transfer gets one receipt: asking again answers 409. Only a
+/// `receipt:admin`, such as transfer-service, may issue receipts. The PDF is
+/// then served by `GET /receipts/{transaction_id}`.
+pub async fn create_receipt(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(request): Json<ReceiptRequest>,
+) -> (StatusCode, Json<BaseResponse<Receipt>>) {
+    tracing::info!("create receipt for transfer: {:?}", request.transaction_id);
+    if let Err(e) = auth.ensure_admin(RECEIPT) {
+        return denied(e);
+    }
+    match state
+        .usecase
+        .create_receipt(&request.transaction_id, &request.user_email)
+        .await
+    {
+        Ok(data) => {
+            let response = BaseResponse::new(current_trace_id(), "Success".to_string(), Some(data));
+            (StatusCode::CREATED, Json::from(response))
+        }
+        Err(e) => error_response(e),
+    }
+}
+
+/// Serves the stored PDF receipt of a transfer to its sender, its receiver or
+/// a `receipt:admin`; anyone else gets 404, as for an unknown receipt.
+pub async fn get_receipt(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(transaction_id): Path<String>,
+) -> Response {
+    tracing::info!("get receipt for transaction id: {:?}", transaction_id);
+    match state.usecase.get_receipt(&transaction_id).await {
+        Ok(stored) if !auth.is_admin(RECEIPT) && !is_party(&auth, stored.receipt.as_ref()) => {
+            error_response::<()>(ReceiptError::ReceiptNotFound(transaction_id).into()).into_response()
+        }
+        Ok(stored) => (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, "application/pdf".to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("inline; filename=\"receipt-{}.pdf\"", transaction_id),
+                ),
+            ],
+            stored.pdf,
+        )
+            .into_response(),
+        Err(e) => error_response::<()>(e).into_response(),
+    }
+}
+
+/// receipts stored without their details are only served to admins
+fn is_party(auth: &AuthUser, receipt: Option<&Receipt>) -> bool {
+    receipt.is_some_and(|r| auth.subject == r.sender || auth.subject == r.receiver)
+}
+
+pub fn error_response<T>(e: anyhow::Error) -> (StatusCode, Json<BaseResponse<T>>) {
+    if let Some(error) = e.downcast_ref::<AuthError>() {
+        if error.status().is_server_error() {
+            tracing::error!("receipt request failed: {:#}", e);
+        }
+        let response = BaseResponse::error(current_trace_id(), error.code(), error.public_message());
+        return (error.status(), Json::from(response));
+    }
+    if let Some(error) = e.downcast_ref::<HttpClientError>() {
+        tracing::error!("transfer-service call failed: {}", error);
+        let response = BaseResponse::error(
+            current_trace_id(),
+            "UPSTREAM_ERROR",
+            "Upstream service unavailable".to_string(),
+        );
+        return (StatusCode::BAD_GATEWAY, Json::from(response));
+    }
+    let (status, code, message) = match e.downcast_ref::<ReceiptError>() {
+        Some(error @ ReceiptError::ReceiptNotFound(_)) => {
+            (StatusCode::NOT_FOUND, "NOT_FOUND", error.to_string())
+        }
+        Some(error @ ReceiptError::TransferNotFound(_)) => {
+            (StatusCode::NOT_FOUND, "TRANSFER_NOT_FOUND", error.to_string())
+        }
+        Some(error @ ReceiptError::AlreadyIssued(_)) => {
+            (StatusCode::CONFLICT, "RECEIPT_ALREADY_ISSUED", error.to_string())
+        }
+        Some(error @ ReceiptError::InvalidTransactionId(_)) => {
+            (StatusCode::BAD_REQUEST, "INVALID_TRANSACTION_ID", error.to_string())
+        }
+        Some(error @ ReceiptError::NotCompleted(_)) => {
+            (StatusCode::UNPROCESSABLE_ENTITY, "TRANSFER_NOT_COMPLETED", error.to_string())
+        }
+        None => {
+            tracing::error!("receipt request failed: {:#}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "Internal server error".to_string(),
+            )
+        }
+    };
+    let response = BaseResponse::error(current_trace_id(), code, message);
+    (status, Json::from(response))
+}
@@ -0,0 +1,15 @@
+use crate::app::AppState;
+use crate::handler::health::health_check;
+use crate::handler::receipt::{create_receipt, get_receipt};
+use axum::routing::{get, post};
+use axum::Router;
+use lib::trace::trace_id::TraceIdLayer;
+
+pub fn routes(app_state: AppState) -> Router {
+    Router::new()
+        .route("/health", get(health_check))
+        .route("/receipts", post(create_receipt))
+        .route("/receipts/{transaction_id}", get(get_receipt))
+        .layer(TraceIdLayer)
+        .with_state(app_state)
+}
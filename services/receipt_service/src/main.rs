@@ -1,3 +1,60 @@
-fn main() {
-    println!("Hello, world!");
+mod app;
+
+use crate::app::{AppConfig, AppState};
+use crate::handler::router::routes;
+use crate::render::template::ReceiptTemplate;
+use crate::repository::http::transfer_gateway::RestTransferRepository;
+use crate::repository::storage::file::FileReceiptStore;
+use crate::usecase::receipt::Usecase;
+use lib::auth::jwt::init_auth;
+use lib::http_client::client::init_http_client;
+use lib::log::logging::init;
+use std::sync::Arc;
+
+mod repository {
+    pub mod http;
+    pub mod storage;
+}
+
+mod render {
+    pub mod pdf;
+    pub mod template;
+}
+
+mod usecase {
+    pub mod receipt;
+}
+mod domain {
+    pub mod dto;
+}
+
+mod handler {
+    pub mod auth;
+    pub mod health;
+    pub mod receipt;
+    pub mod router;
+}
+
+const SERVICE_NAME: &str = "RECEIPT_SERVICE";
+#[tokio::main]
+async fn main() {
+    init(SERVICE_NAME);
+    tracing::info!("starting receipt service ...!");
+
+    init_http_client();
+    init_auth();
+    let config = AppConfig::from_env();
+    let template = ReceiptTemplate::from_env().expect("load receipt template");
+    let usecase = Usecase::new(
+        Arc::new(FileReceiptStore::from_env()),
+        Arc::new(RestTransferRepository),
+        template,
+    );
+
+    let app = routes(AppState { usecase });
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", config.port))
+        .await
+        .expect("bind receipt service port");
+    tracing::info!("receipt service listening on {}", config.port);
+    axum::serve(listener, app).await.expect("run receipt service");
 }
@@ -0,0 +1,112 @@
+use crate::render::template::Line;
+
+const PAGE_WIDTH: u32 = 595;
+const PAGE_HEIGHT: u32 = 842;
+const MARGIN: u32 = 56;
+
+/// Lays the lines out on A4 pages in Helvetica and returns the PDF document.
+///
+/// Receipts are short text documents, so a minimal PDF 1.4 writer with the
+/// standard fonts is all that is needed.
+pub fn render(lines: &[Line]) -> Vec<u8> {
+    let pages = paginate(lines);
+    let mut objects: Vec<String> = Vec::new();
+
+    // 1 catalog, 2 page tree, 3 and 4 fonts, then a page and its content per page
+    let kids = (0..pages.len())
+        .map(|i| format!("{} 0 R", 5 + i * 2))
+        .collect::<Vec<_>>()
+        .join(" ");
+    objects.push("<< /Type /Catalog /Pages 2 0 R >>".to_string());
+    objects.push(format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        kids,
+        pages.len()
+    ));
+    objects.push(
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    );
+    objects.push(
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    );
+    for (i, content) in pages.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH,
+            PAGE_HEIGHT,
+            6 + i * 2
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}\nendstream",
+            content.len(),
+            content
+        ));
+    }
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+    }
+    let xref = pdf.len();
+    pdf.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    pdf.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        )
+        .as_bytes(),
+    );
+    pdf
+}
+
+/// content streams, one per page
+fn paginate(lines: &[Line]) -> Vec<String> {
+    let mut pages = Vec::new();
+    let mut content = String::new();
+    let mut y = PAGE_HEIGHT - MARGIN;
+    for line in lines {
+        let (font, size, leading) = match line {
+            Line::Title(_) => ("F2", 18, 30),
+            Line::Heading(_) => ("F2", 13, 22),
+            Line::Text(_) => ("F1", 11, 16),
+            Line::Blank => ("F1", 11, 10),
+        };
+        if y < MARGIN + leading {
+            pages.push(std::mem::take(&mut content));
+            y = PAGE_HEIGHT - MARGIN;
+        }
+        y -= leading;
+        if let Line::Title(text) | Line::Heading(text) | Line::Text(text) = line {
+            content.push_str(&format!(
+                "BT /{} {} Tf {} {} Td ({}) Tj ET\n",
+                font,
+                size,
+                MARGIN,
+                y,
+                escape(text)
+            ));
+        }
+    }
+    pages.push(content);
+    pages
+}
+
+/// PDF string literal escaping; characters outside Latin-1 become `?`
+fn escape(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\\' | '(' | ')' => format!("\\{}", c),
+            c if (' '..='~').contains(&c) => c.to_string(),
+            c if (c as u32) <= 0xFF => format!("\\{:03o}", c as u32),
+            _ => "?".to_string(),
+        })
+        .collect()
+}
@@ -0,0 +1,122 @@
+use domain::receipt::receipt::Receipt;
+use std::collections::HashMap;
+
+/// Built-in layout, used when no template file is configured.
+const DEFAULT_TEMPLATE: &str = include_str!("../../templates/receipt.txt");
+
+/// A line of a rendered receipt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Line {
+    Title(String),
+    Heading(String),
+    Text(String),
+    Blank,
+}
+
+/// Plain-text receipt layout with `{{field}}` placeholders.
+///
+/// `# ` starts the title and `## ` a heading. A line whose placeholders all
+/// render empty is left out, so optional fields such as the exchange rate only
+/// show when the receipt has them.
+#[derive(Debug, Clone)]
+pub struct ReceiptTemplate {
+    source: String,
+}
+
+impl ReceiptTemplate {
+    pub fn new(source: &str) -> Self {
+        Self {
+            source: source.to_string(),
+        }
+    }
+
+    /// `RECEIPT_TEMPLATE_FILE` when set, the built-in layout otherwise
+    pub fn from_env() -> anyhow::Result<Self> {
+        match std::env::var("RECEIPT_TEMPLATE_FILE") {
+            Ok(path) => {
+                let source = std::fs::read_to_string(&path)
+                    .map_err(|e| anyhow::anyhow!("Failed to read template {}: {}", path, e))?;
+                tracing::info!("loaded receipt template from {}", path);
+                Ok(Self::new(&source))
+            }
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    pub fn render(&self, receipt: &Receipt) -> Vec<Line> {
+        let fields = Self::fields(receipt);
+        self.source
+            .lines()
+            .filter_map(|line| {
+                let (text, placeholders, filled) = Self::substitute(line, &fields);
+                if placeholders > 0 && filled == 0 {
+                    return None;
+                }
+                Some(if let Some(title) = text.strip_prefix("# ") {
+                    Line::Title(title.to_string())
+                } else if let Some(heading) = text.strip_prefix("## ") {
+                    Line::Heading(heading.to_string())
+                } else if text.trim().is_empty() {
+                    Line::Blank
+                } else {
+                    Line::Text(text)
+                })
+            })
+            .collect()
+    }
+
+    fn fields(receipt: &Receipt) -> HashMap<&'static str, String> {
+        let conversion = receipt.conversion;
+        HashMap::from([
+            ("transaction_id", receipt.transaction_id.clone()),
+            (
+                "original_transaction_id",
+                receipt.original_transaction_id.clone().unwrap_or_default(),
+            ),
+            ("status", format!("{:?}", receipt.status)),
+            ("execution_time", receipt.execution_time.clone()),
+            ("sender", receipt.sender.clone()),
+            ("receiver", receipt.receiver.clone()),
+            ("user_email", receipt.user_email.clone()),
+            ("amount", receipt.amount.to_string()),
+            (
+                "credit_amount",
+                conversion.map(|c| c.credit_amount.to_string()).unwrap_or_default(),
+            ),
+            (
+                "applied_rate",
+                conversion.map(|c| c.applied_rate.to_string()).unwrap_or_default(),
+            ),
+        ])
+    }
+
+    /// the line with its placeholders replaced, how many it had and how
+    /// many rendered non-empty; unknown placeholders render empty
+    fn substitute(line: &str, fields: &HashMap<&'static str, String>) -> (String, usize, usize) {
+        let mut out = String::with_capacity(line.len());
+        let (mut placeholders, mut filled) = (0, 0);
+        let mut rest = line;
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start + 2..].find("}}") else {
+                break;
+            };
+            out.push_str(&rest[..start]);
+            let name = rest[start + 2..start + 2 + len].trim();
+            let value = fields.get(name).map(String::as_str).unwrap_or_default();
+            placeholders += 1;
+            if !value.is_empty() {
+                filled += 1;
+            }
+            out.push_str(value);
+            rest = &rest[start + 4 + len..];
+        }
+        out.push_str(rest);
+        (out, placeholders, filled)
+    }
+}
+
+impl Default for ReceiptTemplate {
+    fn default() -> Self {
+        Self::new(DEFAULT_TEMPLATE)
+    }
+}
@@ -0,0 +1,30 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use domain::base::base::BaseResponse;
+use domain::transfer::transfer::Transfer;
+use lib::http_client::client::get_json;
+use lib::http_client::error::HttpClientError;
+use mockall::automock;
+
+/// Reads transfers from transfer-service, the source of truth for what a
+/// receipt states.
+#[derive(Debug, Clone)]
+pub struct RestTransferRepository;
+
+#[async_trait]
+pub trait TransferProvider: Send + Sync {
+    /// `None` when transfer-service does not know the transaction id
+    async fn get_transfer(&self, transaction_id: &str) -> Result<Option<Transfer>>;
+}
+
+#[automock]
+#[async_trait]
+impl TransferProvider for RestTransferRepository {
+    async fn get_transfer(&self, transaction_id: &str) -> Result<Option<Transfer>> {
+        match get_json::<BaseResponse<Transfer>>("transfer", &format!("/transfer/{}", transaction_id)).await {
+            Ok(response) => Ok(response.data),
+            Err(e) if e.downcast_ref::<HttpClientError>().is_some_and(HttpClientError::is_not_found) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use domain::receipt::receipt::Receipt;
+use mockall::automock;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A stored receipt with the details it was issued for.
+#[derive(Debug, Clone)]
+pub struct StoredReceipt {
+    /// `None` for receipts stored before their details were kept alongside the PDF
+    pub receipt: Option<Receipt>,
+    pub pdf: Vec<u8>,
+}
+
+/// Where rendered receipts are kept, keyed by transaction id.
+#[async_trait]
+pub trait ReceiptStore: Send + Sync {
+    /// Stores the receipt unless one is already stored for the transaction;
+    /// `false` when it was, the stored one is left untouched.
+    async fn put(&self, receipt: &Receipt, pdf: &[u8]) -> Result<bool>;
+    async fn get(&self, transaction_id: &str) -> Result<Option<StoredReceipt>>;
+}
+
+/// Receipts as `{transaction_id}.pdf` files in one directory, each with its
+/// details in `{transaction_id}.json`.
+#[derive(Debug, Clone)]
+pub struct FileReceiptStore {
+    dir: PathBuf,
+}
+
+/// tells apart the temporary files of concurrent writes
+static TMP_SEQ: AtomicU64 = AtomicU64::new(0);
+
+impl FileReceiptStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// `RECEIPT_STORAGE_DIR`, `data/receipts` by default
+    pub fn from_env() -> Self {
+        Self::new(std::env::var("RECEIPT_STORAGE_DIR").unwrap_or_else(|_| "data/receipts".to_string()))
+    }
+
+    fn path(&self, transaction_id: &str, extension: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", transaction_id, extension))
+    }
+
+    fn tmp_path(path: &Path) -> PathBuf {
+        let seq = TMP_SEQ.fetch_add(1, Ordering::Relaxed);
+        path.with_extension(format!("{}.{}.tmp", std::process::id(), seq))
+    }
+}
+
+#[automock]
+#[async_trait]
+impl ReceiptStore for FileReceiptStore {
+    async fn put(&self, receipt: &Receipt, pdf: &[u8]) -> Result<bool> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path = self.path(&receipt.transaction_id, "pdf");
+        let details = self.path(&receipt.transaction_id, "json");
+        let tmp = Self::tmp_path(&path);
+        let details_tmp = Self::tmp_path(&details);
+        tokio::fs::write(&tmp, pdf).await?;
+        tokio::fs::write(&details_tmp, serde_json::to_vec(receipt)?).await?;
+
+        // linking fails when the PDF exists, so the first write wins and is never
+        // replaced; readers never see a partial file
+        let claimed = tokio::fs::hard_link(&tmp, &path).await;
+        let _ = tokio::fs::remove_file(&tmp).await;
+        match claimed {
+            Ok(()) => {
+                tokio::fs::rename(&details_tmp, &details).await?;
+                tracing::info!("stored receipt {}", path.display());
+                Ok(true)
+            }
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&details_tmp).await;
+                if e.kind() == std::io::ErrorKind::AlreadyExists {
+                    tracing::warn!("receipt {} already stored", path.display());
+                    Ok(false)
+                } else {
+                    Err(e.into())
+                }
+            }
+        }
+    }
+
+    async fn get(&self, transaction_id: &str) -> Result<Option<StoredReceipt>> {
+        let pdf = match tokio::fs::read(self.path(transaction_id, "pdf")).await {
+            Ok(pdf) => pdf,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let receipt = match tokio::fs::read(self.path(transaction_id, "json")).await {
+            Ok(details) => Some(serde_json::from_slice(&details)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Some(StoredReceipt { receipt, pdf }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::base::base::AuditMetadata;
+    use domain::money::money::{Currency, Money};
+    use domain::transfer::transfer::Transfer;
+
+    #[tokio::test]
+    async fn first_stored_receipt_is_kept() {
+        let dir = std::env::temp_dir().join(format!("receipts-{}", std::process::id()));
+        let store = FileReceiptStore::new(&dir);
+        let transfer = Transfer::new("1", "2", Money::new(10_000, Currency::IDR), AuditMetadata::new());
+        let first = Receipt::for_transfer(&transfer, "first@b.c");
+        let second = Receipt::for_transfer(&transfer, "second@b.c");
+
+        assert!(store.put(&first, b"first").await.unwrap());
+        assert!(!store.put(&second, b"second").await.unwrap());
+
+        let stored = store.get(&transfer.transaction_id).await.unwrap().unwrap();
+        assert_eq!(stored.pdf, b"first");
+        assert_eq!(stored.receipt.unwrap().user_email, "first@b.c");
+        assert!(store.get("unknown").await.unwrap().is_none());
+        let leftovers = std::fs::read_dir(&dir).unwrap().count();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(leftovers, 2);
+    }
+}
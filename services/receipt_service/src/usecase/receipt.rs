@@ -0,0 +1,167 @@
+use crate::render::pdf;
+use crate::render::template::ReceiptTemplate;
+use crate::repository::http::transfer_gateway::TransferProvider;
+use crate::repository::storage::file::{ReceiptStore, StoredReceipt};
+use anyhow::Result;
+use domain::receipt::error::ReceiptError;
+use domain::receipt::receipt::Receipt;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct Usecase {
+    store: Arc<dyn ReceiptStore>,
+    transfers: Arc<dyn TransferProvider>,
+    template: Arc<ReceiptTemplate>,
+}
+
+pub trait ReceiptGenerator {
+    async fn create_receipt(&self, transaction_id: &str, user_email: &str) -> Result<Receipt>;
+    async fn get_receipt(&self, transaction_id: &str) -> Result<StoredReceipt>;
+}
+
+impl Usecase {
+    pub fn new(
+        store: Arc<dyn ReceiptStore>,
+        transfers: Arc<dyn TransferProvider>,
+        template: ReceiptTemplate,
+    ) -> Self {
+        Self {
+            store,
+            transfers,
+            template: Arc::new(template),
+        }
+    }
+
+    /// transaction ids name the stored files, so only safe characters are allowed
+    fn validate_transaction_id(transaction_id: &str) -> Result<(), ReceiptError> {
+        let valid = !transaction_id.is_empty()
+            && transaction_id.len() <= 64
+            && !transaction_id.starts_with('.')
+            && transaction_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'));
+        if valid {
+            Ok(())
+        } else {
+            Err(ReceiptError::InvalidTransactionId(transaction_id.to_string()))
+        }
+    }
+}
+
+impl ReceiptGenerator for Usecase {
+    /// Renders the receipt of a completed transfer, as transfer-service
+    /// reports it, and stores its PDF. A transfer gets one receipt; asking
+    /// again is refused and the stored one kept.
+    async fn create_receipt(&self, transaction_id: &str, user_email: &str) -> Result<Receipt> {
+        tracing::info!("create receipt for transfer {:?}", transaction_id);
+        Self::validate_transaction_id(transaction_id)?;
+        let transfer = self
+            .transfers
+            .get_transfer(transaction_id)
+            .await?
+            .ok_or_else(|| ReceiptError::TransferNotFound(transaction_id.to_string()))?;
+        if !Receipt::is_completed(transfer.status) {
+            return Err(ReceiptError::NotCompleted(transfer.status).into());
+        }
+        let receipt = Receipt::for_transfer(&transfer, user_email);
+        let document = pdf::render(&self.template.render(&receipt));
+        if !self.store.put(&receipt, &document).await? {
+            return Err(ReceiptError::AlreadyIssued(transaction_id.to_string()).into());
+        }
+        Ok(receipt)
+    }
+
+    async fn get_receipt(&self, transaction_id: &str) -> Result<StoredReceipt> {
+        Self::validate_transaction_id(transaction_id)?;
+        self.store
+            .get(transaction_id)
+            .await?
+            .ok_or_else(|| ReceiptError::ReceiptNotFound(transaction_id.to_string()).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::http::transfer_gateway::MockRestTransferRepository;
+    use crate::repository::storage::file::MockFileReceiptStore;
+    use domain::base::base::AuditMetadata;
+    use domain::money::money::{Currency, Money};
+    use domain::transfer::transfer::{Transfer, TransferStatus};
+    use mockall::predicate::eq;
+
+    fn committed() -> Transfer {
+        let mut transfer = Transfer::new("1", "2", Money::new(10_000, Currency::IDR), AuditMetadata::new())
+            .with_transaction_id("tx-1".to_string());
+        transfer.mark_reserved("sender debited").unwrap();
+        transfer.mark_committed("receiver credited").unwrap();
+        transfer
+    }
+
+    fn usecase(store: MockFileReceiptStore, transfers: MockRestTransferRepository) -> Usecase {
+        Usecase::new(Arc::new(store), Arc::new(transfers), ReceiptTemplate::default())
+    }
+
+    #[tokio::test]
+    async fn receipt_states_the_transfer_loaded_from_transfer_service() {
+        let mut transfers = MockRestTransferRepository::new();
+        transfers
+            .expect_get_transfer()
+            .with(eq("tx-1"))
+            .returning(|_| Ok(Some(committed())));
+        let mut store = MockFileReceiptStore::new();
+        store
+            .expect_put()
+            .withf(|receipt, pdf| receipt.sender == "1" && receipt.receiver == "2" && !pdf.is_empty())
+            .times(1)
+            .returning(|_, _| Ok(true));
+
+        let receipt = usecase(store, transfers).create_receipt("tx-1", "a@b.c").await.unwrap();
+
+        assert_eq!(receipt.status, TransferStatus::Committed);
+        assert_eq!(receipt.amount, Money::new(10_000, Currency::IDR));
+    }
+
+    #[tokio::test]
+    async fn unknown_transfer_gets_no_receipt() {
+        let mut transfers = MockRestTransferRepository::new();
+        transfers.expect_get_transfer().returning(|_| Ok(None));
+        let mut store = MockFileReceiptStore::new();
+        store.expect_put().never();
+
+        let err = usecase(store, transfers).create_receipt("tx-1", "").await.unwrap_err();
+
+        assert!(matches!(err.downcast_ref(), Some(ReceiptError::TransferNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn pending_transfer_gets_no_receipt() {
+        let mut transfers = MockRestTransferRepository::new();
+        transfers.expect_get_transfer().returning(|_| {
+            let mut transfer = committed();
+            transfer.status = TransferStatus::Reserved;
+            Ok(Some(transfer))
+        });
+        let mut store = MockFileReceiptStore::new();
+        store.expect_put().never();
+
+        let err = usecase(store, transfers).create_receipt("tx-1", "").await.unwrap_err();
+
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(ReceiptError::NotCompleted(TransferStatus::Reserved))
+        ));
+    }
+
+    #[tokio::test]
+    async fn issued_receipt_is_not_replaced() {
+        let mut transfers = MockRestTransferRepository::new();
+        transfers.expect_get_transfer().returning(|_| Ok(Some(committed())));
+        let mut store = MockFileReceiptStore::new();
+        store.expect_put().returning(|_, _| Ok(false));
+
+        let err = usecase(store, transfers).create_receipt("tx-1", "").await.unwrap_err();
+
+        assert!(matches!(err.downcast_ref(), Some(ReceiptError::AlreadyIssued(_))));
+    }
+}
@@ -0,0 +1,29 @@
+use crate::usecase::registration::Usecase;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone)]
+pub struct AppState {
+    pub usecase: Usecase,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AppConfig {
+    pub port: u16,
+    /// link sent in verification mails
+    pub verification_url: String,
+}
+
+impl AppConfig {
+    pub fn from_env() -> Self {
+        dotenvy::dotenv().ok();
+        let port = std::env::var("PORT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(8081);
+        Self {
+            port,
+            verification_url: std::env::var("VERIFICATION_URL")
+                .unwrap_or_else(|_| format!("http://localhost:{}/registrations/verify", port)),
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use domain::user::user::User;
+use domain::wallet::wallet::Wallet;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+pub struct SignUpRequest {
+    pub email: String,
+    pub name: String,
+}
+
+/// token from the verification mail, in the body or as `?token=`
+#[derive(Debug, Deserialize)]
+pub struct VerifyRequest {
+    pub token: String,
+}
+
+/// a verified user and the wallet provisioned for them
+#[derive(Debug, Serialize)]
+pub struct Verified {
+    pub user: User,
+    pub wallet: Wallet,
+}
@@ -0,0 +1,14 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum RegistrationError {
+    #[error("Verification token is invalid")]
+    InvalidToken,
+    #[error("Verification token has expired")]
+    TokenExpired,
+    /// the user was deactivated after signing up, verifying does not revive them
+    #[error("User {0} is inactive")]
+    UserInactive(i32),
+    #[error("User {0} not found")]
+    UserNotFound(i32),
+}
@@ -0,0 +1,95 @@
+use crate::app::AppState;
+use crate::domain::dto::{SignUpRequest, Verified, VerifyRequest};
+use crate::domain::error::RegistrationError;
+use crate::usecase::registration::Registration;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use domain::base::base::BaseResponse;
+use domain::user::error::UserError;
+use domain::user::user::User;
+use lib::http_client::error::HttpClientError;
+use lib::trace::trace_id::current_trace_id;
+
+/// Signs a user up
+/// request :
+///   - email
+///   - name
+///
+/// The user stays Pending until the link mailed to them is followed.
+pub async fn sign_up(
+    State(state): State<AppState>,
+    Json(request): Json<SignUpRequest>,
+) -> (StatusCode, Json<BaseResponse<User>>) {
+    tracing::info!("sign up for request: {:?}", request);
+    registration_response(
+        state.usecase.sign_up(&request.email, &request.name).await,
+        StatusCode::CREATED,
+    )
+}
+
+/// Verifies the email with the token of the verification mail, given in the
+/// body; activates the user and provisions their wallet.
+pub async fn verify(
+    State(state): State<AppState>,
+    Json(request): Json<VerifyRequest>,
+) -> (StatusCode, Json<BaseResponse<Verified>>) {
+    registration_response(state.usecase.verify(&request.token).await, StatusCode::OK)
+}
+
+/// Same as [`verify`], for the link of the mail: `?token=`.
+pub async fn verify_link(
+    State(state): State<AppState>,
+    Query(request): Query<VerifyRequest>,
+) -> (StatusCode, Json<BaseResponse<Verified>>) {
+    registration_response(state.usecase.verify(&request.token).await, StatusCode::OK)
+}
+
+fn registration_response<T>(
+    result: anyhow::Result<T>,
+    success: StatusCode,
+) -> (StatusCode, Json<BaseResponse<T>>) {
+    match result {
+        Ok(data) => {
+            let response = BaseResponse::new(current_trace_id(), "Success".to_string(), Some(data));
+            (success, Json::from(response))
+        }
+        Err(e) => {
+            let (status, code, message) = if let Some(error) = e.downcast_ref::<RegistrationError>() {
+                let (status, code) = match error {
+                    RegistrationError::InvalidToken => (StatusCode::BAD_REQUEST, "INVALID_TOKEN"),
+                    RegistrationError::TokenExpired => (StatusCode::GONE, "TOKEN_EXPIRED"),
+                    RegistrationError::UserInactive(_) => (StatusCode::CONFLICT, "INVALID_STATUS"),
+                    RegistrationError::UserNotFound(_) => (StatusCode::NOT_FOUND, "NOT_FOUND"),
+                };
+                (status, code.to_string(), error.to_string())
+            } else if let Some(error @ (UserError::InvalidEmail(_) | UserError::EmptyName)) =
+                e.downcast_ref::<UserError>()
+            {
+                (StatusCode::BAD_REQUEST, "INVALID_USER".to_string(), error.to_string())
+            } else if let Some(error) = e.downcast_ref::<HttpClientError>()
+                && error.is_client_error()
+            {
+                // rejections of user-service, e.g. EMAIL_TAKEN, are passed on as is
+                let status = error
+                    .status()
+                    .and_then(|s| StatusCode::from_u16(s).ok())
+                    .unwrap_or(StatusCode::BAD_REQUEST);
+                (
+                    status,
+                    error.error_code().unwrap_or("UPSTREAM_REJECTED").to_string(),
+                    error.upstream_message().unwrap_or("Rejected").to_string(),
+                )
+            } else {
+                tracing::error!("registration request failed: {:#}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "INTERNAL_ERROR".to_string(),
+                    "Internal server error".to_string(),
+                )
+            };
+            let response = BaseResponse::error(current_trace_id(), &code, message);
+            (status, Json::from(response))
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use crate::app::AppState;
+use crate::handler::health::health_check;
+use crate::handler::registration::{sign_up, verify, verify_link};
+use axum::routing::{get, post};
+use axum::Router;
+use lib::trace::trace_id::TraceIdLayer;
+
+pub fn routes(app_state: AppState) -> Router {
+    Router::new()
+        .route("/health", get(health_check))
+        .route("/registrations", post(sign_up))
+        .route("/registrations/verify", post(verify).get(verify_link))
+        .layer(TraceIdLayer)
+        .with_state(app_state)
+}
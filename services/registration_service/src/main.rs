@@ -0,0 +1,61 @@
+mod app;
+
+use crate::app::{AppConfig, AppState};
+use crate::handler::router::routes;
+use crate::repository::http::{user_gateway, wallet_gateway};
+use crate::repository::mail::sender;
+use crate::security::token::VerificationTokens;
+use crate::usecase::registration::Usecase;
+use lib::auth::jwt::init_auth;
+use lib::http_client::client::init_http_client;
+use lib::log::logging::init;
+use std::sync::Arc;
+
+mod repository {
+    pub mod http;
+    pub mod mail;
+}
+
+mod security {
+    pub mod token;
+}
+
+mod usecase {
+    pub mod registration;
+}
+mod domain {
+    pub mod dto;
+    pub mod error;
+}
+
+mod handler {
+    pub mod health;
+    pub mod registration;
+    pub mod router;
+}
+
+const SERVICE_NAME: &str = "REGISTRATION_SERVICE";
+#[tokio::main]
+async fn main() {
+    init(SERVICE_NAME);
+    tracing::info!("starting registration service ...!");
+
+    let config = AppConfig::from_env();
+    init_http_client();
+    init_auth();
+    let tokens = VerificationTokens::from_env().expect("load verification token settings");
+    let usecase = Usecase::new(
+        Arc::new(user_gateway::RestRepository),
+        Arc::new(wallet_gateway::RestRepository),
+        sender::from_env(),
+        tokens,
+        config.verification_url.clone(),
+    );
+
+    let app = routes(AppState { usecase });
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", config.port))
+        .await
+        .expect("bind registration service port");
+    tracing::info!("registration service listening on {}", config.port);
+    axum::serve(listener, app).await.expect("run registration service");
+}
@@ -0,0 +1,2 @@
+pub mod user_gateway;
+pub mod wallet_gateway;
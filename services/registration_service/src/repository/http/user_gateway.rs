@@ -0,0 +1,56 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use domain::base::base::BaseResponse;
+use domain::user::user::User;
+use lib::http_client::client::{get_json, post_json};
+use lib::http_client::error::HttpClientError;
+use mockall::automock;
+use serde::Serialize;
+
+#[derive(Debug, Clone)]
+pub struct RestRepository;
+
+#[derive(Debug, Serialize)]
+struct RegisterUserRequest<'a> {
+    email: &'a str,
+    name: &'a str,
+    pending: bool,
+}
+
+/// Users on user-service; registration only ever creates pending users and
+/// activates them once verified.
+#[async_trait]
+pub trait UserGateway: Send + Sync {
+    async fn register_pending(&self, email: &str, name: &str) -> Result<User>;
+    /// `None` when user-service does not know the user
+    async fn find_user_by_id(&self, user_id: i32) -> Result<Option<User>>;
+    async fn activate(&self, user_id: i32) -> Result<User>;
+}
+
+#[automock]
+#[async_trait]
+impl UserGateway for RestRepository {
+    async fn register_pending(&self, email: &str, name: &str) -> Result<User> {
+        let body = RegisterUserRequest { email, name, pending: true };
+        let response: BaseResponse<User> = post_json("user", "/users", &body).await?;
+        response
+            .data
+            .ok_or_else(|| anyhow::anyhow!("Empty register response: {}", response.message))
+    }
+
+    async fn find_user_by_id(&self, user_id: i32) -> Result<Option<User>> {
+        match get_json::<BaseResponse<User>>("user", &format!("/users/{}", user_id)).await {
+            Ok(response) => Ok(response.data),
+            Err(e) if e.downcast_ref::<HttpClientError>().is_some_and(HttpClientError::is_not_found) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn activate(&self, user_id: i32) -> Result<User> {
+        let path = format!("/users/{}/activate", user_id);
+        let response: BaseResponse<User> = post_json("user", &path, &()).await?;
+        response
+            .data
+            .ok_or_else(|| anyhow::anyhow!("Empty activate response: {}", response.message))
+    }
+}
@@ -0,0 +1,28 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use domain::base::base::BaseResponse;
+use domain::wallet::wallet::Wallet;
+use lib::http_client::client::get_json;
+use mockall::automock;
+
+#[derive(Debug, Clone)]
+pub struct RestRepository;
+
+#[async_trait]
+pub trait WalletGateway: Send + Sync {
+    /// Wallet of the user, created by wallet-service on first inquiry;
+    /// safe to call again for a user that already has one.
+    async fn provision(&self, user_id: i32) -> Result<Wallet>;
+}
+
+#[automock]
+#[async_trait]
+impl WalletGateway for RestRepository {
+    async fn provision(&self, user_id: i32) -> Result<Wallet> {
+        let path = format!("/wallet/inquiry/{}", user_id);
+        let response: BaseResponse<Wallet> = get_json("wallet", &path).await?;
+        response
+            .data
+            .ok_or_else(|| anyhow::anyhow!("Empty wallet response: {}", response.message))
+    }
+}
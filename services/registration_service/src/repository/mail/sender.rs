@@ -0,0 +1,98 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+use mockall::automock;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[async_trait]
+pub trait MailSender: Send + Sync {
+    async fn send(&self, mail: &Mail) -> Result<()>;
+}
+
+/// Builds the sender named by `MAIL_SENDER`: `file` writes to
+/// `MAIL_OUTBOX_DIR`, anything else logs the mail.
+pub fn from_env() -> Arc<dyn MailSender> {
+    let from = std::env::var("MAIL_FROM").unwrap_or_else(|_| "no-reply@simplewallet.local".to_string());
+    match std::env::var("MAIL_SENDER").as_deref() {
+        Ok("file") => {
+            let dir = std::env::var("MAIL_OUTBOX_DIR").unwrap_or_else(|_| "data/outbox".to_string());
+            Arc::new(FileMailSender::new(dir, from))
+        }
+        _ => Arc::new(StdoutMailSender::new(from)),
+    }
+}
+
+fn format_mail(from: &str, mail: &Mail) -> String {
+    format!(
+        "From: {}\nTo: {}\nDate: {}\nSubject: {}\n\n{}\n",
+        from,
+        mail.to,
+        Utc::now().to_rfc2822(),
+        mail.subject,
+        mail.body
+    )
+}
+
+/// Prints mails instead of sending them, for local runs.
+#[derive(Debug, Clone)]
+pub struct StdoutMailSender {
+    from: String,
+}
+
+impl StdoutMailSender {
+    pub fn new(from: impl Into<String>) -> Self {
+        Self { from: from.into() }
+    }
+}
+
+#[automock]
+#[async_trait]
+impl MailSender for StdoutMailSender {
+    async fn send(&self, mail: &Mail) -> Result<()> {
+        let mut stdout = tokio::io::stdout();
+        stdout.write_all(format_mail(&self.from, mail).as_bytes()).await?;
+        stdout.flush().await?;
+        Ok(())
+    }
+}
+
+/// Drops every mail as an `.eml` file in one directory, for local runs and tests.
+#[derive(Debug, Clone)]
+pub struct FileMailSender {
+    dir: PathBuf,
+    from: String,
+}
+
+impl FileMailSender {
+    pub fn new(dir: impl Into<PathBuf>, from: impl Into<String>) -> Self {
+        Self {
+            dir: dir.into(),
+            from: from.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl MailSender for FileMailSender {
+    async fn send(&self, mail: &Mail) -> Result<()> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let name = format!(
+            "{}-{}.eml",
+            Utc::now().format("%Y%m%dT%H%M%S%.6f"),
+            mail.to.replace(|c: char| !c.is_ascii_alphanumeric() && c != '.', "_")
+        );
+        let path = self.dir.join(name);
+        tokio::fs::write(&path, format_mail(&self.from, mail)).await?;
+        tracing::info!("mail to {} written to {}", mail.to, path.display());
+        Ok(())
+    }
+}
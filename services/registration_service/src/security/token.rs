@@ -0,0 +1,119 @@
+use crate::domain::error::RegistrationError;
+use chrono::{Duration, Utc};
+use domain::user::user::User;
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+
+/// audience of verification tokens, so no other token signed with the same
+/// secret is accepted as one
+const AUDIENCE: &str = "email-verification";
+
+/// What a verification token proves: the holder received the mail sent to
+/// `email` for user `sub`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct VerificationClaims {
+    pub sub: i32,
+    pub email: String,
+    pub aud: String,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+/// Issues and checks the HS256-signed, expiring tokens sent in verification mails.
+#[derive(Clone)]
+pub struct VerificationTokens {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    ttl: Duration,
+}
+
+impl VerificationTokens {
+    pub fn new(secret: &[u8], ttl: Duration) -> Self {
+        Self {
+            encoding: EncodingKey::from_secret(secret),
+            decoding: DecodingKey::from_secret(secret),
+            ttl,
+        }
+    }
+
+    /// `VERIFICATION_TOKEN_SECRET` (required) and
+    /// `VERIFICATION_TOKEN_TTL_MINUTES`, 60 by default
+    pub fn from_env() -> anyhow::Result<Self> {
+        let secret = std::env::var("VERIFICATION_TOKEN_SECRET")
+            .map_err(|_| anyhow::anyhow!("VERIFICATION_TOKEN_SECRET is not set"))?;
+        if secret.is_empty() {
+            anyhow::bail!("VERIFICATION_TOKEN_SECRET is empty");
+        }
+        let ttl_minutes = std::env::var("VERIFICATION_TOKEN_TTL_MINUTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60);
+        Ok(Self::new(secret.as_bytes(), Duration::minutes(ttl_minutes)))
+    }
+
+    /// Token for a registered user, valid for the configured ttl.
+    pub fn issue(&self, user: &User) -> anyhow::Result<String> {
+        let user_id = user
+            .id
+            .ok_or_else(|| anyhow::anyhow!("cannot issue a token for an unsaved user"))?;
+        let now = Utc::now();
+        let claims = VerificationClaims {
+            sub: user_id,
+            email: user.email.clone(),
+            aud: AUDIENCE.to_string(),
+            iat: now.timestamp(),
+            exp: (now + self.ttl).timestamp(),
+        };
+        Ok(jsonwebtoken::encode(&Header::new(Algorithm::HS256), &claims, &self.encoding)?)
+    }
+
+    pub fn verify(&self, token: &str) -> Result<VerificationClaims, RegistrationError> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_audience(&[AUDIENCE]);
+        validation.leeway = 0;
+        jsonwebtoken::decode::<VerificationClaims>(token, &self.decoding, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| match e.kind() {
+                ErrorKind::ExpiredSignature => RegistrationError::TokenExpired,
+                _ => RegistrationError::InvalidToken,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user() -> User {
+        User {
+            id: Some(7),
+            ..User::pending("Jane@Example.com", "Jane")
+        }
+    }
+
+    #[test]
+    fn issued_tokens_verify() {
+        let tokens = VerificationTokens::new(b"secret", Duration::minutes(5));
+        let claims = tokens.verify(&tokens.issue(&user()).unwrap()).unwrap();
+        assert_eq!(claims.sub, 7);
+        assert_eq!(claims.email, "jane@example.com");
+    }
+
+    #[test]
+    fn expired_tokens_are_rejected() {
+        let tokens = VerificationTokens::new(b"secret", Duration::minutes(-1));
+        let token = tokens.issue(&user()).unwrap();
+        assert!(matches!(tokens.verify(&token), Err(RegistrationError::TokenExpired)));
+    }
+
+    #[test]
+    fn tokens_signed_with_another_secret_are_rejected() {
+        let token = VerificationTokens::new(b"other", Duration::minutes(5))
+            .issue(&user())
+            .unwrap();
+        let tokens = VerificationTokens::new(b"secret", Duration::minutes(5));
+        assert!(matches!(tokens.verify(&token), Err(RegistrationError::InvalidToken)));
+        assert!(matches!(tokens.verify("garbage"), Err(RegistrationError::InvalidToken)));
+    }
+}
@@ -0,0 +1,96 @@
+use crate::domain::dto::Verified;
+use crate::domain::error::RegistrationError;
+use crate::repository::http::user_gateway::UserGateway;
+use crate::repository::http::wallet_gateway::WalletGateway;
+use crate::repository::mail::sender::{Mail, MailSender};
+use crate::security::token::VerificationTokens;
+use anyhow::Result;
+use domain::user::user::{User, UserStatus};
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct Usecase {
+    users: Arc<dyn UserGateway>,
+    wallets: Arc<dyn WalletGateway>,
+    mailer: Arc<dyn MailSender>,
+    tokens: Arc<VerificationTokens>,
+    /// link in the mail, the token is appended as `?token=`
+    verification_url: String,
+}
+
+pub trait Registration {
+    async fn sign_up(&self, email: &str, name: &str) -> Result<User>;
+    async fn verify(&self, token: &str) -> Result<Verified>;
+}
+
+impl Usecase {
+    pub fn new(
+        users: Arc<dyn UserGateway>,
+        wallets: Arc<dyn WalletGateway>,
+        mailer: Arc<dyn MailSender>,
+        tokens: VerificationTokens,
+        verification_url: String,
+    ) -> Self {
+        Self {
+            users,
+            wallets,
+            mailer,
+            tokens: Arc::new(tokens),
+            verification_url,
+        }
+    }
+
+    fn verification_mail(&self, user: &User, token: &str) -> Mail {
+        Mail {
+            to: user.email.clone(),
+            subject: "Verify your SimpleWallet email".to_string(),
+            body: format!(
+                "Hi {},\n\nConfirm your email to activate your SimpleWallet account:\n\n{}?token={}\n\nThe link expires; signing up again with the same email sends a new one.",
+                user.name, self.verification_url, token
+            ),
+        }
+    }
+}
+
+impl Registration for Usecase {
+    /// Registers a pending user on user-service and mails them a verification
+    /// link. Signing up again while still pending mails a fresh link.
+    async fn sign_up(&self, email: &str, name: &str) -> Result<User> {
+        let user = User::pending(email, name);
+        user.validate()?;
+        let user = self.users.register_pending(&user.email, &user.name).await?;
+        tracing::info!("registered pending user {:?}", user.id);
+
+        let token = self.tokens.issue(&user)?;
+        self.mailer.send(&self.verification_mail(&user, &token)).await?;
+        Ok(user)
+    }
+
+    /// Activates the user the token was issued for and provisions their wallet.
+    ///
+    /// Verifying twice is harmless: an already active user only gets their
+    /// wallet back. A user deactivated since signing up stays inactive.
+    async fn verify(&self, token: &str) -> Result<Verified> {
+        let claims = self.tokens.verify(token)?;
+        let user = self
+            .users
+            .find_user_by_id(claims.sub)
+            .await?
+            .ok_or(RegistrationError::UserNotFound(claims.sub))?;
+        // the token proves ownership of the email it was sent to only
+        if user.email != claims.email {
+            return Err(RegistrationError::InvalidToken.into());
+        }
+
+        let user = match user.status {
+            UserStatus::Pending => {
+                tracing::info!("email verified for user {}", claims.sub);
+                self.users.activate(claims.sub).await?
+            }
+            UserStatus::Active => user,
+            UserStatus::Inactive => return Err(RegistrationError::UserInactive(claims.sub).into()),
+        };
+        let wallet = self.wallets.provision(claims.sub).await?;
+        Ok(Verified { user, wallet })
+    }
+}
@@ -0,0 +1,37 @@
+-- transfers and the audit trail of their status changes
+CREATE SCHEMA IF NOT EXISTS TRANSFER_DIGITAL;
+
+DO $$ BEGIN
+    CREATE TYPE currency AS ENUM ('IDR', 'USD');
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+DO $$ BEGIN
+    CREATE TYPE transfer_status AS ENUM ('Initiated', 'Reserved', 'Committed', 'Failed', 'Reversed', 'Expired');
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+CREATE TABLE IF NOT EXISTS TRANSFER_DIGITAL.DATA_TRANSFER (
+    id             SERIAL PRIMARY KEY,
+    transaction_id VARCHAR(64)     NOT NULL UNIQUE,
+    account_debit  VARCHAR(32)     NOT NULL,
+    account_credit VARCHAR(32)     NOT NULL,
+    currency       currency        NOT NULL,
+    amount         NUMERIC(20,2)   NOT NULL CHECK (amount > 0),
+    conversion     JSONB,
+    status         transfer_status NOT NULL DEFAULT 'Initiated',
+    created_date   TIMESTAMPTZ     NOT NULL DEFAULT now(),
+    updated_date   TIMESTAMPTZ
+);
+
+CREATE TABLE IF NOT EXISTS TRANSFER_DIGITAL.TRANSFER_TRANSITION (
+    id           BIGSERIAL PRIMARY KEY,
+    transfer_id  INTEGER         NOT NULL REFERENCES TRANSFER_DIGITAL.DATA_TRANSFER (id),
+    from_status  transfer_status NOT NULL,
+    to_status    transfer_status NOT NULL,
+    reason       TEXT            NOT NULL,
+    created_date TIMESTAMPTZ     NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS transfer_transition_transfer_idx
+    ON TRANSFER_DIGITAL.TRANSFER_TRANSITION (transfer_id);
@@ -0,0 +1,7 @@
+-- a reversal is a compensating transfer linked to the transfer it undoes
+ALTER TABLE TRANSFER_DIGITAL.DATA_TRANSFER
+    ADD COLUMN IF NOT EXISTS reversal_of VARCHAR(64)
+        REFERENCES TRANSFER_DIGITAL.DATA_TRANSFER (transaction_id);
+
+CREATE INDEX IF NOT EXISTS data_transfer_reversal_of_idx
+    ON TRANSFER_DIGITAL.DATA_TRANSFER (reversal_of) WHERE reversal_of IS NOT NULL;
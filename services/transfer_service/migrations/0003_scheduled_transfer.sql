@@ -0,0 +1,46 @@
+-- standing orders and the outcome of every attempt to execute them
+DO $$ BEGIN
+    CREATE TYPE schedule_status AS ENUM ('Active', 'Paused', 'Completed', 'Cancelled');
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+DO $$ BEGIN
+    CREATE TYPE execution_outcome AS ENUM ('Committed', 'Pending', 'Retrying', 'Skipped');
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+CREATE TABLE IF NOT EXISTS TRANSFER_DIGITAL.SCHEDULED_TRANSFER (
+    id                  SERIAL PRIMARY KEY,
+    from_id             INTEGER         NOT NULL,
+    to_id               INTEGER         NOT NULL,
+    currency            currency        NOT NULL,
+    amount              NUMERIC(20,2)   NOT NULL CHECK (amount > 0),
+    recurrence          JSONB           NOT NULL,
+    max_attempts        INTEGER         NOT NULL CHECK (max_attempts > 0),
+    retry_delay_seconds INTEGER         NOT NULL,
+    next_run_at         TIMESTAMPTZ     NOT NULL,
+    attempts            INTEGER         NOT NULL DEFAULT 0,
+    retry_at            TIMESTAMPTZ,
+    status              schedule_status NOT NULL DEFAULT 'Active',
+    created_date        TIMESTAMPTZ     NOT NULL DEFAULT now(),
+    updated_date        TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS scheduled_transfer_due_idx
+    ON TRANSFER_DIGITAL.SCHEDULED_TRANSFER (COALESCE(retry_at, next_run_at)) WHERE status = 'Active';
+CREATE INDEX IF NOT EXISTS scheduled_transfer_from_idx
+    ON TRANSFER_DIGITAL.SCHEDULED_TRANSFER (from_id);
+
+CREATE TABLE IF NOT EXISTS TRANSFER_DIGITAL.SCHEDULE_EXECUTION (
+    id             BIGSERIAL PRIMARY KEY,
+    schedule_id    INTEGER           NOT NULL REFERENCES TRANSFER_DIGITAL.SCHEDULED_TRANSFER (id),
+    scheduled_for  TIMESTAMPTZ       NOT NULL,
+    attempt        INTEGER           NOT NULL,
+    transaction_id VARCHAR(64),
+    outcome        execution_outcome NOT NULL,
+    message        TEXT              NOT NULL,
+    created_date   TIMESTAMPTZ       NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS schedule_execution_schedule_idx
+    ON TRANSFER_DIGITAL.SCHEDULE_EXECUTION (schedule_id);
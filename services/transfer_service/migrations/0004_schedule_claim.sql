@@ -0,0 +1,3 @@
+-- executor instance working on a schedule holds it until claimed_until, so
+-- concurrent executors never run the same occurrence
+ALTER TABLE TRANSFER_DIGITAL.SCHEDULED_TRANSFER ADD COLUMN IF NOT EXISTS claimed_until TIMESTAMPTZ;
@@ -0,0 +1,2 @@
+-- transfers whose credit and refund were both rejected wait for an operator
+ALTER TYPE transfer_status ADD VALUE IF NOT EXISTS 'NeedsIntervention';
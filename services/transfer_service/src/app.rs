@@ -0,0 +1,45 @@
+use crate::usecase::schedule::ScheduleUsecase;
+use crate::usecase::transfer::Usecase;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone)]
+pub struct AppState {
+    pub usecase: Usecase,
+    pub schedules: ScheduleUsecase,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AppConfig {
+    pub port: u16,
+    /// how often sagas left in flight are picked up again
+    pub recovery_interval_seconds: u64,
+    /// how long a saga must have been left untouched before recovery drives
+    /// it, so requests still working on it are not raced
+    pub recovery_grace_seconds: u64,
+    /// how often due scheduled transfers are looked for
+    pub schedule_interval_seconds: u64,
+}
+
+impl AppConfig {
+    pub fn from_env() -> Self {
+        dotenvy::dotenv().ok();
+        Self {
+            port: std::env::var("PORT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(8084),
+            recovery_interval_seconds: std::env::var("RECOVERY_INTERVAL_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+            recovery_grace_seconds: std::env::var("RECOVERY_GRACE_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(120),
+            schedule_interval_seconds: std::env::var("SCHEDULE_INTERVAL_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60),
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use domain::money::money::Money;
+use chrono::{DateTime, Utc};
+use domain::receipt::receipt::Receipt;
+use domain::transfer::schedule::{Recurrence, RetryPolicy};
+use domain::transfer::transfer::Transfer;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TransferRequest {
+    pub from_id: i32,
+    /// receiver by user id, or by account number with `to_norek`; exactly one is given
+    #[serde(default)]
+    pub to_id: Option<i32>,
+    #[serde(default)]
+    pub to_norek: Option<String>,
+    pub amount: Money,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct ReversalRequest {
+    /// amount to give back in the credited currency, everything left when omitted
+    #[serde(default)]
+    pub amount: Option<Money>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ReversalResponse {
+    pub transfer: Transfer,
+    /// issued once the reversal is Committed
+    pub receipt: Option<Receipt>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ScheduleRequest {
+    pub from_id: i32,
+    pub to_id: i32,
+    pub amount: Money,
+    pub recurrence: Recurrence,
+    /// first occurrence, later ones keep its time of day
+    pub first_run_at: DateTime<Utc>,
+    /// retry policy for failed occurrences, the default when omitted
+    #[serde(default)]
+    pub policy: Option<RetryPolicy>,
+}
+
+/// fields left out are unchanged
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct ScheduleUpdateRequest {
+    #[serde(default)]
+    pub amount: Option<Money>,
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    #[serde(default)]
+    pub next_run_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub policy: Option<RetryPolicy>,
+    /// `true` pauses the schedule, `false` resumes it
+    #[serde(default)]
+    pub paused: Option<bool>,
+}
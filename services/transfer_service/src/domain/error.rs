@@ -0,0 +1,169 @@
+use axum::http::StatusCode;
+use axum::Json;
+use domain::base::base::BaseResponse;
+use domain::transfer::error::TransferError;
+use lib::auth::error::AuthError;
+use lib::http_client::error::HttpClientError;
+use lib::trace::trace_id::current_trace_id;
+use thiserror::Error;
+
+/// Errors surfaced by transfer-service handlers. Each maps to one HTTP status
+/// and a stable `error_code`; upstream and internal details are only logged.
+#[derive(Debug, Error)]
+pub enum TransferServiceError {
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    InvalidAmount(String),
+    #[error("{0}")]
+    InvalidRequest(String),
+    #[error("{0}")]
+    InvalidRecipient(String),
+    #[error("{0}")]
+    Conflict(String),
+    /// the transfer or schedule is not in a state that allows the request
+    #[error("{0}")]
+    Rejected(String),
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    Forbidden(String),
+    /// wallet-service answered 4xx, its status, code and message are passed on
+    #[error("{message}")]
+    UpstreamRejected {
+        status: StatusCode,
+        code: String,
+        message: String,
+    },
+    #[error("Upstream call failed: {0}")]
+    Upstream(String),
+    #[error(transparent)]
+    Internal(anyhow::Error),
+}
+
+impl TransferServiceError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            TransferServiceError::NotFound(_) => StatusCode::NOT_FOUND,
+            TransferServiceError::InvalidAmount(_) => StatusCode::BAD_REQUEST,
+            TransferServiceError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            TransferServiceError::InvalidRecipient(_) => StatusCode::BAD_REQUEST,
+            TransferServiceError::Conflict(_) => StatusCode::CONFLICT,
+            TransferServiceError::Rejected(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            TransferServiceError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            TransferServiceError::Forbidden(_) => StatusCode::FORBIDDEN,
+            TransferServiceError::UpstreamRejected { status, .. } => *status,
+            TransferServiceError::Upstream(_) => StatusCode::BAD_GATEWAY,
+            TransferServiceError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> &str {
+        match self {
+            TransferServiceError::NotFound(_) => "NOT_FOUND",
+            TransferServiceError::InvalidAmount(_) => "INVALID_AMOUNT",
+            TransferServiceError::InvalidRequest(_) => "INVALID_REQUEST",
+            TransferServiceError::InvalidRecipient(_) => "INVALID_RECIPIENT",
+            TransferServiceError::Conflict(_) => "CONFLICT",
+            TransferServiceError::Rejected(_) => "TRANSFER_REJECTED",
+            TransferServiceError::Unauthorized(_) => "UNAUTHORIZED",
+            TransferServiceError::Forbidden(_) => "FORBIDDEN",
+            TransferServiceError::UpstreamRejected { code, .. } => code,
+            TransferServiceError::Upstream(_) => "UPSTREAM_ERROR",
+            TransferServiceError::Internal(_) => "INTERNAL_ERROR",
+        }
+    }
+
+    /// message safe to show to clients
+    fn public_message(&self) -> String {
+        match self {
+            TransferServiceError::Upstream(_) => "Upstream service unavailable".to_string(),
+            TransferServiceError::Internal(_) => "Internal server error".to_string(),
+            other => other.to_string(),
+        }
+    }
+
+    /// Status and error body for any response type.
+    pub fn to_response<T>(&self) -> (StatusCode, Json<BaseResponse<T>>) {
+        let status = self.status();
+        if status.is_server_error() {
+            tracing::error!("{}: {:#}", self.code(), self);
+        } else {
+            tracing::warn!("{}: {}", self.code(), self);
+        }
+        let response = BaseResponse::error(current_trace_id(), self.code(), self.public_message());
+        (status, Json::from(response))
+    }
+}
+
+impl From<AuthError> for TransferServiceError {
+    fn from(value: AuthError) -> Self {
+        match value {
+            AuthError::Forbidden(message) => TransferServiceError::Forbidden(message),
+            AuthError::InvalidConfig(_) => TransferServiceError::Internal(value.into()),
+            other => TransferServiceError::Unauthorized(other.public_message()),
+        }
+    }
+}
+
+impl From<TransferError> for TransferServiceError {
+    fn from(value: TransferError) -> Self {
+        let message = value.to_string();
+        match value {
+            TransferError::TransferNotFound(_) | TransferError::ScheduleNotFound(_) => {
+                TransferServiceError::NotFound(message)
+            }
+            TransferError::InvalidAmount(_)
+            | TransferError::InvalidReversalAmount(_)
+            | TransferError::Amount(_) => TransferServiceError::InvalidAmount(message),
+            TransferError::SameParties | TransferError::InvalidSchedule(_) => {
+                TransferServiceError::InvalidRequest(message)
+            }
+            TransferError::InvalidRecipient(_) => TransferServiceError::InvalidRecipient(message),
+            TransferError::DuplicateTransaction(_) | TransferError::ConcurrentTransition(_, _) => {
+                TransferServiceError::Conflict(message)
+            }
+            TransferError::IllegalTransition(_, _)
+            | TransferError::NotReversible(_)
+            | TransferError::ReversalOfReversal(_)
+            | TransferError::ReversalExceedsTransfer(_, _)
+            | TransferError::ScheduleClosed(_) => TransferServiceError::Rejected(message),
+        }
+    }
+}
+
+impl From<HttpClientError> for TransferServiceError {
+    fn from(value: HttpClientError) -> Self {
+        if !value.is_client_error() {
+            return TransferServiceError::Upstream(value.to_string());
+        }
+        // e.g. a failed check digit (400) or an unknown account number (404)
+        TransferServiceError::UpstreamRejected {
+            status: value
+                .status()
+                .and_then(|s| StatusCode::from_u16(s).ok())
+                .unwrap_or(StatusCode::BAD_REQUEST),
+            code: value.error_code().unwrap_or("UPSTREAM_REJECTED").to_string(),
+            message: value.upstream_message().unwrap_or("Rejected").to_string(),
+        }
+    }
+}
+
+/// Usecases report through `anyhow`; typed causes are recovered here and
+/// everything else is internal.
+impl From<anyhow::Error> for TransferServiceError {
+    fn from(value: anyhow::Error) -> Self {
+        let value = match value.downcast::<TransferError>() {
+            Ok(e) => return e.into(),
+            Err(value) => value,
+        };
+        let value = match value.downcast::<AuthError>() {
+            Ok(e) => return e.into(),
+            Err(value) => value,
+        };
+        match value.downcast::<HttpClientError>() {
+            Ok(e) => e.into(),
+            Err(value) => TransferServiceError::Internal(value),
+        }
+    }
+}
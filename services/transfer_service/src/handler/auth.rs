@@ -0,0 +1,13 @@
+use crate::domain::error::TransferServiceError;
+use axum::http::StatusCode;
+use axum::Json;
+use domain::base::base::BaseResponse;
+use lib::auth::error::AuthError;
+
+/// resource name of the `transfer:admin` scope, which may act on any transfer
+pub const TRANSFER: &str = "transfer";
+
+/// Response of a caller that may not act on the transfer or schedule.
+pub fn denied<T>(e: AuthError) -> (StatusCode, Json<BaseResponse<T>>) {
+    TransferServiceError::from(e).to_response()
+}
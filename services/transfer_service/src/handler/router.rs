@@ -0,0 +1,31 @@
+use crate::app::AppState;
+use crate::handler::health::{dependencies, health_check};
+use crate::handler::schedule::{
+    cancel_schedule, create_schedule, get_schedule, get_schedule_executions, get_user_schedules,
+    update_schedule,
+};
+use crate::handler::transfer::{
+    create_transfer, get_pending_transfers, get_transfer, reverse_transfer,
+};
+use axum::routing::{get, post};
+use axum::Router;
+use lib::trace::trace_id::TraceIdLayer;
+
+pub fn routes(app_state: AppState) -> Router {
+    Router::new()
+        .route("/health", get(health_check))
+        .route("/health/dependencies", get(dependencies))
+        .route("/transfer", post(create_transfer))
+        .route("/transfer/{transaction_id}", get(get_transfer))
+        .route("/transfer/{transaction_id}/reversal", post(reverse_transfer))
+        .route("/transfer/user/{user_id}/pending", get(get_pending_transfers))
+        .route("/schedule", post(create_schedule))
+        .route(
+            "/schedule/{id}",
+            get(get_schedule).put(update_schedule).delete(cancel_schedule),
+        )
+        .route("/schedule/{id}/executions", get(get_schedule_executions))
+        .route("/schedule/user/{user_id}", get(get_user_schedules))
+        .layer(TraceIdLayer)
+        .with_state(app_state)
+}
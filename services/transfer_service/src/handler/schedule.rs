@@ -0,0 +1,133 @@
+use crate::app::AppState;
+use crate::domain::dto::{ScheduleRequest, ScheduleUpdateRequest};
+use crate::domain::error::TransferServiceError;
+use crate::handler::auth::{denied, TRANSFER};
+use crate::usecase::schedule::TransferScheduler;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use domain::base::base::BaseResponse;
+use lib::auth::extractor::AuthUser;
+use lib::trace::trace_id::current_trace_id;
+use domain::transfer::schedule::{ScheduleExecution, ScheduledTransfer};
+
+/// Creates a scheduled transfer
+/// request :
+///   - sender id
+///   - receiver id
+///   - amount
+///   - recurrence (once, daily, weekly or monthly on a day)
+///   - first run time
+///   - retry policy (optional)
+///
+/// Due occurrences are executed by the background executor through the
+/// normal transfer saga. Schedules are only managed by their sender, or a
+/// `transfer:admin`.
+pub async fn create_schedule(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(request): Json<ScheduleRequest>,
+) -> (StatusCode, Json<BaseResponse<ScheduledTransfer>>) {
+    tracing::info!("create schedule for request: {:?}", request);
+    if let Err(e) = auth.ensure_owner(TRANSFER, request.from_id) {
+        return denied(e);
+    }
+    let result = state
+        .schedules
+        .create_schedule(
+            request.from_id,
+            request.to_id,
+            request.amount,
+            request.recurrence,
+            request.first_run_at,
+            request.policy.unwrap_or_default(),
+        )
+        .await;
+    schedule_response(result)
+}
+
+/// Retrieves a scheduled transfer by its id.
+pub async fn get_schedule(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<i32>,
+) -> (StatusCode, Json<BaseResponse<ScheduledTransfer>>) {
+    tracing::info!("inquiry schedule for id: {:?}", id);
+    let result = state.schedules.get_schedule(id).await.and_then(|schedule| {
+        auth.ensure_owner(TRANSFER, schedule.from_id)?;
+        Ok(schedule)
+    });
+    schedule_response(result)
+}
+
+/// Lists the scheduled transfers sent by a user.
+pub async fn get_user_schedules(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(user_id): Path<i32>,
+) -> (StatusCode, Json<BaseResponse<Vec<ScheduledTransfer>>>) {
+    tracing::info!("inquiry schedules for user id: {:?}", user_id);
+    if let Err(e) = auth.ensure_owner(TRANSFER, user_id) {
+        return denied(e);
+    }
+    schedule_response(state.schedules.get_schedules_by_user(user_id).await)
+}
+
+/// Updates a scheduled transfer; also pauses or resumes it.
+pub async fn update_schedule(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<i32>,
+    Json(request): Json<ScheduleUpdateRequest>,
+) -> (StatusCode, Json<BaseResponse<ScheduledTransfer>>) {
+    let result = async {
+        ensure_schedule_owner(&state, &auth, id).await?;
+        state.schedules.update_schedule(id, request).await
+    };
+    schedule_response(result.await)
+}
+
+/// Cancels a scheduled transfer, no further occurrences are executed.
+pub async fn cancel_schedule(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<i32>,
+) -> (StatusCode, Json<BaseResponse<ScheduledTransfer>>) {
+    let result = async {
+        ensure_schedule_owner(&state, &auth, id).await?;
+        state.schedules.cancel_schedule(id).await
+    };
+    schedule_response(result.await)
+}
+
+/// Lists every execution attempt of a scheduled transfer and its outcome.
+pub async fn get_schedule_executions(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<i32>,
+) -> (StatusCode, Json<BaseResponse<Vec<ScheduleExecution>>>) {
+    let result = async {
+        ensure_schedule_owner(&state, &auth, id).await?;
+        state.schedules.get_executions(id).await
+    };
+    schedule_response(result.await)
+}
+
+/// schedules are managed by their sender, or a `transfer:admin`
+async fn ensure_schedule_owner(state: &AppState, auth: &AuthUser, id: i32) -> anyhow::Result<()> {
+    if auth.is_admin(TRANSFER) {
+        return Ok(());
+    }
+    let schedule = state.schedules.get_schedule(id).await?;
+    Ok(auth.ensure_owner(TRANSFER, schedule.from_id)?)
+}
+
+fn schedule_response<T>(result: anyhow::Result<T>) -> (StatusCode, Json<BaseResponse<T>>) {
+    match result {
+        Ok(data) => {
+            let response = BaseResponse::new(current_trace_id(), "Success".to_string(), Some(data));
+            (StatusCode::OK, Json::from(response))
+        }
+        Err(e) => TransferServiceError::from(e).to_response(),
+    }
+}
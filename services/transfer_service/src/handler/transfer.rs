@@ -0,0 +1,154 @@
+use crate::app::AppState;
+use crate::domain::dto::{ReversalRequest, ReversalResponse, TransferRequest};
+use crate::domain::error::TransferServiceError;
+use crate::handler::auth::{denied, TRANSFER};
+use crate::usecase::transfer::TransferSaga;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use domain::base::base::BaseResponse;
+use lib::auth::error::AuthError;
+use lib::auth::extractor::AuthUser;
+use lib::trace::trace_id::current_trace_id;
+use domain::transfer::transfer::{Transfer, TransferStatus};
+
+/// Starts a transfer saga
+/// request :
+///   - sender id
+///   - receiver id, or receiver account number (norek)
+///   - amount
+///
+/// The transfer is stored as Initiated, then the sender is debited and the
+/// receiver credited through wallet-service. If the credit is rejected the
+/// debit is refunded and the transfer ends Failed.
+///
+/// Responds 200 when Committed, 422 when Failed and 202 when a step is still
+/// pending or the transfer waits for an operator; pending transfers are
+/// finished by the background recovery. A non-positive amount, the sender as
+/// receiver, or a missing, doubled or malformed receiver answers 400; an
+/// account number wallet-service rejects or does not know answers with its status.
+/// Only the sender, or a `transfer:admin`, may start a transfer.
+pub async fn create_transfer(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(request): Json<TransferRequest>,
+) -> (StatusCode, Json<BaseResponse<Transfer>>) {
+    tracing::info!("create transfer for request: {:?}", request);
+    if let Err(e) = auth.ensure_owner(TRANSFER, request.from_id) {
+        return denied(e);
+    }
+    let to_id = match state
+        .usecase
+        .resolve_recipient(request.to_id, request.to_norek.as_deref())
+        .await
+    {
+        Ok(to_id) => to_id,
+        Err(e) => return TransferServiceError::from(e).to_response(),
+    };
+    match state
+        .usecase
+        .create_transfer(request.from_id, to_id, request.amount)
+        .await
+    {
+        Ok(data) => {
+            let status = match data.status {
+                TransferStatus::Committed => StatusCode::OK,
+                TransferStatus::Initiated | TransferStatus::Reserved | TransferStatus::NeedsIntervention => {
+                    StatusCode::ACCEPTED
+                }
+                _ => StatusCode::UNPROCESSABLE_ENTITY,
+            };
+            let message = format!("{:?}", data.status);
+            (status, Json::from(BaseResponse::new(current_trace_id(), message, Some(data))))
+        }
+        Err(e) => TransferServiceError::from(e).to_response(),
+    }
+}
+
+/// Retrieves a transfer and its status history by transaction id, for its
+/// sender, its receiver or a `transfer:admin`; 404 when unknown.
+pub async fn get_transfer(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(transaction_id): Path<String>,
+) -> (StatusCode, Json<BaseResponse<Transfer>>) {
+    tracing::info!("inquiry transfer for transaction id: {:?}", transaction_id);
+    match state.usecase.get_transfer(&transaction_id).await {
+        Ok(data)
+            if !auth.is_admin(TRANSFER)
+                && auth.subject != data.account_debit
+                && auth.subject != data.account_credit =>
+        {
+            denied(AuthError::Forbidden(format!("Not a party to transfer {}", transaction_id)))
+        }
+        Ok(data) => {
+            let response = BaseResponse::new(current_trace_id(), "Success".to_string(), Some(data));
+            (StatusCode::OK, Json::from(response))
+        }
+        Err(e) => TransferServiceError::from(e).to_response(),
+    }
+}
+
+/// Lists the user's transfers, sent or received, that are still Initiated
+/// or Reserved. Wallet-service asks before closing a wallet.
+pub async fn get_pending_transfers(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(user_id): Path<i32>,
+) -> (StatusCode, Json<BaseResponse<Vec<Transfer>>>) {
+    tracing::info!("inquiry pending transfers for user id: {:?}", user_id);
+    if let Err(e) = auth.ensure_owner(TRANSFER, user_id) {
+        return denied(e);
+    }
+    match state.usecase.get_pending_transfers(user_id).await {
+        Ok(data) => {
+            let response = BaseResponse::new(current_trace_id(), "Success".to_string(), Some(data));
+            (StatusCode::OK, Json::from(response))
+        }
+        Err(e) => TransferServiceError::from(e).to_response(),
+    }
+}
+
+/// Reverses a committed transfer, fully or partially
+/// request :
+///   - amount (optional, in the credited currency; everything left when omitted)
+///
+/// A linked compensating transfer takes the amount back from the receiver and
+/// refunds the sender. Reversals never add up to more than was transferred;
+/// once they cover all of it the original transfer becomes Reversed.
+///
+/// Responds like a transfer (200, 202 or 422) with the reversal, and its
+/// receipt once Committed, 404 for an unknown transfer, 400 for an invalid amount and 422
+/// when the transfer cannot be reversed by that amount.
+/// Needs `transfer:admin`.
+pub async fn reverse_transfer(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(transaction_id): Path<String>,
+    request: Option<Json<ReversalRequest>>,
+) -> (StatusCode, Json<BaseResponse<ReversalResponse>>) {
+    let Json(request) = request.unwrap_or_default();
+    tracing::info!("reverse transfer {:?} for request: {:?}", transaction_id, request);
+    if let Err(e) = auth.ensure_admin(TRANSFER) {
+        return denied(e);
+    }
+    match state
+        .usecase
+        .reverse_transfer(&transaction_id, request.amount)
+        .await
+    {
+        Ok((transfer, receipt)) => {
+            let status = match transfer.status {
+                TransferStatus::Committed => StatusCode::OK,
+                TransferStatus::Initiated | TransferStatus::Reserved | TransferStatus::NeedsIntervention => {
+                    StatusCode::ACCEPTED
+                }
+                _ => StatusCode::UNPROCESSABLE_ENTITY,
+            };
+            let message = format!("{:?}", transfer.status);
+            let data = ReversalResponse { transfer, receipt };
+            (status, Json::from(BaseResponse::new(current_trace_id(), message, Some(data))))
+        }
+        Err(e) => TransferServiceError::from(e).to_response(),
+    }
+}
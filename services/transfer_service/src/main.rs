@@ -1,3 +1,88 @@
-fn main() {
-    println!("Hello, world!");
+mod app;
+
+use crate::app::{AppConfig, AppState};
+use crate::handler::router::routes;
+use crate::repository::db::postgres::TransferRepository;
+use crate::repository::db::schedule::ScheduleRepository;
+use crate::repository::http::receipt_gateway::RestReceiptRepository;
+use crate::repository::http::wallet_gateway::RestRepository;
+use crate::usecase::schedule::{ScheduleUsecase, TransferScheduler};
+use crate::usecase::transfer::{TransferSaga, Usecase};
+use lib::auth::jwt::init_auth;
+use lib::db::postgres::init_pool;
+use lib::http_client::client::init_http_client;
+use lib::log::logging::init;
+use lib::trace::trace_id::{new_trace_id, with_trace_id};
+use chrono::Utc;
+use std::time::Duration;
+
+mod repository {
+    pub mod db;
+    pub mod http;
+}
+
+mod usecase {
+    pub mod schedule;
+    pub mod transfer;
+}
+mod domain {
+    pub mod dto;
+    pub mod error;
+}
+
+mod handler {
+    pub mod auth;
+    pub mod health;
+    pub mod router;
+    pub mod schedule;
+    pub mod transfer;
+}
+
+const SERVICE_NAME: &str = "TRANSFER_SERVICE";
+#[tokio::main]
+async fn main() {
+    init(SERVICE_NAME);
+    tracing::info!("starting transfer service ...!");
+    init_http_client();
+    init_auth();
+
+    let config = AppConfig::from_env();
+    let pool = init_pool().clone();
+    let usecase = Usecase::new(TransferRepository::new(pool.clone()), RestRepository, RestReceiptRepository);
+    let schedules = ScheduleUsecase::new(ScheduleRepository::new(pool), usecase.clone());
+
+    // resume sagas interrupted by a crash, then keep retrying pending steps
+    let recovery = usecase.clone();
+    let interval = Duration::from_secs(config.recovery_interval_seconds);
+    let grace = chrono::Duration::seconds(config.recovery_grace_seconds as i64);
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let idle_since = Utc::now() - grace;
+            if let Err(e) = with_trace_id(new_trace_id(), recovery.resume_in_flight(idle_since)).await {
+                tracing::error!("saga recovery failed: {}", e);
+            }
+        }
+    });
+
+    // execute standing orders as they come due
+    let executor = schedules.clone();
+    let interval = Duration::from_secs(config.schedule_interval_seconds);
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = with_trace_id(new_trace_id(), executor.run_due(Utc::now())).await {
+                tracing::error!("scheduled transfer execution failed: {}", e);
+            }
+        }
+    });
+
+    let app = routes(AppState { usecase, schedules });
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", config.port))
+        .await
+        .expect("bind transfer service port");
+    tracing::info!("transfer service listening on {}", config.port);
+    axum::serve(listener, app).await.expect("run transfer service");
 }
@@ -0,0 +1,267 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{GenericClient, Transaction};
+use domain::base::base::AuditMetadata;
+use domain::money::exchange::Conversion;
+use domain::money::money::{Currency, Money};
+use domain::transfer::error::TransferError;
+use domain::transfer::transfer::{Transfer, TransferStatus, TransferTransition};
+use mockall::automock;
+use postgres_types::Json;
+use tokio_postgres::error::SqlState;
+use tokio_postgres::Row;
+
+const TRANSFER_COLUMNS: &str = "id, transaction_id, account_debit, account_credit, currency, amount, conversion, reversal_of, status, created_date, updated_date";
+
+/// reversals in these statuses gave nothing back and do not count as reversed
+const VOID_STATUSES: [TransferStatus; 2] = [TransferStatus::Failed, TransferStatus::Expired];
+
+#[derive(Debug, Clone)]
+pub struct TransferRepository {
+    pool: deadpool_postgres::Pool,
+}
+
+#[async_trait]
+pub trait TransferProvider {
+    async fn create_transfer(&self, transfer: &Transfer) -> Result<Transfer>;
+    async fn get_transfer_by_transaction_id(&self, transaction_id: &str) -> Result<Option<Transfer>>;
+    /// transfers currently in one of `statuses` and not changed since
+    /// `updated_before`, oldest first
+    async fn get_transfers_by_status(&self, statuses: &[TransferStatus], updated_before: DateTime<Utc>) -> Result<Vec<Transfer>>;
+    /// transfers in one of `statuses` sent or received by `account`, oldest first
+    async fn get_transfers_by_account(&self, account: &str, statuses: &[TransferStatus]) -> Result<Vec<Transfer>>;
+    /// Persists the transfer's latest transition. Fails with `ConcurrentTransition`
+    /// when the stored status is no longer the transition's starting point,
+    /// i.e. someone else moved it.
+    async fn save_transition(&self, transfer: &Transfer) -> Result<()>;
+    /// Stores a reversal of `transaction_id` for `amount` (everything left when
+    /// `None`). The original is locked while the already reversed total is
+    /// summed, so concurrent reversals cannot refund more than was transferred.
+    async fn create_reversal(&self, transaction_id: &str, amount: Option<Money>) -> Result<Transfer>;
+    /// reversals of `transaction_id`, oldest first
+    async fn get_reversals(&self, transaction_id: &str) -> Result<Vec<Transfer>>;
+}
+
+impl TransferRepository {
+    pub fn new(pool: deadpool_postgres::Pool) -> Self {
+        Self { pool }
+    }
+
+    fn transfer_from_row(row: &Row) -> Transfer {
+        let currency: Currency = row.get("currency");
+        let amount: Money = row.get("amount");
+        let conversion: Option<Json<Conversion>> = row.get("conversion");
+        Transfer {
+            id: Some(row.get("id")),
+            transaction_id: row.get("transaction_id"),
+            account_debit: row.get("account_debit"),
+            account_credit: row.get("account_credit"),
+            amount: amount.with_currency(currency),
+            conversion: conversion.map(|c| c.0),
+            reversal_of: row.get("reversal_of"),
+            status: row.get("status"),
+            transitions: Vec::new(),
+            audit: AuditMetadata {
+                created_date: row.get("created_date"),
+                updated_date: row.get("updated_date"),
+            },
+        }
+    }
+
+    async fn insert_transfer(tx: &Transaction<'_>, transfer: &Transfer) -> Result<Transfer> {
+        let currency = transfer.amount.currency();
+        let conversion = transfer.conversion.map(Json);
+
+        let row = tx.query_one(
+            &format!(
+                "INSERT INTO TRANSFER_DIGITAL.DATA_TRANSFER (transaction_id, account_debit, account_credit, currency, amount, conversion, reversal_of, status, created_date, updated_date)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+             RETURNING {}",
+                TRANSFER_COLUMNS
+            ),
+            &[
+                &transfer.transaction_id,
+                &transfer.account_debit,
+                &transfer.account_credit,
+                &currency,
+                &transfer.amount,
+                &conversion,
+                &transfer.reversal_of,
+                &transfer.status,
+                &transfer.audit.created_date,
+                &transfer.audit.updated_date,
+            ],
+        ).await?;
+        let transfer_id: i32 = row.get("id");
+
+        for transition in &transfer.transitions {
+            tx.execute(
+                "INSERT INTO TRANSFER_DIGITAL.TRANSFER_TRANSITION (transfer_id, from_status, to_status, reason, created_date)
+                 VALUES ($1, $2, $3, $4, $5)",
+                &[&transfer_id, &transition.from, &transition.to, &transition.reason, &transition.at],
+            ).await?;
+        }
+
+        let mut created = Self::transfer_from_row(&row);
+        created.transitions = transfer.transitions.clone();
+        Ok(created)
+    }
+}
+
+#[async_trait]
+#[automock]
+impl TransferProvider for TransferRepository {
+    async fn create_transfer(&self, transfer: &Transfer) -> Result<Transfer> {
+        tracing::info!("create transfer {:?}", transfer.transaction_id);
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
+        let created = Self::insert_transfer(&tx, transfer).await.map_err(|e| {
+            // a concurrent caller stored the same transaction id first
+            match e.downcast_ref::<tokio_postgres::Error>().and_then(|e| e.code()) {
+                Some(&SqlState::UNIQUE_VIOLATION) => TransferError::DuplicateTransaction(transfer.transaction_id.clone()).into(),
+                _ => e,
+            }
+        })?;
+        tx.commit().await?;
+        Ok(created)
+    }
+
+    async fn get_transfer_by_transaction_id(&self, transaction_id: &str) -> Result<Option<Transfer>> {
+        tracing::info!("get transfer by transaction id {:?}", transaction_id);
+        let client = self.pool.get().await?;
+        let row = client.query_opt(
+            &format!(
+                "SELECT {} FROM TRANSFER_DIGITAL.DATA_TRANSFER WHERE transaction_id = $1",
+                TRANSFER_COLUMNS
+            ),
+            &[&transaction_id],
+        ).await?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let mut transfer = Self::transfer_from_row(&row);
+        let transitions = client.query(
+            "SELECT from_status, to_status, reason, created_date
+             FROM TRANSFER_DIGITAL.TRANSFER_TRANSITION WHERE transfer_id = $1 ORDER BY id",
+            &[&transfer.id],
+        ).await?;
+        transfer.transitions = transitions
+            .iter()
+            .map(|t| TransferTransition {
+                from: t.get("from_status"),
+                to: t.get("to_status"),
+                reason: t.get("reason"),
+                at: t.get("created_date"),
+            })
+            .collect();
+        Ok(Some(transfer))
+    }
+
+    async fn get_transfers_by_status(&self, statuses: &[TransferStatus], updated_before: DateTime<Utc>) -> Result<Vec<Transfer>> {
+        tracing::info!("get transfers by status {:?} updated before {}", statuses, updated_before);
+        let client = self.pool.get().await?;
+        let statuses = statuses.to_vec();
+        let rows = client.query(
+            &format!(
+                "SELECT {} FROM TRANSFER_DIGITAL.DATA_TRANSFER WHERE status = ANY($1) AND updated_date < $2 ORDER BY id",
+                TRANSFER_COLUMNS
+            ),
+            &[&statuses, &updated_before],
+        ).await?;
+        Ok(rows.iter().map(Self::transfer_from_row).collect())
+    }
+
+    async fn get_transfers_by_account(&self, account: &str, statuses: &[TransferStatus]) -> Result<Vec<Transfer>> {
+        tracing::info!("get transfers of account {:?} by status {:?}", account, statuses);
+        let client = self.pool.get().await?;
+        let statuses = statuses.to_vec();
+        let rows = client.query(
+            &format!(
+                "SELECT {} FROM TRANSFER_DIGITAL.DATA_TRANSFER
+             WHERE status = ANY($1) AND (account_debit = $2 OR account_credit = $2) ORDER BY id",
+                TRANSFER_COLUMNS
+            ),
+            &[&statuses, &account],
+        ).await?;
+        Ok(rows.iter().map(Self::transfer_from_row).collect())
+    }
+
+    async fn save_transition(&self, transfer: &Transfer) -> Result<()> {
+        let transition = transfer
+            .last_transition()
+            .ok_or_else(|| anyhow!("Transfer {} has no transition to save", transfer.transaction_id))?;
+        tracing::info!(
+            "transfer {:?} {:?} -> {:?}: {}",
+            transfer.transaction_id,
+            transition.from,
+            transition.to,
+            transition.reason
+        );
+
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
+        let row = tx.query_opt(
+            "UPDATE TRANSFER_DIGITAL.DATA_TRANSFER SET status = $1, updated_date = $2
+             WHERE transaction_id = $3 AND status = $4
+             RETURNING id",
+            &[&transition.to, &transition.at, &transfer.transaction_id, &transition.from],
+        ).await?;
+        // another driver of the same saga saved this step first
+        let row = row.ok_or_else(|| {
+            TransferError::ConcurrentTransition(transfer.transaction_id.clone(), transition.from)
+        })?;
+        let transfer_id: i32 = row.get("id");
+
+        tx.execute(
+            "INSERT INTO TRANSFER_DIGITAL.TRANSFER_TRANSITION (transfer_id, from_status, to_status, reason, created_date)
+             VALUES ($1, $2, $3, $4, $5)",
+            &[&transfer_id, &transition.from, &transition.to, &transition.reason, &transition.at],
+        ).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn create_reversal(&self, transaction_id: &str, amount: Option<Money>) -> Result<Transfer> {
+        tracing::info!("create reversal of {:?} for {:?}", transaction_id, amount);
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
+
+        let row = tx.query_opt(
+            &format!(
+                "SELECT {} FROM TRANSFER_DIGITAL.DATA_TRANSFER WHERE transaction_id = $1 FOR UPDATE",
+                TRANSFER_COLUMNS
+            ),
+            &[&transaction_id],
+        ).await?;
+        let original = Self::transfer_from_row(&row.ok_or_else(|| TransferError::TransferNotFound(transaction_id.to_string()))?);
+
+        let void = VOID_STATUSES.to_vec();
+        let reversed = tx.query_one(
+            "SELECT COALESCE(SUM(amount), 0) AS reversed FROM TRANSFER_DIGITAL.DATA_TRANSFER
+             WHERE reversal_of = $1 AND NOT (status = ANY($2))",
+            &[&transaction_id, &void],
+        ).await?;
+        let reversed: Money = reversed.get("reversed");
+        let reversed = reversed.with_currency(original.credit_amount().currency());
+
+        let reversal = original.reversal(amount, reversed)?;
+        let created = Self::insert_transfer(&tx, &reversal).await?;
+        tx.commit().await?;
+        Ok(created)
+    }
+
+    async fn get_reversals(&self, transaction_id: &str) -> Result<Vec<Transfer>> {
+        tracing::info!("get reversals of {:?}", transaction_id);
+        let client = self.pool.get().await?;
+        let rows = client.query(
+            &format!(
+                "SELECT {} FROM TRANSFER_DIGITAL.DATA_TRANSFER WHERE reversal_of = $1 ORDER BY id",
+                TRANSFER_COLUMNS
+            ),
+            &[&transaction_id],
+        ).await?;
+        Ok(rows.iter().map(Self::transfer_from_row).collect())
+    }
+}
@@ -0,0 +1,285 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use deadpool_postgres::{GenericClient, Transaction};
+use domain::base::base::AuditMetadata;
+use domain::money::money::{Currency, Money};
+use domain::transfer::error::TransferError;
+use domain::transfer::schedule::{Recurrence, RetryPolicy, ScheduleExecution, ScheduleStatus, ScheduledTransfer};
+use mockall::automock;
+use postgres_types::Json;
+use tokio_postgres::Row;
+
+/// how long an executor may work on a claimed schedule before another one may take it over
+pub const CLAIM_SECONDS: i64 = 600;
+
+const SCHEDULE_COLUMNS: &str = "id, from_id, to_id, currency, amount, recurrence, max_attempts, retry_delay_seconds, next_run_at, attempts, retry_at, status, created_date, updated_date";
+
+#[derive(Debug, Clone)]
+pub struct ScheduleRepository {
+    pool: deadpool_postgres::Pool,
+}
+
+#[async_trait]
+pub trait ScheduleProvider {
+    async fn create_schedule(&self, schedule: &ScheduledTransfer) -> Result<ScheduledTransfer>;
+    async fn get_schedule(&self, id: i32) -> Result<Option<ScheduledTransfer>>;
+    /// schedules sending from the user, oldest first
+    async fn get_schedules_by_user(&self, user_id: i32) -> Result<Vec<ScheduledTransfer>>;
+    /// Claims the active schedules due at `now`, earliest first. A claimed
+    /// schedule is not handed to another executor until its execution is saved
+    /// or the claim lapses after [`CLAIM_SECONDS`].
+    async fn claim_due_schedules(&self, now: DateTime<Utc>) -> Result<Vec<ScheduledTransfer>>;
+    /// Stores the attempt and advances the schedule past it, releasing the
+    /// claim. Only the executor's fields are written, and only while the
+    /// schedule is still active: a pause, cancel or edit made meanwhile wins.
+    async fn save_execution(&self, schedule: &ScheduledTransfer, execution: &ScheduleExecution) -> Result<()>;
+    /// every recorded attempt of the schedule, oldest first
+    async fn get_executions(&self, id: i32) -> Result<Vec<ScheduleExecution>>;
+}
+
+impl ScheduleRepository {
+    pub fn new(pool: deadpool_postgres::Pool) -> Self {
+        Self { pool }
+    }
+
+    fn schedule_from_row(row: &Row) -> ScheduledTransfer {
+        let currency: Currency = row.get("currency");
+        let amount: Money = row.get("amount");
+        let recurrence: Json<Recurrence> = row.get("recurrence");
+        let max_attempts: i32 = row.get("max_attempts");
+        let retry_delay_seconds: i32 = row.get("retry_delay_seconds");
+        let attempts: i32 = row.get("attempts");
+        ScheduledTransfer {
+            id: Some(row.get("id")),
+            from_id: row.get("from_id"),
+            to_id: row.get("to_id"),
+            amount: amount.with_currency(currency),
+            recurrence: recurrence.0,
+            policy: RetryPolicy {
+                max_attempts: max_attempts.max(1) as u32,
+                retry_delay_seconds: retry_delay_seconds.max(0) as u32,
+            },
+            next_run_at: row.get("next_run_at"),
+            attempts: attempts.max(0) as u32,
+            retry_at: row.get("retry_at"),
+            status: row.get("status"),
+            audit: AuditMetadata {
+                created_date: row.get("created_date"),
+                updated_date: row.get("updated_date"),
+            },
+        }
+    }
+
+    /// counts and durations are unsigned in the domain, the columns are INTEGER
+    fn to_column(value: u32, field: &str) -> Result<i32> {
+        i32::try_from(value)
+            .map_err(|_| TransferError::InvalidSchedule(format!("{field} is too large, got {value}")).into())
+    }
+
+    async fn update_schedule(tx: &Transaction<'_>, schedule: &ScheduledTransfer) -> Result<()> {
+        let currency = schedule.amount.currency();
+        let recurrence = Json(schedule.recurrence);
+        let max_attempts = Self::to_column(schedule.policy.max_attempts, "max_attempts")?;
+        let retry_delay_seconds = Self::to_column(schedule.policy.retry_delay_seconds, "retry_delay_seconds")?;
+        let attempts = Self::to_column(schedule.attempts, "attempts")?;
+        tx.execute(
+            "UPDATE TRANSFER_DIGITAL.SCHEDULED_TRANSFER SET
+                currency = $1, amount = $2, recurrence = $3, max_attempts = $4, retry_delay_seconds = $5,
+                next_run_at = $6, attempts = $7, retry_at = $8, status = $9, updated_date = $10
+             WHERE id = $11",
+            &[
+                &currency,
+                &schedule.amount,
+                &recurrence,
+                &max_attempts,
+                &retry_delay_seconds,
+                &schedule.next_run_at,
+                &attempts,
+                &schedule.retry_at,
+                &schedule.status,
+                &schedule.audit.updated_date,
+                &schedule.id,
+            ],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Applies `change` to the schedule with its row locked, so it works on
+    /// the latest state and concurrent changes are applied one after another.
+    pub async fn modify_schedule(
+        &self,
+        id: i32,
+        change: impl FnOnce(&mut ScheduledTransfer) -> Result<(), TransferError> + Send,
+    ) -> Result<ScheduledTransfer> {
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
+        let row = tx.query_opt(
+            &format!("SELECT {} FROM TRANSFER_DIGITAL.SCHEDULED_TRANSFER WHERE id = $1 FOR UPDATE", SCHEDULE_COLUMNS),
+            &[&id],
+        ).await?
+            .ok_or(TransferError::ScheduleNotFound(id))?;
+        let mut schedule = Self::schedule_from_row(&row);
+        change(&mut schedule)?;
+        tracing::info!("save schedule {:?} status {:?}", schedule.id, schedule.status);
+        Self::update_schedule(&tx, &schedule).await?;
+        tx.commit().await?;
+        Ok(schedule)
+    }
+}
+
+#[async_trait]
+#[automock]
+impl ScheduleProvider for ScheduleRepository {
+    async fn create_schedule(&self, schedule: &ScheduledTransfer) -> Result<ScheduledTransfer> {
+        tracing::info!("create schedule from {} to {}", schedule.from_id, schedule.to_id);
+        let client = self.pool.get().await?;
+        let currency = schedule.amount.currency();
+        let recurrence = Json(schedule.recurrence);
+        let max_attempts = Self::to_column(schedule.policy.max_attempts, "max_attempts")?;
+        let retry_delay_seconds = Self::to_column(schedule.policy.retry_delay_seconds, "retry_delay_seconds")?;
+        let row = client.query_one(
+            &format!(
+                "INSERT INTO TRANSFER_DIGITAL.SCHEDULED_TRANSFER (from_id, to_id, currency, amount, recurrence, max_attempts, retry_delay_seconds, next_run_at, status, created_date)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+             RETURNING {}",
+                SCHEDULE_COLUMNS
+            ),
+            &[
+                &schedule.from_id,
+                &schedule.to_id,
+                &currency,
+                &schedule.amount,
+                &recurrence,
+                &max_attempts,
+                &retry_delay_seconds,
+                &schedule.next_run_at,
+                &schedule.status,
+                &schedule.audit.created_date,
+            ],
+        ).await?;
+        Ok(Self::schedule_from_row(&row))
+    }
+
+    async fn get_schedule(&self, id: i32) -> Result<Option<ScheduledTransfer>> {
+        tracing::info!("get schedule {}", id);
+        let client = self.pool.get().await?;
+        let row = client.query_opt(
+            &format!("SELECT {} FROM TRANSFER_DIGITAL.SCHEDULED_TRANSFER WHERE id = $1", SCHEDULE_COLUMNS),
+            &[&id],
+        ).await?;
+        Ok(row.as_ref().map(Self::schedule_from_row))
+    }
+
+    async fn get_schedules_by_user(&self, user_id: i32) -> Result<Vec<ScheduledTransfer>> {
+        tracing::info!("get schedules of user {}", user_id);
+        let client = self.pool.get().await?;
+        let rows = client.query(
+            &format!(
+                "SELECT {} FROM TRANSFER_DIGITAL.SCHEDULED_TRANSFER WHERE from_id = $1 ORDER BY id",
+                SCHEDULE_COLUMNS
+            ),
+            &[&user_id],
+        ).await?;
+        Ok(rows.iter().map(Self::schedule_from_row).collect())
+    }
+
+    async fn claim_due_schedules(&self, now: DateTime<Utc>) -> Result<Vec<ScheduledTransfer>> {
+        let client = self.pool.get().await?;
+        let status = ScheduleStatus::Active;
+        let claimed_until = now + Duration::seconds(CLAIM_SECONDS);
+        // SKIP LOCKED lets concurrent executors claim disjoint sets
+        let rows = client.query(
+            &format!(
+                "UPDATE TRANSFER_DIGITAL.SCHEDULED_TRANSFER SET claimed_until = $3
+             WHERE id IN (
+                SELECT id FROM TRANSFER_DIGITAL.SCHEDULED_TRANSFER
+                WHERE status = $1 AND COALESCE(retry_at, next_run_at) <= $2
+                  AND (claimed_until IS NULL OR claimed_until <= $2)
+                FOR UPDATE SKIP LOCKED
+             )
+             RETURNING {}",
+                SCHEDULE_COLUMNS
+            ),
+            &[&status, &now, &claimed_until],
+        ).await?;
+        let mut schedules: Vec<ScheduledTransfer> = rows.iter().map(Self::schedule_from_row).collect();
+        schedules.sort_by_key(ScheduledTransfer::due_at);
+        Ok(schedules)
+    }
+
+    async fn save_execution(&self, schedule: &ScheduledTransfer, execution: &ScheduleExecution) -> Result<()> {
+        tracing::info!(
+            "schedule {:?} attempt {} for {}: {:?}",
+            schedule.id,
+            execution.attempt,
+            execution.scheduled_for,
+            execution.outcome
+        );
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
+        let attempts = Self::to_column(schedule.attempts, "attempts")?;
+        let active = ScheduleStatus::Active;
+        let updated = tx.execute(
+            "UPDATE TRANSFER_DIGITAL.SCHEDULED_TRANSFER SET
+                next_run_at = $1, attempts = $2, retry_at = $3, status = $4, updated_date = $5, claimed_until = NULL
+             WHERE id = $6 AND status = $7",
+            &[
+                &schedule.next_run_at,
+                &attempts,
+                &schedule.retry_at,
+                &schedule.status,
+                &schedule.audit.updated_date,
+                &schedule.id,
+                &active,
+            ],
+        ).await?;
+        if updated == 0 {
+            tracing::warn!("schedule {:?} changed while executing, keeping the change", schedule.id);
+            tx.execute(
+                "UPDATE TRANSFER_DIGITAL.SCHEDULED_TRANSFER SET claimed_until = NULL WHERE id = $1",
+                &[&schedule.id],
+            ).await?;
+        }
+        let attempt = Self::to_column(execution.attempt, "attempt")?;
+        tx.execute(
+            "INSERT INTO TRANSFER_DIGITAL.SCHEDULE_EXECUTION (schedule_id, scheduled_for, attempt, transaction_id, outcome, message, created_date)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+            &[
+                &schedule.id,
+                &execution.scheduled_for,
+                &attempt,
+                &execution.transaction_id,
+                &execution.outcome,
+                &execution.message,
+                &execution.at,
+            ],
+        ).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+    async fn get_executions(&self, id: i32) -> Result<Vec<ScheduleExecution>> {
+        tracing::info!("get executions of schedule {}", id);
+        let client = self.pool.get().await?;
+        let rows = client.query(
+            "SELECT scheduled_for, attempt, transaction_id, outcome, message, created_date
+             FROM TRANSFER_DIGITAL.SCHEDULE_EXECUTION WHERE schedule_id = $1 ORDER BY id",
+            &[&id],
+        ).await?;
+        Ok(rows
+            .iter()
+            .map(|r| {
+                let attempt: i32 = r.get("attempt");
+                ScheduleExecution {
+                    scheduled_for: r.get("scheduled_for"),
+                    attempt: attempt.max(0) as u32,
+                    transaction_id: r.get("transaction_id"),
+                    outcome: r.get("outcome"),
+                    message: r.get("message"),
+                    at: r.get("created_date"),
+                }
+            })
+            .collect())
+    }
+}
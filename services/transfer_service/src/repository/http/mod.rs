@@ -0,0 +1,2 @@
+pub mod receipt_gateway;
+pub mod wallet_gateway;
@@ -0,0 +1,35 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use domain::base::base::BaseResponse;
+use domain::receipt::receipt::Receipt;
+use lib::http_client::client::post_json_idempotent;
+use mockall::automock;
+use serde::Serialize;
+
+#[derive(Debug, Clone)]
+pub struct RestReceiptRepository;
+
+#[derive(Debug, Serialize)]
+struct ReceiptRequest<'a> {
+    transaction_id: &'a str,
+}
+
+/// Receipts on receipt-service, which loads the transfer back from this
+/// service and issues one receipt per transaction id.
+#[async_trait]
+pub trait ReceiptGateway: Send + Sync {
+    /// Answers 409 when the transfer already has a receipt.
+    async fn issue_receipt(&self, transaction_id: &str) -> Result<Receipt>;
+}
+
+#[automock]
+#[async_trait]
+impl ReceiptGateway for RestReceiptRepository {
+    async fn issue_receipt(&self, transaction_id: &str) -> Result<Receipt> {
+        let body = ReceiptRequest { transaction_id };
+        let response: BaseResponse<Receipt> = post_json_idempotent("receipt", "/receipts", &body).await?;
+        response
+            .data
+            .ok_or_else(|| anyhow::anyhow!("Empty receipt response: {}", response.message))
+    }
+}
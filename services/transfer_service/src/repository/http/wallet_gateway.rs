@@ -0,0 +1,71 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use domain::base::base::BaseResponse;
+use domain::money::money::Money;
+use domain::wallet::wallet::Wallet;
+use lib::http_client::client::{get_json, post_json_idempotent};
+use mockall::automock;
+use serde::Serialize;
+
+#[derive(Debug, Clone)]
+pub struct RestRepository;
+
+#[derive(Debug, Serialize)]
+struct MovementRequest<'a> {
+    user_id: i32,
+    amount: Money,
+    reference: &'a str,
+    compensation: bool,
+}
+
+/// Debit and credit legs on wallet-service. Both are idempotent per `reference`,
+/// so a leg can be retried safely when the outcome of an earlier call is unknown.
+#[async_trait]
+pub trait WalletGateway: Send + Sync {
+    /// A `compensation` debit takes back an earlier credit (a reversal) and
+    /// does not count against the user's spending limits.
+    async fn debit(&self, user_id: i32, amount: Money, reference: &str, compensation: bool) -> Result<Wallet>;
+    async fn credit(&self, user_id: i32, amount: Money, reference: &str) -> Result<Wallet>;
+    /// Gives a debited sender their money back; accepted even when the
+    /// sender has been deactivated since the debit.
+    async fn refund(&self, user_id: i32, amount: Money, reference: &str) -> Result<Wallet>;
+    /// Wallet owning the account number; wallet-service checks its check digit
+    /// and answers 404 for unknown numbers. `norek` must be digits only.
+    async fn find_by_norek(&self, norek: &str) -> Result<Wallet>;
+}
+
+#[automock]
+#[async_trait]
+impl WalletGateway for RestRepository {
+    async fn debit(&self, user_id: i32, amount: Money, reference: &str, compensation: bool) -> Result<Wallet> {
+        let body = MovementRequest { user_id, amount, reference, compensation };
+        let response: BaseResponse<Wallet> = post_json_idempotent("wallet", "/wallet/debit", &body).await?;
+        response
+            .data
+            .ok_or_else(|| anyhow::anyhow!("Empty debit response: {}", response.message))
+    }
+
+    async fn credit(&self, user_id: i32, amount: Money, reference: &str) -> Result<Wallet> {
+        let body = MovementRequest { user_id, amount, reference, compensation: false };
+        let response: BaseResponse<Wallet> = post_json_idempotent("wallet", "/wallet/credit", &body).await?;
+        response
+            .data
+            .ok_or_else(|| anyhow::anyhow!("Empty credit response: {}", response.message))
+    }
+
+    async fn refund(&self, user_id: i32, amount: Money, reference: &str) -> Result<Wallet> {
+        let body = MovementRequest { user_id, amount, reference, compensation: true };
+        let response: BaseResponse<Wallet> = post_json_idempotent("wallet", "/wallet/credit", &body).await?;
+        response
+            .data
+            .ok_or_else(|| anyhow::anyhow!("Empty refund response: {}", response.message))
+    }
+
+    async fn find_by_norek(&self, norek: &str) -> Result<Wallet> {
+        let path = format!("/wallet/norek/{norek}");
+        let response: BaseResponse<Wallet> = get_json("wallet", &path).await?;
+        response
+            .data
+            .ok_or_else(|| anyhow::anyhow!("Empty wallet response: {}", response.message))
+    }
+}
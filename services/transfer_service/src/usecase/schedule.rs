@@ -0,0 +1,192 @@
+use crate::domain::dto::ScheduleUpdateRequest;
+use crate::repository::db::schedule::{ScheduleProvider, ScheduleRepository};
+use crate::usecase::transfer::{TransferSaga, Usecase};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use domain::base::base::AuditMetadata;
+use domain::money::money::Money;
+use domain::transfer::error::TransferError;
+use domain::transfer::schedule::{Recurrence, RetryPolicy, ScheduleExecution, ScheduledTransfer};
+use domain::transfer::transfer::{Transfer, TransferStatus};
+
+#[derive(Clone)]
+pub struct ScheduleUsecase {
+    repo: ScheduleRepository,
+    transfers: Usecase,
+}
+
+pub trait TransferScheduler {
+    async fn create_schedule(
+        &self,
+        from_id: i32,
+        to_id: i32,
+        amount: Money,
+        recurrence: Recurrence,
+        first_run_at: DateTime<Utc>,
+        policy: RetryPolicy,
+    ) -> Result<ScheduledTransfer>;
+    async fn get_schedule(&self, id: i32) -> Result<ScheduledTransfer>;
+    async fn get_schedules_by_user(&self, user_id: i32) -> Result<Vec<ScheduledTransfer>>;
+    async fn update_schedule(&self, id: i32, update: ScheduleUpdateRequest) -> Result<ScheduledTransfer>;
+    async fn cancel_schedule(&self, id: i32) -> Result<ScheduledTransfer>;
+    async fn get_executions(&self, id: i32) -> Result<Vec<ScheduleExecution>>;
+    async fn run_due(&self, now: DateTime<Utc>) -> Result<usize>;
+}
+
+impl ScheduleUsecase {
+    pub fn new(repo: ScheduleRepository, transfers: Usecase) -> Self {
+        Self { repo, transfers }
+    }
+
+    /// Starts the transfer of the schedule's current occurrence through the
+    /// normal saga and records the outcome.
+    ///
+    /// A transfer still pending counts as executed, saga recovery finishes it,
+    /// as does one waiting for an operator; only transfers that failed are
+    /// retried per the schedule's policy. When starting fails the attempt's
+    /// transfer is looked up: it may have been stored, and even debited,
+    /// before the error, and then retrying with a new id could pay twice.
+    async fn execute(&self, mut schedule: ScheduledTransfer, now: DateTime<Utc>) -> Result<()> {
+        let mut transfer = Transfer::new(
+            &schedule.from_id.to_string(),
+            &schedule.to_id.to_string(),
+            schedule.amount,
+            AuditMetadata::new(),
+        );
+        let transaction_id = schedule.attempt_transaction_id();
+        transfer.transaction_id = transaction_id.clone();
+
+        let execution = match self.transfers.start_transfer(transfer).await {
+            Ok(transfer) => Self::record_outcome(&mut schedule, &transfer, now),
+            Err(e) => match self.transfers.get_transfer(&transaction_id).await {
+                Ok(transfer) => {
+                    tracing::warn!(
+                        "starting transfer {} of schedule {:?} failed after it was stored: {}",
+                        transaction_id,
+                        schedule.id,
+                        e
+                    );
+                    Self::record_outcome(&mut schedule, &transfer, now)
+                }
+                Err(lookup) if matches!(lookup.downcast_ref(), Some(TransferError::TransferNotFound(_))) => {
+                    schedule.record_failure(None, &e.to_string(), now)
+                }
+                // unknown whether it was stored: keep the attempt, the claim
+                // expires and the same transaction id is tried again
+                Err(lookup) => {
+                    return Err(lookup.context(format!("transfer {} of a failed start not found: {}", transaction_id, e)));
+                }
+            },
+        };
+        self.repo.save_execution(&schedule, &execution).await
+    }
+
+    fn record_outcome(schedule: &mut ScheduledTransfer, transfer: &Transfer, now: DateTime<Utc>) -> ScheduleExecution {
+        match transfer.status {
+            TransferStatus::Committed => schedule.record_started(&transfer.transaction_id, true, now),
+            // the sender may have been debited, starting another transfer could pay twice
+            TransferStatus::Initiated | TransferStatus::Reserved | TransferStatus::NeedsIntervention => {
+                schedule.record_started(&transfer.transaction_id, false, now)
+            }
+            status => {
+                let reason = transfer
+                    .last_transition()
+                    .map(|t| t.reason.clone())
+                    .unwrap_or_default();
+                schedule.record_failure(
+                    Some(&transfer.transaction_id),
+                    &format!("transfer {:?}: {}", status, reason),
+                    now,
+                )
+            }
+        }
+    }
+}
+
+impl TransferScheduler for ScheduleUsecase {
+    async fn create_schedule(
+        &self,
+        from_id: i32,
+        to_id: i32,
+        amount: Money,
+        recurrence: Recurrence,
+        first_run_at: DateTime<Utc>,
+        policy: RetryPolicy,
+    ) -> Result<ScheduledTransfer> {
+        tracing::info!("create {:?} schedule from {} to {} amount {}", recurrence, from_id, to_id, amount);
+        let schedule = ScheduledTransfer::new(from_id, to_id, amount, recurrence, first_run_at, policy)?;
+        self.repo.create_schedule(&schedule).await
+    }
+
+    async fn get_schedule(&self, id: i32) -> Result<ScheduledTransfer> {
+        self.repo
+            .get_schedule(id)
+            .await?
+            .ok_or_else(|| TransferError::ScheduleNotFound(id).into())
+    }
+
+    async fn get_schedules_by_user(&self, user_id: i32) -> Result<Vec<ScheduledTransfer>> {
+        self.repo.get_schedules_by_user(user_id).await
+    }
+
+    /// Changes the amount, recurrence, next run or retry policy, and pauses
+    /// or resumes the schedule. Closed schedules cannot be changed.
+    async fn update_schedule(&self, id: i32, update: ScheduleUpdateRequest) -> Result<ScheduledTransfer> {
+        tracing::info!("update schedule {} with {:?}", id, update);
+        self.repo
+            .modify_schedule(id, move |schedule| {
+                if schedule.status.is_terminal() {
+                    return Err(TransferError::ScheduleClosed(schedule.status));
+                }
+                match update.paused {
+                    Some(true) => schedule.pause()?,
+                    Some(false) => schedule.resume()?,
+                    None => {}
+                }
+                if let Some(amount) = update.amount {
+                    schedule.amount = amount;
+                }
+                if let Some(recurrence) = update.recurrence {
+                    schedule.recurrence = recurrence;
+                }
+                if let Some(policy) = update.policy {
+                    schedule.policy = policy;
+                }
+                if let Some(next_run_at) = update.next_run_at {
+                    schedule.next_run_at = next_run_at;
+                    schedule.attempts = 0;
+                    schedule.retry_at = None;
+                }
+                schedule.validate()?;
+                schedule.audit.touch();
+                Ok(())
+            })
+            .await
+    }
+
+    async fn cancel_schedule(&self, id: i32) -> Result<ScheduledTransfer> {
+        tracing::info!("cancel schedule {}", id);
+        self.repo.modify_schedule(id, |schedule| schedule.cancel()).await
+    }
+
+    async fn get_executions(&self, id: i32) -> Result<Vec<ScheduleExecution>> {
+        self.get_schedule(id).await?;
+        self.repo.get_executions(id).await
+    }
+
+    /// Claims and executes every schedule due at `now`, returns how many were run.
+    async fn run_due(&self, now: DateTime<Utc>) -> Result<usize> {
+        let schedules = self.repo.claim_due_schedules(now).await?;
+        let count = schedules.len();
+        if count > 0 {
+            tracing::info!("executing {} due schedules", count);
+        }
+        for schedule in schedules {
+            let id = schedule.id;
+            if let Err(e) = self.execute(schedule, now).await {
+                tracing::error!("failed to execute schedule {:?}: {}", id, e);
+            }
+        }
+        Ok(count)
+    }
+}
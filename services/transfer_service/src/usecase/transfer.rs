@@ -0,0 +1,578 @@
+use crate::repository::db::postgres::{TransferProvider, TransferRepository};
+use crate::repository::http::receipt_gateway::{ReceiptGateway, RestReceiptRepository};
+use crate::repository::http::wallet_gateway::{RestRepository, WalletGateway};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use domain::base::base::AuditMetadata;
+use domain::money::money::Money;
+use domain::receipt::receipt::Receipt;
+use domain::transfer::error::TransferError;
+use domain::transfer::transfer::{Transfer, TransferStatus};
+use lib::http_client::error::HttpClientError;
+
+/// statuses in which a saga still has steps left to run
+const IN_FLIGHT: [TransferStatus; 2] = [TransferStatus::Initiated, TransferStatus::Reserved];
+
+#[derive(Clone)]
+pub struct Usecase<R = TransferRepository, W = RestRepository, C = RestReceiptRepository> {
+    repo: R,
+    wallet: W,
+    receipts: C,
+}
+
+pub trait TransferSaga {
+    async fn create_transfer(&self, from_id: i32, to_id: i32, amount: Money) -> Result<Transfer>;
+    async fn resolve_recipient(&self, to_id: Option<i32>, to_norek: Option<&str>) -> Result<i32>;
+    async fn get_transfer(&self, transaction_id: &str) -> Result<Transfer>;
+    async fn get_pending_transfers(&self, user_id: i32) -> Result<Vec<Transfer>>;
+    async fn resume_in_flight(&self, updated_before: DateTime<Utc>) -> Result<usize>;
+    async fn reverse_transfer(&self, transaction_id: &str, amount: Option<Money>) -> Result<(Transfer, Option<Receipt>)>;
+}
+
+impl<R: TransferProvider + Sync, W: WalletGateway, C: ReceiptGateway> Usecase<R, W, C> {
+    pub fn new(repo: R, wallet: W, receipts: C) -> Self {
+        Self { repo, wallet, receipts }
+    }
+
+    /// Stores and drives a transfer built by the caller. A transfer whose
+    /// `transaction_id` already exists is returned as stored instead of being
+    /// started again, so callers with deterministic ids can retry safely.
+    pub async fn start_transfer(&self, transfer: Transfer) -> Result<Transfer> {
+        if !transfer.amount.is_positive() {
+            return Err(TransferError::InvalidAmount(transfer.amount).into());
+        }
+        if transfer.account_debit == transfer.account_credit {
+            return Err(TransferError::SameParties.into());
+        }
+        if let Some(existing) = self
+            .repo
+            .get_transfer_by_transaction_id(&transfer.transaction_id)
+            .await?
+        {
+            tracing::info!("transfer {} already started", existing.transaction_id);
+            return Ok(existing);
+        }
+
+        let transfer = match self.repo.create_transfer(&transfer).await {
+            Ok(created) => created,
+            Err(e) => match e.downcast_ref::<TransferError>() {
+                Some(TransferError::DuplicateTransaction(_)) => {
+                    tracing::info!("transfer {} started concurrently", transfer.transaction_id);
+                    return self
+                        .repo
+                        .get_transfer_by_transaction_id(&transfer.transaction_id)
+                        .await?
+                        .ok_or(e);
+                }
+                _ => return Err(e),
+            },
+        };
+        self.drive(transfer).await
+    }
+
+    /// reference of one saga leg, wallet-service applies each reference once
+    fn leg_reference(transfer: &Transfer, leg: &str) -> String {
+        format!("{}:{}", transfer.transaction_id, leg)
+    }
+
+    /// sender and receiver user ids, stored as the transfer's debit/credit accounts
+    fn parties(transfer: &Transfer) -> Result<(i32, i32)> {
+        let from_id = transfer.account_debit.parse()?;
+        let to_id = transfer.account_credit.parse()?;
+        Ok((from_id, to_id))
+    }
+
+    /// Wallet-service answered 4xx: the leg was definitely not applied.
+    /// Anything else (timeouts, 5xx) leaves the outcome unknown.
+    fn is_rejection(e: &anyhow::Error) -> bool {
+        e.downcast_ref::<HttpClientError>()
+            .is_some_and(HttpClientError::is_client_error)
+    }
+
+    /// Runs the remaining saga steps for the transfer's current status.
+    ///
+    /// - Initiated: debit the sender, then Reserved; a rejected debit fails the transfer.
+    /// - Reserved: credit the receiver, then Committed; a rejected credit is
+    ///   compensated by refunding the sender, then Failed. When the refund is
+    ///   rejected as well the transfer ends NeedsIntervention for an operator.
+    ///
+    /// When a wallet call has an unknown outcome the transfer stays where it is
+    /// and the step is retried by [`TransferSaga::resume_in_flight`]. Wallet
+    /// legs are applied once per reference, so two drivers of the same saga
+    /// move money once; the one losing a step carries on from the stored state.
+    async fn drive(&self, mut transfer: Transfer) -> Result<Transfer> {
+        let (from_id, to_id) = Self::parties(&transfer)?;
+
+        if transfer.status == TransferStatus::Initiated {
+            let reference = Self::leg_reference(&transfer, "debit");
+            // a reversal takes back what the receiver got, it is not their spending
+            let compensation = transfer.reversal_of.is_some();
+            match self.wallet.debit(from_id, transfer.amount, &reference, compensation).await {
+                Ok(_) => {
+                    transfer.mark_reserved("sender debited")?;
+                    transfer = self.save_step(transfer).await?;
+                }
+                Err(e) if Self::is_rejection(&e) => {
+                    transfer.mark_failed(&format!("debit rejected: {e}"))?;
+                    return self.save_step(transfer).await;
+                }
+                Err(e) => {
+                    tracing::warn!("debit of transfer {} pending: {}", transfer.transaction_id, e);
+                    return Ok(transfer);
+                }
+            }
+        }
+
+        if transfer.status == TransferStatus::Reserved {
+            let reference = Self::leg_reference(&transfer, "credit");
+            match self.wallet.credit(to_id, transfer.credit_amount(), &reference).await {
+                Ok(_) => {
+                    transfer.mark_committed("receiver credited")?;
+                    transfer = self.save_step(transfer).await?;
+                }
+                Err(e) if Self::is_rejection(&e) => {
+                    tracing::warn!(
+                        "credit of transfer {} rejected, refunding sender: {}",
+                        transfer.transaction_id,
+                        e
+                    );
+                    let refund = Self::leg_reference(&transfer, "refund");
+                    match self.wallet.refund(from_id, transfer.amount, &refund).await {
+                        Ok(_) => {
+                            transfer.mark_failed(&format!("credit rejected: {e}; debit refunded"))?;
+                            transfer = self.save_step(transfer).await?;
+                        }
+                        Err(refund_err) if Self::is_rejection(&refund_err) => {
+                            // retrying cannot help, stop and hand it to an operator
+                            tracing::error!(
+                                "transfer {} needs manual intervention, refund of {} to user_id {} rejected: {}",
+                                transfer.transaction_id,
+                                transfer.amount,
+                                from_id,
+                                refund_err
+                            );
+                            transfer.mark_needs_intervention(&format!(
+                                "credit rejected: {e}; refund rejected: {refund_err}"
+                            ))?;
+                            transfer = self.save_step(transfer).await?;
+                        }
+                        Err(refund_err) => {
+                            tracing::error!(
+                                "refund of transfer {} pending: {}",
+                                transfer.transaction_id,
+                                refund_err
+                            );
+                        }
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("credit of transfer {} pending: {}", transfer.transaction_id, e);
+                }
+            }
+        }
+
+        if transfer.status == TransferStatus::Committed
+            && let Some(original) = &transfer.reversal_of
+        {
+            self.settle_reversed(original).await?;
+        }
+
+        Ok(transfer)
+    }
+
+    /// Saves the step just taken. When another driver saved a step first the
+    /// stored transfer is returned instead, the leg it ran was the same one.
+    async fn save_step(&self, transfer: Transfer) -> Result<Transfer> {
+        match self.repo.save_transition(&transfer).await {
+            Ok(()) => Ok(transfer),
+            Err(e) if matches!(e.downcast_ref(), Some(TransferError::ConcurrentTransition(_, _))) => {
+                tracing::info!("transfer {} was advanced concurrently, reloading", transfer.transaction_id);
+                self.get_transfer(&transfer.transaction_id).await
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Has receipt-service issue the receipt of a committed reversal. The
+    /// reversal stands either way, so a failure is only logged; an operator
+    /// can issue the receipt later.
+    async fn issue_reversal_receipt(&self, reversal: &Transfer) -> Option<Receipt> {
+        if reversal.reversal_of.is_none() || reversal.status != TransferStatus::Committed {
+            return None;
+        }
+        match self.receipts.issue_receipt(&reversal.transaction_id).await {
+            Ok(receipt) => Some(receipt),
+            Err(e) if e.downcast_ref::<HttpClientError>().and_then(HttpClientError::status) == Some(409) => {
+                tracing::info!("receipt of reversal {} already issued", reversal.transaction_id);
+                None
+            }
+            Err(e) => {
+                tracing::error!("receipt of reversal {} not issued: {}", reversal.transaction_id, e);
+                None
+            }
+        }
+    }
+
+    /// Marks the original Reversed once committed reversals add up to
+    /// everything it credited; partially reversed transfers stay Committed.
+    async fn settle_reversed(&self, transaction_id: &str) -> Result<()> {
+        let mut original = self.get_transfer(transaction_id).await?;
+        if original.status != TransferStatus::Committed {
+            return Ok(());
+        }
+        let credited = original.credit_amount();
+        let mut reversed = Money::zero(credited.currency());
+        for reversal in self.repo.get_reversals(transaction_id).await? {
+            if reversal.status == TransferStatus::Committed {
+                reversed = reversed.checked_add(reversal.amount)?;
+            }
+        }
+        if !original.reversible_amount(reversed)?.is_zero() {
+            return Ok(());
+        }
+
+        original.mark_reversed("fully reversed")?;
+        // a concurrent reversal may have settled it first
+        if let Err(e) = self.repo.save_transition(&original).await {
+            tracing::warn!("transfer {} not marked reversed: {}", transaction_id, e);
+        }
+        Ok(())
+    }
+}
+
+impl<R: TransferProvider + Sync, W: WalletGateway, C: ReceiptGateway> TransferSaga for Usecase<R, W, C> {
+    async fn create_transfer(&self, from_id: i32, to_id: i32, amount: Money) -> Result<Transfer> {
+        tracing::info!("create transfer from {} to {} amount {}", from_id, to_id, amount);
+        let transfer = Transfer::new(
+            &from_id.to_string(),
+            &to_id.to_string(),
+            amount,
+            AuditMetadata::new(),
+        );
+        self.start_transfer(transfer).await
+    }
+
+    /// User id of the receiver, given directly or by account number; an
+    /// account number is resolved through wallet-service.
+    async fn resolve_recipient(&self, to_id: Option<i32>, to_norek: Option<&str>) -> Result<i32> {
+        match (to_id, to_norek) {
+            (Some(to_id), None) => Ok(to_id),
+            (None, Some(norek)) => {
+                // only digits go into the wallet-service path
+                let digits: String = norek.chars().filter(|c| !matches!(c, ' ' | '-')).collect();
+                if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(TransferError::InvalidRecipient(format!("{norek:?} is not an account number")).into());
+                }
+                let wallet = self.wallet.find_by_norek(&digits).await?;
+                tracing::info!("account number {} belongs to user_id {}", digits, wallet.user_id);
+                Ok(wallet.user_id)
+            }
+            (Some(_), Some(_)) => {
+                Err(TransferError::InvalidRecipient("give either to_id or to_norek, not both".to_string()).into())
+            }
+            (None, None) => Err(TransferError::InvalidRecipient("give to_id or to_norek".to_string()).into()),
+        }
+    }
+
+    async fn get_transfer(&self, transaction_id: &str) -> Result<Transfer> {
+        tracing::info!("get transfer {}", transaction_id);
+        self.repo
+            .get_transfer_by_transaction_id(transaction_id)
+            .await?
+            .ok_or_else(|| TransferError::TransferNotFound(transaction_id.to_string()).into())
+    }
+
+    /// Transfers sending money from or to the user whose saga has not finished.
+    async fn get_pending_transfers(&self, user_id: i32) -> Result<Vec<Transfer>> {
+        tracing::info!("get pending transfers of user_id {}", user_id);
+        self.repo
+            .get_transfers_by_account(&user_id.to_string(), &IN_FLIGHT)
+            .await
+    }
+
+    /// Picks up sagas left Initiated or Reserved, e.g. after a crash, and
+    /// drives each one forward. Only transfers untouched since `updated_before`
+    /// are taken, younger ones may still be driven by their request.
+    /// Returns how many were resumed.
+    async fn resume_in_flight(&self, updated_before: DateTime<Utc>) -> Result<usize> {
+        let transfers = self
+            .repo
+            .get_transfers_by_status(&IN_FLIGHT, updated_before)
+            .await?;
+        let count = transfers.len();
+        if count > 0 {
+            tracing::info!("resuming {} in-flight transfers", count);
+        }
+        for transfer in transfers {
+            let transaction_id = transfer.transaction_id.clone();
+            match self.drive(transfer).await {
+                Ok(transfer) => {
+                    self.issue_reversal_receipt(&transfer).await;
+                }
+                Err(e) => tracing::error!("failed to resume transfer {}: {}", transaction_id, e),
+            }
+        }
+        Ok(count)
+    }
+
+    /// Reverses `amount` of a committed transfer (everything left when `None`)
+    /// through a linked compensating transfer that runs as its own saga. Once
+    /// the reversal is Committed its receipt, referencing the original
+    /// transaction, is issued by receipt-service; a reversal finished by the
+    /// recovery gets its receipt then.
+    async fn reverse_transfer(&self, transaction_id: &str, amount: Option<Money>) -> Result<(Transfer, Option<Receipt>)> {
+        tracing::info!("reverse transfer {} amount {:?}", transaction_id, amount);
+        let reversal = self.repo.create_reversal(transaction_id, amount).await?;
+        let reversal = self.drive(reversal).await?;
+        let receipt = self.issue_reversal_receipt(&reversal).await;
+        Ok((reversal, receipt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::db::postgres::MockTransferRepository;
+    use crate::repository::http::receipt_gateway::MockRestReceiptRepository;
+    use crate::repository::http::wallet_gateway::MockRestRepository;
+    use domain::money::money::Currency;
+    use domain::wallet::wallet::Wallet;
+    use mockall::predicate::{always, eq};
+
+    fn idr(minor: i64) -> Money {
+        Money::new(minor, Currency::IDR)
+    }
+
+    fn initiated() -> Transfer {
+        Transfer::new("1", "2", idr(10_000), AuditMetadata::new())
+    }
+
+    fn reserved() -> Transfer {
+        let mut transfer = initiated();
+        transfer.mark_reserved("sender debited").unwrap();
+        transfer
+    }
+
+    fn wallet(user_id: i32) -> Wallet {
+        Wallet::new(Some(user_id), "880000000013".to_string(), user_id, idr(0), AuditMetadata::new())
+    }
+
+    /// wallet-service refused the leg, it was not applied
+    fn rejected() -> anyhow::Error {
+        HttpClientError::from_response(422, r#"{"message": "rejected"}"#.to_string()).into()
+    }
+
+    /// the leg may or may not have been applied
+    fn unavailable() -> anyhow::Error {
+        HttpClientError::from_response(503, String::new()).into()
+    }
+
+    /// a saga that never reaches receipt-service
+    fn saga(
+        repo: MockTransferRepository,
+        wallet_gateway: MockRestRepository,
+    ) -> Usecase<MockTransferRepository, MockRestRepository, MockRestReceiptRepository> {
+        let mut receipts = MockRestReceiptRepository::new();
+        receipts.expect_issue_receipt().never();
+        Usecase::new(repo, wallet_gateway, receipts)
+    }
+
+    /// expects exactly one saved step, ending in `status`
+    fn expect_step(repo: &mut MockTransferRepository, status: TransferStatus) {
+        repo.expect_save_transition()
+            .withf(move |t| t.status == status)
+            .times(1)
+            .returning(|_| Box::pin(async { Ok(()) }));
+    }
+
+    #[tokio::test]
+    async fn rejected_credit_refunds_the_sender() {
+        let transfer = reserved();
+        let refund = format!("{}:refund", transfer.transaction_id);
+        let mut wallet_gateway = MockRestRepository::new();
+        wallet_gateway.expect_credit().times(1).returning(|_, _, _| Err(rejected()));
+        wallet_gateway
+            .expect_refund()
+            .with(eq(1), eq(idr(10_000)), eq(refund))
+            .times(1)
+            .returning(|user_id, _, _| Ok(wallet(user_id)));
+        let mut repo = MockTransferRepository::new();
+        expect_step(&mut repo, TransferStatus::Failed);
+
+        let driven = saga(repo, wallet_gateway).drive(transfer).await.unwrap();
+        assert_eq!(driven.status, TransferStatus::Failed);
+    }
+
+    #[tokio::test]
+    async fn rejected_refund_needs_intervention() {
+        let mut wallet_gateway = MockRestRepository::new();
+        wallet_gateway.expect_credit().times(1).returning(|_, _, _| Err(rejected()));
+        wallet_gateway.expect_refund().times(1).returning(|_, _, _| Err(rejected()));
+        let mut repo = MockTransferRepository::new();
+        expect_step(&mut repo, TransferStatus::NeedsIntervention);
+
+        let driven = saga(repo, wallet_gateway).drive(reserved()).await.unwrap();
+        assert_eq!(driven.status, TransferStatus::NeedsIntervention);
+        assert!(driven.status.is_terminal());
+    }
+
+    #[tokio::test]
+    async fn unknown_outcomes_leave_the_saga_for_recovery() {
+        // debit outcome unknown: stays Initiated, nothing else is tried
+        let mut wallet_gateway = MockRestRepository::new();
+        wallet_gateway.expect_debit().times(1).returning(|_, _, _, _| Err(unavailable()));
+        wallet_gateway.expect_credit().never();
+        let mut repo = MockTransferRepository::new();
+        repo.expect_save_transition().never();
+        let driven = saga(repo, wallet_gateway).drive(initiated()).await.unwrap();
+        assert_eq!(driven.status, TransferStatus::Initiated);
+
+        // credit outcome unknown: stays Reserved and the sender is not refunded
+        let mut wallet_gateway = MockRestRepository::new();
+        wallet_gateway.expect_credit().times(1).returning(|_, _, _| Err(unavailable()));
+        wallet_gateway.expect_refund().never();
+        let mut repo = MockTransferRepository::new();
+        repo.expect_save_transition().never();
+        let driven = saga(repo, wallet_gateway).drive(reserved()).await.unwrap();
+        assert_eq!(driven.status, TransferStatus::Reserved);
+    }
+
+    #[tokio::test]
+    async fn happy_path_debits_then_credits() {
+        let transfer = initiated();
+        let debit = format!("{}:debit", transfer.transaction_id);
+        let credit = format!("{}:credit", transfer.transaction_id);
+        let mut wallet_gateway = MockRestRepository::new();
+        wallet_gateway
+            .expect_debit()
+            .with(eq(1), eq(idr(10_000)), eq(debit), eq(false))
+            .times(1)
+            .returning(|user_id, _, _, _| Ok(wallet(user_id)));
+        wallet_gateway
+            .expect_credit()
+            .with(eq(2), eq(idr(10_000)), eq(credit))
+            .times(1)
+            .returning(|user_id, _, _| Ok(wallet(user_id)));
+        let mut repo = MockTransferRepository::new();
+        expect_step(&mut repo, TransferStatus::Reserved);
+        expect_step(&mut repo, TransferStatus::Committed);
+
+        let driven = saga(repo, wallet_gateway).drive(transfer).await.unwrap();
+        assert_eq!(driven.status, TransferStatus::Committed);
+    }
+
+    #[tokio::test]
+    async fn lost_step_race_continues_from_the_stored_transfer() {
+        let transfer = initiated();
+        let mut stored = transfer.clone();
+        stored.mark_reserved("sender debited").unwrap();
+        stored.mark_committed("receiver credited").unwrap();
+
+        let mut wallet_gateway = MockRestRepository::new();
+        wallet_gateway.expect_debit().times(1).returning(|user_id, _, _, _| Ok(wallet(user_id)));
+        // the other driver already credited the receiver
+        wallet_gateway.expect_credit().never();
+        let mut repo = MockTransferRepository::new();
+        repo.expect_save_transition().times(1).returning(|t| {
+            let e = TransferError::ConcurrentTransition(t.transaction_id.clone(), TransferStatus::Initiated);
+            Box::pin(async move { Err(e.into()) })
+        });
+        repo.expect_get_transfer_by_transaction_id()
+            .times(1)
+            .returning(move |_| {
+                let stored = stored.clone();
+                Box::pin(async move { Ok(Some(stored)) })
+            });
+
+        let driven = saga(repo, wallet_gateway).drive(transfer).await.unwrap();
+        assert_eq!(driven.status, TransferStatus::Committed);
+    }
+
+    #[tokio::test]
+    async fn recovery_skips_recently_updated_transfers() {
+        let cutoff = Utc::now() - chrono::Duration::minutes(2);
+        let mut repo = MockTransferRepository::new();
+        repo.expect_get_transfers_by_status()
+            .withf(move |statuses, updated_before| statuses == IN_FLIGHT && *updated_before == cutoff)
+            .times(1)
+            .returning(|_, _| Box::pin(async { Ok(Vec::new()) }));
+
+        let resumed = saga(repo, MockRestRepository::new())
+            .resume_in_flight(cutoff)
+            .await
+            .unwrap();
+        assert_eq!(resumed, 0);
+    }
+
+    fn reversal_of(original: &Transfer) -> Transfer {
+        let mut original = original.clone();
+        original.mark_reserved("sender debited").unwrap();
+        original.mark_committed("receiver credited").unwrap();
+        original.reversal(Some(idr(4_000)), idr(0)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn committed_reversal_gets_its_receipt() {
+        let original = initiated();
+        let reversal = reversal_of(&original);
+        let reversal_id = reversal.transaction_id.clone();
+        let mut wallet_gateway = MockRestRepository::new();
+        wallet_gateway
+            .expect_debit()
+            .with(eq(2), eq(idr(4_000)), always(), eq(true))
+            .times(1)
+            .returning(|user_id, _, _, _| Ok(wallet(user_id)));
+        wallet_gateway.expect_credit().times(1).returning(|user_id, _, _| Ok(wallet(user_id)));
+        let mut repo = MockTransferRepository::new();
+        repo.expect_create_reversal().times(1).returning(move |_, _| {
+            let reversal = reversal.clone();
+            Box::pin(async move { Ok(reversal) })
+        });
+        expect_step(&mut repo, TransferStatus::Reserved);
+        expect_step(&mut repo, TransferStatus::Committed);
+        // partially reversed, the original stays Committed
+        let mut committed = original.clone();
+        committed.mark_reserved("sender debited").unwrap();
+        committed.mark_committed("receiver credited").unwrap();
+        repo.expect_get_transfer_by_transaction_id().returning(move |_| {
+            let committed = committed.clone();
+            Box::pin(async move { Ok(Some(committed)) })
+        });
+        repo.expect_get_reversals().returning(|_| Box::pin(async { Ok(Vec::new()) }));
+        let mut receipts = MockRestReceiptRepository::new();
+        receipts
+            .expect_issue_receipt()
+            .with(eq(reversal_id))
+            .times(1)
+            .returning(|_| Ok(Receipt::for_transfer(&initiated(), "")));
+
+        let (driven, receipt) = Usecase::new(repo, wallet_gateway, receipts)
+            .reverse_transfer(&original.transaction_id, Some(idr(4_000)))
+            .await
+            .unwrap();
+        assert_eq!(driven.status, TransferStatus::Committed);
+        assert!(receipt.is_some());
+    }
+
+    #[tokio::test]
+    async fn pending_reversal_gets_no_receipt_yet() {
+        let original = initiated();
+        let reversal = reversal_of(&original);
+        let mut wallet_gateway = MockRestRepository::new();
+        wallet_gateway.expect_debit().times(1).returning(|user_id, _, _, _| Ok(wallet(user_id)));
+        wallet_gateway.expect_credit().times(1).returning(|_, _, _| Err(unavailable()));
+        let mut repo = MockTransferRepository::new();
+        repo.expect_create_reversal().times(1).returning(move |_, _| {
+            let reversal = reversal.clone();
+            Box::pin(async move { Ok(reversal) })
+        });
+        expect_step(&mut repo, TransferStatus::Reserved);
+
+        let (driven, receipt) = saga(repo, wallet_gateway)
+            .reverse_transfer(&original.transaction_id, Some(idr(4_000)))
+            .await
+            .unwrap();
+        assert_eq!(driven.status, TransferStatus::Reserved);
+        assert!(receipt.is_none());
+    }
+}
@@ -0,0 +1,17 @@
+-- registered users; emails are stored lowercase and unique
+CREATE SCHEMA IF NOT EXISTS USER_DIGITAL;
+
+DO $$ BEGIN
+    CREATE TYPE user_status AS ENUM ('Active', 'Inactive');
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+CREATE TABLE IF NOT EXISTS USER_DIGITAL.DATA_USER (
+    id           SERIAL PRIMARY KEY,
+    email        VARCHAR(254) NOT NULL,
+    name         VARCHAR(128) NOT NULL,
+    status       user_status  NOT NULL DEFAULT 'Active',
+    created_date TIMESTAMPTZ  NOT NULL DEFAULT now(),
+    updated_date TIMESTAMPTZ,
+    CONSTRAINT data_user_email_key UNIQUE (email)
+);
@@ -0,0 +1,2 @@
+-- users signed up through registration-service wait in Pending until their email is verified
+ALTER TYPE user_status ADD VALUE IF NOT EXISTS 'Pending';
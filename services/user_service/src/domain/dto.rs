@@ -0,0 +1,17 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct RegisterUserRequest {
+    pub email: String,
+    pub name: String,
+    /// registers the user as Pending until their email is verified
+    #[serde(default)]
+    pub pending: bool,
+}
+
+/// fields left out keep their current value
+#[derive(Debug, Deserialize, Default)]
+pub struct UpdateUserRequest {
+    pub email: Option<String>,
+    pub name: Option<String>,
+}
@@ -0,0 +1,15 @@
+use axum::http::StatusCode;
+use axum::Json;
+use domain::base::base::BaseResponse;
+use lib::auth::error::AuthError;
+use lib::trace::trace_id::current_trace_id;
+
+/// resource name of the `user:admin` scope, which may act on any user
+pub const USER: &str = "user";
+
+/// Response of a caller that may not act on the user.
+pub fn denied<T>(e: AuthError) -> (StatusCode, Json<BaseResponse<T>>) {
+    tracing::warn!("{}: {}", e.code(), e);
+    let response = BaseResponse::error(current_trace_id(), e.code(), e.public_message());
+    (e.status(), Json::from(response))
+}
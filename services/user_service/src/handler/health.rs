@@ -0,0 +1,3 @@
+pub async fn health_check() -> &'static str {
+    "Pong! User service is healthy!"
+}
@@ -0,0 +1,19 @@
+use crate::app::AppState;
+use crate::handler::health::health_check;
+use crate::handler::user::{activate_user, deactivate_user, get_user, register_user, update_user};
+use axum::routing::{get, post};
+use axum::Router;
+use lib::trace::trace_id::TraceIdLayer;
+
+pub fn routes(app_state: AppState) -> Router {
+    Router::new()
+        .route("/health", get(health_check))
+        .route("/users", post(register_user))
+        .route(
+            "/users/{id}",
+            get(get_user).put(update_user).delete(deactivate_user),
+        )
+        .route("/users/{id}/activate", post(activate_user))
+        .layer(TraceIdLayer)
+        .with_state(app_state)
+}
@@ -0,0 +1,128 @@
+use crate::app::AppState;
+use crate::domain::dto::{RegisterUserRequest, UpdateUserRequest};
+use crate::handler::auth::{denied, USER};
+use crate::usecase::user::UserRegistry;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use domain::base::base::BaseResponse;
+use domain::user::error::UserError;
+use domain::user::user::User;
+use lib::auth::extractor::AuthUser;
+use lib::trace::trace_id::current_trace_id;
+
+/// Registers a user
+/// request :
+///   - email (unique, case-insensitive, at most 254 characters)
+///   - name (at most 128 characters)
+///   - pending (optional), keeps the user Pending until their email is verified
+///
+/// Responds 409 when the email is already registered, except for a pending
+/// sign-up of a user still pending, which returns that user.
+/// Users sign up through registration-service, so this needs `user:admin`.
+pub async fn register_user(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(request): Json<RegisterUserRequest>,
+) -> (StatusCode, Json<BaseResponse<User>>) {
+    tracing::info!("register user for request: {:?}", request);
+    if let Err(e) = auth.ensure_admin(USER) {
+        return denied(e);
+    }
+    user_response(
+        state
+            .usecase
+            .register_user(&request.email, &request.name, request.pending)
+            .await,
+    )
+}
+
+/// Retrieves a user by id, 404 when unknown.
+/// Only the user themselves, or a `user:admin`, may read it.
+pub async fn get_user(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<i32>,
+) -> (StatusCode, Json<BaseResponse<User>>) {
+    tracing::info!("inquiry user for id: {:?}", id);
+    if let Err(e) = auth.ensure_owner(USER, id) {
+        return denied(e);
+    }
+    user_response(state.usecase.get_user(id).await)
+}
+
+/// Updates the email and/or name of an active user, by the user themselves
+/// or a `user:admin`.
+pub async fn update_user(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<i32>,
+    Json(request): Json<UpdateUserRequest>,
+) -> (StatusCode, Json<BaseResponse<User>>) {
+    if let Err(e) = auth.ensure_owner(USER, id) {
+        return denied(e);
+    }
+    user_response(state.usecase.update_user(id, request).await)
+}
+
+/// Deactivates a user, the record is kept. Allowed to the user themselves
+/// or a `user:admin`.
+pub async fn deactivate_user(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<i32>,
+) -> (StatusCode, Json<BaseResponse<User>>) {
+    if let Err(e) = auth.ensure_owner(USER, id) {
+        return denied(e);
+    }
+    user_response(state.usecase.deactivate_user(id).await)
+}
+
+/// Activates a deactivated or pending user; needs `user:admin`, pending
+/// users are activated by registration-service once their email is verified.
+pub async fn activate_user(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<i32>,
+) -> (StatusCode, Json<BaseResponse<User>>) {
+    if let Err(e) = auth.ensure_admin(USER) {
+        return denied(e);
+    }
+    user_response(state.usecase.activate_user(id).await)
+}
+
+fn user_response<T>(result: anyhow::Result<T>) -> (StatusCode, Json<BaseResponse<T>>) {
+    match result {
+        Ok(data) => {
+            let response = BaseResponse::new(current_trace_id(), "Success".to_string(), Some(data));
+            (StatusCode::OK, Json::from(response))
+        }
+        Err(e) => {
+            let (status, code, message) = match e.downcast_ref::<UserError>() {
+                Some(error) => {
+                    let (status, code) = match error {
+                        UserError::UserNotFound(_) => (StatusCode::NOT_FOUND, "NOT_FOUND"),
+                        UserError::EmailTaken(_) => (StatusCode::CONFLICT, "EMAIL_TAKEN"),
+                        UserError::InvalidEmail(_) | UserError::EmptyName | UserError::TooLong(..) => {
+                            (StatusCode::BAD_REQUEST, "INVALID_USER")
+                        }
+                        UserError::UserInactive(_) | UserError::AlreadyActive(_) => {
+                            (StatusCode::CONFLICT, "INVALID_STATUS")
+                        }
+                    };
+                    (status, code, error.to_string())
+                }
+                None => {
+                    tracing::error!("user request failed: {:#}", e);
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "INTERNAL_ERROR",
+                        "Internal server error".to_string(),
+                    )
+                }
+            };
+            let response = BaseResponse::error(current_trace_id(), code, message);
+            (status, Json::from(response))
+        }
+    }
+}
@@ -1,3 +1,46 @@
-fn main() {
-    println!("Hello, world!");
+mod app;
+
+use crate::app::{AppConfig, AppState};
+use crate::handler::router::routes;
+use crate::repository::db::postgres::UserRepository;
+use crate::usecase::user::Usecase;
+use lib::auth::jwt::init_auth;
+use lib::db::postgres::init_pool;
+use lib::log::logging::init;
+
+mod repository {
+    pub mod db;
+}
+
+mod usecase {
+    pub mod user;
+}
+mod domain {
+    pub mod dto;
+}
+
+mod handler {
+    pub mod auth;
+    pub mod health;
+    pub mod router;
+    pub mod user;
+}
+
+const SERVICE_NAME: &str = "USER_SERVICE";
+#[tokio::main]
+async fn main() {
+    init(SERVICE_NAME);
+    tracing::info!("starting user service ...!");
+    init_auth();
+
+    let config = AppConfig::from_env();
+    let pool = init_pool().clone();
+    let usecase = Usecase::new(UserRepository::new(pool));
+
+    let app = routes(AppState { usecase });
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", config.port))
+        .await
+        .expect("bind user service port");
+    tracing::info!("user service listening on {}", config.port);
+    axum::serve(listener, app).await.expect("run user service");
 }
@@ -0,0 +1,133 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use deadpool_postgres::GenericClient;
+use domain::base::base::AuditMetadata;
+use domain::user::error::UserError;
+use domain::user::user::User;
+use mockall::automock;
+use tokio_postgres::error::SqlState;
+use tokio_postgres::Row;
+
+const USER_COLUMNS: &str = "id, email, name, status, created_date, updated_date";
+
+#[derive(Debug, Clone)]
+pub struct UserRepository {
+    pool: deadpool_postgres::Pool,
+}
+
+#[async_trait]
+pub trait UserProvider {
+    /// Stores a new user; fails with [`UserError::EmailTaken`] when the email
+    /// is already registered.
+    async fn create_user(&self, user: &User) -> Result<User>;
+    async fn get_user(&self, id: i32) -> Result<Option<User>>;
+    async fn get_user_by_email(&self, email: &str) -> Result<Option<User>>;
+    /// Saves the user's email, name and status, with the same email check as
+    /// [`UserProvider::create_user`].
+    async fn update_user(&self, user: &User) -> Result<User>;
+}
+
+impl UserRepository {
+    pub fn new(pool: deadpool_postgres::Pool) -> Self {
+        Self { pool }
+    }
+
+    fn user_from_row(row: &Row) -> User {
+        User {
+            id: Some(row.get("id")),
+            email: row.get("email"),
+            name: row.get("name"),
+            status: row.get("status"),
+            audit: AuditMetadata {
+                created_date: row.get("created_date"),
+                updated_date: row.get("updated_date"),
+            },
+        }
+    }
+
+    /// the unique email constraint is the source of truth, even under races
+    fn map_conflict(e: tokio_postgres::Error, email: &str) -> anyhow::Error {
+        if e.code() == Some(&SqlState::UNIQUE_VIOLATION) {
+            UserError::EmailTaken(email.to_string()).into()
+        } else {
+            e.into()
+        }
+    }
+}
+
+#[async_trait]
+#[automock]
+impl UserProvider for UserRepository {
+    async fn create_user(&self, user: &User) -> Result<User> {
+        tracing::info!("create user {:?}", user.email);
+        let client = self.pool.get().await?;
+        let row = client
+            .query_one(
+                &format!(
+                    "INSERT INTO USER_DIGITAL.DATA_USER (email, name, status, created_date, updated_date)
+                 VALUES ($1, $2, $3, $4, $5)
+                 RETURNING {}",
+                    USER_COLUMNS
+                ),
+                &[
+                    &user.email,
+                    &user.name,
+                    &user.status,
+                    &user.audit.created_date,
+                    &user.audit.updated_date,
+                ],
+            )
+            .await
+            .map_err(|e| Self::map_conflict(e, &user.email))?;
+        Ok(Self::user_from_row(&row))
+    }
+
+    async fn get_user(&self, id: i32) -> Result<Option<User>> {
+        tracing::info!("get user by id {:?}", id);
+        let client = self.pool.get().await?;
+        let row = client
+            .query_opt(
+                &format!("SELECT {} FROM USER_DIGITAL.DATA_USER WHERE id = $1", USER_COLUMNS),
+                &[&id],
+            )
+            .await?;
+        Ok(row.as_ref().map(Self::user_from_row))
+    }
+
+    async fn get_user_by_email(&self, email: &str) -> Result<Option<User>> {
+        tracing::info!("get user by email {:?}", email);
+        let client = self.pool.get().await?;
+        let row = client
+            .query_opt(
+                &format!("SELECT {} FROM USER_DIGITAL.DATA_USER WHERE email = $1", USER_COLUMNS),
+                &[&email],
+            )
+            .await?;
+        Ok(row.as_ref().map(Self::user_from_row))
+    }
+
+    async fn update_user(&self, user: &User) -> Result<User> {
+        tracing::info!("update user {:?}", user.id);
+        let client = self.pool.get().await?;
+        let row = client
+            .query_opt(
+                &format!(
+                    "UPDATE USER_DIGITAL.DATA_USER SET email = $1, name = $2, status = $3, updated_date = $4
+                 WHERE id = $5
+                 RETURNING {}",
+                    USER_COLUMNS
+                ),
+                &[
+                    &user.email,
+                    &user.name,
+                    &user.status,
+                    &user.audit.updated_date,
+                    &user.id,
+                ],
+            )
+            .await
+            .map_err(|e| Self::map_conflict(e, &user.email))?;
+        let row = row.ok_or(UserError::UserNotFound(user.id.unwrap_or_default()))?;
+        Ok(Self::user_from_row(&row))
+    }
+}
@@ -0,0 +1,82 @@
+use crate::domain::dto::UpdateUserRequest;
+use crate::repository::db::postgres::{UserProvider, UserRepository};
+use anyhow::Result;
+use domain::user::error::UserError;
+use domain::user::user::{User, UserStatus};
+
+#[derive(Clone)]
+pub struct Usecase {
+    repo: UserRepository,
+}
+
+pub trait UserRegistry {
+    async fn register_user(&self, email: &str, name: &str, pending: bool) -> Result<User>;
+    async fn get_user(&self, id: i32) -> Result<User>;
+    async fn update_user(&self, id: i32, update: UpdateUserRequest) -> Result<User>;
+    async fn deactivate_user(&self, id: i32) -> Result<User>;
+    async fn activate_user(&self, id: i32) -> Result<User>;
+}
+
+impl Usecase {
+    pub fn new(repo: UserRepository) -> Self {
+        Self { repo }
+    }
+}
+
+impl UserRegistry for Usecase {
+    /// Registers a user, Pending when they still have to verify their email;
+    /// emails are unique, compared case-insensitively.
+    async fn register_user(&self, email: &str, name: &str, pending: bool) -> Result<User> {
+        let user = if pending {
+            User::pending(email, name)
+        } else {
+            User::new(email, name)
+        };
+        user.validate()?;
+        if let Some(existing) = self.repo.get_user_by_email(&user.email).await? {
+            // signing up again before verifying gets the same pending user back,
+            // so a new verification mail can be sent
+            if pending && existing.status == UserStatus::Pending {
+                return Ok(existing);
+            }
+            return Err(UserError::EmailTaken(user.email).into());
+        }
+        self.repo.create_user(&user).await
+    }
+
+    async fn get_user(&self, id: i32) -> Result<User> {
+        self.repo
+            .get_user(id)
+            .await?
+            .ok_or_else(|| UserError::UserNotFound(id).into())
+    }
+
+    /// Changes the email and/or name; inactive users cannot be changed.
+    async fn update_user(&self, id: i32, update: UpdateUserRequest) -> Result<User> {
+        tracing::info!("update user {} with {:?}", id, update);
+        let mut user = self.get_user(id).await?;
+        user.update(update.email.as_deref(), update.name.as_deref())?;
+        if let Some(existing) = self.repo.get_user_by_email(&user.email).await?
+            && existing.id != user.id
+        {
+            return Err(UserError::EmailTaken(user.email).into());
+        }
+        self.repo.update_user(&user).await
+    }
+
+    /// Marks the user inactive; the record is kept.
+    async fn deactivate_user(&self, id: i32) -> Result<User> {
+        tracing::info!("deactivate user {}", id);
+        let mut user = self.get_user(id).await?;
+        user.deactivate()?;
+        self.repo.update_user(&user).await
+    }
+
+    /// Activates a deactivated user, or a pending one once their email is verified.
+    async fn activate_user(&self, id: i32) -> Result<User> {
+        tracing::info!("activate user {}", id);
+        let mut user = self.get_user(id).await?;
+        user.activate()?;
+        self.repo.update_user(&user).await
+    }
+}
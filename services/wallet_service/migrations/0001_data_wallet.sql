@@ -0,0 +1,25 @@
+-- wallet balances, one row per (user_id, currency) pocket
+CREATE SCHEMA IF NOT EXISTS WALLET_DIGITAL;
+
+DO $$ BEGIN
+    CREATE TYPE wallet_status AS ENUM ('Active', 'Inactive');
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+DO $$ BEGIN
+    CREATE TYPE currency AS ENUM ('IDR', 'USD');
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+CREATE TABLE IF NOT EXISTS WALLET_DIGITAL.DATA_WALLET (
+    id           SERIAL PRIMARY KEY,
+    norek        VARCHAR(32)   NOT NULL DEFAULT '',
+    user_id      INTEGER       NOT NULL,
+    currency     currency      NOT NULL DEFAULT 'IDR',
+    balance      NUMERIC(20,2) NOT NULL DEFAULT 0,
+    status       wallet_status NOT NULL DEFAULT 'Active',
+    created_date TIMESTAMPTZ   NOT NULL DEFAULT now(),
+    updated_date TIMESTAMPTZ,
+    CONSTRAINT data_wallet_user_currency_key UNIQUE (user_id, currency),
+    CONSTRAINT data_wallet_balance_check CHECK (balance >= 0)
+);
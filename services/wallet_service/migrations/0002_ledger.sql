@@ -0,0 +1,40 @@
+-- double-entry ledger: every balance change is a journal entry whose postings sum to zero per currency
+CREATE TABLE IF NOT EXISTS WALLET_DIGITAL.LEDGER_JOURNAL (
+    id           BIGSERIAL PRIMARY KEY,
+    reference    VARCHAR(64)  NOT NULL,
+    description  VARCHAR(128) NOT NULL,
+    created_date TIMESTAMPTZ  NOT NULL DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS WALLET_DIGITAL.LEDGER_POSTING (
+    id           BIGSERIAL PRIMARY KEY,
+    journal_id   BIGINT        NOT NULL REFERENCES WALLET_DIGITAL.LEDGER_JOURNAL (id),
+    account      VARCHAR(64)   NOT NULL,
+    currency     currency      NOT NULL,
+    amount       NUMERIC(20,2) NOT NULL CHECK (amount <> 0),
+    created_date TIMESTAMPTZ   NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS ledger_posting_account_idx
+    ON WALLET_DIGITAL.LEDGER_POSTING (account, currency);
+
+-- postings are append-only
+CREATE OR REPLACE RULE ledger_posting_no_update AS ON UPDATE TO WALLET_DIGITAL.LEDGER_POSTING DO INSTEAD NOTHING;
+CREATE OR REPLACE RULE ledger_posting_no_delete AS ON DELETE TO WALLET_DIGITAL.LEDGER_POSTING DO INSTEAD NOTHING;
+
+-- opening balances for wallets that existed before the ledger
+WITH opening AS (
+    INSERT INTO WALLET_DIGITAL.LEDGER_JOURNAL (reference, description)
+    SELECT 'opening-' || w.id, 'opening balance'
+    FROM WALLET_DIGITAL.DATA_WALLET w
+    WHERE w.balance <> 0
+      AND NOT EXISTS (SELECT 1 FROM WALLET_DIGITAL.LEDGER_POSTING p
+                      WHERE p.account = 'wallet:' || w.user_id AND p.currency = w.currency)
+    RETURNING id, reference
+)
+INSERT INTO WALLET_DIGITAL.LEDGER_POSTING (journal_id, account, currency, amount)
+SELECT o.id, posting.account, w.currency, posting.amount
+FROM opening o
+JOIN WALLET_DIGITAL.DATA_WALLET w ON o.reference = 'opening-' || w.id
+CROSS JOIN LATERAL (VALUES ('wallet:' || w.user_id, w.balance),
+                           ('system:external', -w.balance)) AS posting (account, amount);
@@ -0,0 +1,8 @@
+-- responses of POST /wallet/transfer keyed by the client's Idempotency-Key header
+CREATE TABLE IF NOT EXISTS WALLET_DIGITAL.IDEMPOTENCY_KEY (
+    idempotency_key VARCHAR(255) PRIMARY KEY,
+    request_hash    CHAR(64)     NOT NULL,
+    status_code     SMALLINT,
+    response        JSONB,
+    created_date    TIMESTAMPTZ  NOT NULL DEFAULT now()
+);
@@ -0,0 +1,3 @@
+-- a reference is booked at most once, which makes debit/credit legs safe to retry
+CREATE UNIQUE INDEX IF NOT EXISTS ledger_journal_reference_key
+    ON WALLET_DIGITAL.LEDGER_JOURNAL (reference);
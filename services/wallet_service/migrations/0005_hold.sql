@@ -0,0 +1,29 @@
+-- funds reserved by holds, the available balance of a pocket is balance - held
+ALTER TABLE WALLET_DIGITAL.DATA_WALLET
+    ADD COLUMN IF NOT EXISTS held NUMERIC(20,2) NOT NULL DEFAULT 0;
+
+ALTER TABLE WALLET_DIGITAL.DATA_WALLET
+    DROP CONSTRAINT IF EXISTS data_wallet_held_check;
+ALTER TABLE WALLET_DIGITAL.DATA_WALLET
+    ADD CONSTRAINT data_wallet_held_check CHECK (held >= 0 AND held <= balance);
+
+DO $$ BEGIN
+    CREATE TYPE hold_status AS ENUM ('Active', 'Captured', 'Released', 'Expired');
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+CREATE TABLE IF NOT EXISTS WALLET_DIGITAL.DATA_HOLD (
+    id              SERIAL PRIMARY KEY,
+    reference       VARCHAR(64)   NOT NULL UNIQUE,
+    user_id         INTEGER       NOT NULL,
+    currency        currency      NOT NULL,
+    amount          NUMERIC(20,2) NOT NULL CHECK (amount > 0),
+    captured_amount NUMERIC(20,2),
+    status          hold_status   NOT NULL DEFAULT 'Active',
+    expires_at      TIMESTAMPTZ   NOT NULL,
+    created_date    TIMESTAMPTZ   NOT NULL DEFAULT now(),
+    updated_date    TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS data_hold_active_expiry_idx
+    ON WALLET_DIGITAL.DATA_HOLD (expires_at) WHERE status = 'Active';
@@ -0,0 +1,34 @@
+-- tier of each user, picks the default limits from the tier table in config
+DO $$ BEGIN
+    CREATE TYPE user_tier AS ENUM ('Basic', 'Verified', 'Premium');
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+CREATE TABLE IF NOT EXISTS WALLET_DIGITAL.USER_TIER (
+    user_id      INTEGER     PRIMARY KEY,
+    tier         user_tier   NOT NULL DEFAULT 'Basic',
+    updated_date TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- per-wallet overrides of the tier limits, NULL keeps the tier's rule
+CREATE TABLE IF NOT EXISTS WALLET_DIGITAL.SPENDING_LIMIT (
+    user_id                INTEGER       NOT NULL,
+    currency               currency      NOT NULL,
+    max_single_transfer    NUMERIC(20,2),
+    daily_outgoing         NUMERIC(20,2),
+    monthly_outgoing       NUMERIC(20,2),
+    max_transfers_per_hour INTEGER,
+    updated_date           TIMESTAMPTZ   NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, currency)
+);
+
+-- outgoing totals per pocket and calendar hour/day/month
+CREATE TABLE IF NOT EXISTS WALLET_DIGITAL.SPENDING_COUNTER (
+    user_id      INTEGER       NOT NULL,
+    currency     currency      NOT NULL,
+    period       VARCHAR(8)    NOT NULL CHECK (period IN ('hour', 'day', 'month')),
+    period_start TIMESTAMPTZ   NOT NULL,
+    total        NUMERIC(20,2) NOT NULL DEFAULT 0,
+    count        INTEGER       NOT NULL DEFAULT 0,
+    PRIMARY KEY (user_id, currency, period, period_start)
+);
@@ -0,0 +1,28 @@
+-- wallet lifecycle beyond Active/Inactive, see WalletStatus in the domain crate
+ALTER TYPE wallet_status ADD VALUE IF NOT EXISTS 'Frozen';
+ALTER TYPE wallet_status ADD VALUE IF NOT EXISTS 'Dormant';
+ALTER TYPE wallet_status ADD VALUE IF NOT EXISTS 'PendingClosure';
+ALTER TYPE wallet_status ADD VALUE IF NOT EXISTS 'Closed';
+
+DO $$ BEGIN
+    CREATE TYPE wallet_status_reason AS ENUM (
+        'CustomerRequest', 'SuspectedFraud', 'ComplianceReview', 'LegalOrder',
+        'Inactivity', 'ReviewCleared', 'Other'
+    );
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+-- audit trail of every status change, append only
+CREATE TABLE IF NOT EXISTS WALLET_DIGITAL.WALLET_STATUS_HISTORY (
+    id           SERIAL PRIMARY KEY,
+    user_id      INTEGER              NOT NULL,
+    from_status  wallet_status        NOT NULL,
+    to_status    wallet_status        NOT NULL,
+    reason       wallet_status_reason NOT NULL,
+    note         TEXT,
+    actor        VARCHAR(128)         NOT NULL,
+    created_date TIMESTAMPTZ          NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS wallet_status_history_user_idx
+    ON WALLET_DIGITAL.WALLET_STATUS_HISTORY (user_id, created_date);
@@ -0,0 +1,13 @@
+-- one receipt per closed wallet; the sweep itself is in the ledger under
+-- references starting with the receipt's reference
+CREATE TABLE IF NOT EXISTS WALLET_DIGITAL.WALLET_CLOSURE (
+    reference           VARCHAR(64)          PRIMARY KEY,
+    user_id             INTEGER              NOT NULL UNIQUE,
+    -- NULL when the balance went to the suspense account
+    destination_user_id INTEGER,
+    swept               JSONB                NOT NULL,
+    reason              wallet_status_reason NOT NULL,
+    note                TEXT,
+    actor               VARCHAR(128)         NOT NULL,
+    created_date        TIMESTAMPTZ          NOT NULL DEFAULT now()
+);
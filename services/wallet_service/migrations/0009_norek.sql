@@ -0,0 +1,8 @@
+-- serials of account numbers, the number itself (prefix, serial, check digits)
+-- is built by NorekFormat so a serial is never handed out twice
+CREATE SEQUENCE IF NOT EXISTS WALLET_DIGITAL.NOREK_SEQ;
+
+-- every pocket of a wallet carries the wallet's norek; wallets created before
+-- numbers were generated keep '' until the service assigns one at startup
+CREATE UNIQUE INDEX IF NOT EXISTS data_wallet_norek_currency_key
+    ON WALLET_DIGITAL.DATA_WALLET (norek, currency) WHERE norek <> '';
@@ -0,0 +1,9 @@
+-- keys are scoped to the caller that sent them, claimed_at tells an abandoned
+-- claim apart from a request still running
+ALTER TABLE WALLET_DIGITAL.IDEMPOTENCY_KEY ADD COLUMN IF NOT EXISTS caller VARCHAR(255) NOT NULL DEFAULT '';
+ALTER TABLE WALLET_DIGITAL.IDEMPOTENCY_KEY ADD COLUMN IF NOT EXISTS claimed_at TIMESTAMPTZ NOT NULL DEFAULT now();
+ALTER TABLE WALLET_DIGITAL.IDEMPOTENCY_KEY
+    DROP CONSTRAINT IF EXISTS idempotency_key_pkey,
+    ADD CONSTRAINT idempotency_key_pkey PRIMARY KEY (caller, idempotency_key);
+-- expired keys are purged by age
+CREATE INDEX IF NOT EXISTS idempotency_key_created_date_idx ON WALLET_DIGITAL.IDEMPOTENCY_KEY (created_date);
@@ -1,21 +1,57 @@
+use domain::wallet::norek::CheckDigit;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use crate::usecase::hold::HoldUsecase;
+use crate::usecase::idempotency::IdempotencyUsecase;
 use crate::usecase::wallet::Usecase;
 
 #[derive(Clone)]
 pub struct AppState {
-    pub config: AppConfig,
-    pub usecase: Usecase
+    pub usecase: Usecase,
+    pub idempotency: IdempotencyUsecase,
+    pub holds: HoldUsecase,
 }
 
+/// Settings of the service itself; database, logging and HTTP client settings
+/// are read from the environment by `lib` when those are initialised.
 #[derive(Debug, Deserialize, Serialize, Default, Clone)]
 pub struct AppConfig {
-    pub db_host : String,
-    pub db_user : String,
-    pub db_pwd : String,
-    pub log_format: String,
-    pub log_level : String,
-    pub user_host : String,
-    pub transfer_host : String,
-    pub http_timeout_seconds : u64,
-    pub http_client_retry_attempts : u32,
-}
\ No newline at end of file
+    pub port : u16,
+    /// how often holds past their expiry are released
+    pub hold_expiry_interval_seconds : u64,
+    /// how long in-flight requests may take to finish after SIGTERM
+    pub shutdown_grace_seconds : u64,
+    /// how long users fetched from user-service are cached
+    pub user_cache_ttl_seconds : u64,
+    /// leading digits of every generated account number
+    pub norek_prefix : String,
+    /// total digits of an account number, check digits included
+    pub norek_length : usize,
+    pub norek_check_digit : CheckDigit,
+    /// how long idempotency keys are remembered
+    pub idempotency_key_ttl_seconds : u64,
+}
+
+impl AppConfig {
+    pub fn from_env() -> Self {
+        dotenvy::dotenv().ok();
+        Self {
+            port: env_or("PORT", 8087),
+            hold_expiry_interval_seconds: env_or("HOLD_EXPIRY_INTERVAL_SECONDS", 60),
+            shutdown_grace_seconds: env_or("SHUTDOWN_GRACE_SECONDS", 30),
+            user_cache_ttl_seconds: env_or("USER_CACHE_TTL_SECONDS", 30),
+            norek_prefix: env_or("NOREK_PREFIX", "88".to_string()),
+            norek_length: env_or("NOREK_LENGTH", 12),
+            norek_check_digit: env_or("NOREK_CHECK_DIGIT", CheckDigit::Luhn),
+            idempotency_key_ttl_seconds: env_or("IDEMPOTENCY_KEY_TTL_SECONDS", 86_400),
+        }
+    }
+}
+
+/// value of the environment variable, `default` when unset or unparsable
+fn env_or<T: FromStr>(key: &str, default: T) -> T {
+    std::env::var(key)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
@@ -1,17 +1,95 @@
-use domain::wallet::wallet::WalletStatus;
+use domain::money::money::{Currency, Money};
+use domain::transfer::transfer::Transfer;
+use domain::user::user::UserTier;
+use domain::wallet::limit::SpendingLimits;
+use domain::wallet::wallet::{StatusReason, Wallet};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
-pub struct Request {
-    pub id: Option<i32>,
-    pub email: Option<String>,
-    pub name: Option<String>,
-    pub status: Option<WalletStatus>,
+pub struct TransferRequest {
+    pub from_id: i32,
+    /// receiver by user id, or by account number with `to_norek`; exactly one is given
+    #[serde(default)]
+    pub to_id: Option<i32>,
+    // skipped when absent so idempotency hashes of by-id requests stay the same
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to_norek: Option<String>,
+    pub amount: Money,
+    /// currency to credit the receiver in, converting when it differs from `amount`
+    #[serde(default)]
+    pub target_currency: Option<Currency>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
-pub struct TransferRequest {
-    pub from_id: i32,
-    pub to_id: i32,
-    pub amount: f64,
+pub struct TransferResponse {
+    pub wallet: Wallet,
+    pub transfer: Transfer,
+}
+
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PocketRequest {
+    pub user_id: i32,
+    pub currency: Currency,
+}
+
+/// debit or credit leg of a transfer driven by transfer-service
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct MovementRequest {
+    pub user_id: i32,
+    pub amount: Money,
+    /// unique per leg, a retried request with the same reference is applied once
+    pub reference: String,
+    /// credit refunding an earlier debit, allowed for deactivated users, or
+    /// debit reversing an earlier credit, not counted against spending limits
+    #[serde(default)]
+    pub compensation: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PlaceHoldRequest {
+    pub user_id: i32,
+    pub amount: Money,
+    /// how long the funds stay reserved before the hold is released automatically,
+    /// 1 second up to 30 days
+    pub expires_in_seconds: i64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct CaptureHoldRequest {
+    /// amount to capture, the whole hold when omitted
+    #[serde(default)]
+    pub amount: Option<Money>,
+}
+
+/// admin update of a user's tier and per-wallet limit overrides
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct LimitRequest {
+    pub user_id: i32,
+    #[serde(default)]
+    pub tier: Option<UserTier>,
+    /// overrides for one currency pocket, rules left out fall back to the tier
+    #[serde(default)]
+    pub limits: Option<SpendingLimits>,
+}
+
+/// admin freeze or unfreeze of a wallet, recorded in its status history
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct StatusChangeRequest {
+    pub reason: StatusReason,
+    /// free text for the audit trail, e.g. a case number
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// closure of a wallet, its remaining balance goes to `sweep_to`'s wallet
+/// or to the suspense account when none is nominated
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct CloseWalletRequest {
+    #[serde(default)]
+    pub sweep_to: Option<i32>,
+    #[serde(default)]
+    pub reason: StatusReason,
+    #[serde(default)]
+    pub note: Option<String>,
 }
@@ -0,0 +1,210 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use domain::base::base::BaseResponse;
+use lib::trace::trace_id::current_trace_id;
+use domain::ledger::error::LedgerError;
+use domain::transfer::error::TransferError;
+use domain::wallet::error::WalletError;
+use lib::auth::error::AuthError;
+use lib::http_client::error::HttpClientError;
+use thiserror::Error;
+
+pub type ServiceResult<T> = Result<T, WalletServiceError>;
+
+/// Errors surfaced by wallet-service handlers. Each maps to one HTTP status
+/// and a stable `error_code`; upstream and internal details are only logged.
+#[derive(Debug, Error)]
+pub enum WalletServiceError {
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    InsufficientFunds(String),
+    #[error("{0}")]
+    InvalidAmount(String),
+    #[error("{0}")]
+    InvalidRequest(String),
+    #[error("{0}")]
+    Conflict(String),
+    #[error("{0}")]
+    LimitExceeded(String),
+    #[error("{0}")]
+    UserInactive(String),
+    #[error("{0}")]
+    WalletBlocked(String),
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("Upstream call failed: {0}")]
+    Upstream(String),
+    #[error(transparent)]
+    Internal(anyhow::Error),
+}
+
+impl WalletServiceError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            WalletServiceError::NotFound(_) => StatusCode::NOT_FOUND,
+            WalletServiceError::InsufficientFunds(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            WalletServiceError::InvalidAmount(_) => StatusCode::BAD_REQUEST,
+            WalletServiceError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            WalletServiceError::Conflict(_) => StatusCode::CONFLICT,
+            WalletServiceError::LimitExceeded(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            WalletServiceError::UserInactive(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            WalletServiceError::WalletBlocked(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            WalletServiceError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            WalletServiceError::Forbidden(_) => StatusCode::FORBIDDEN,
+            WalletServiceError::Upstream(_) => StatusCode::BAD_GATEWAY,
+            WalletServiceError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            WalletServiceError::NotFound(_) => "NOT_FOUND",
+            WalletServiceError::InsufficientFunds(_) => "INSUFFICIENT_FUNDS",
+            WalletServiceError::InvalidAmount(_) => "INVALID_AMOUNT",
+            WalletServiceError::InvalidRequest(_) => "INVALID_REQUEST",
+            WalletServiceError::Conflict(_) => "CONFLICT",
+            WalletServiceError::LimitExceeded(_) => "LIMIT_EXCEEDED",
+            WalletServiceError::UserInactive(_) => "USER_INACTIVE",
+            WalletServiceError::WalletBlocked(_) => "WALLET_BLOCKED",
+            WalletServiceError::Unauthorized(_) => "UNAUTHORIZED",
+            WalletServiceError::Forbidden(_) => "FORBIDDEN",
+            WalletServiceError::Upstream(_) => "UPSTREAM_ERROR",
+            WalletServiceError::Internal(_) => "INTERNAL_ERROR",
+        }
+    }
+
+    /// message safe to show to clients
+    fn public_message(&self) -> String {
+        match self {
+            WalletServiceError::Upstream(_) => "Upstream service unavailable".to_string(),
+            WalletServiceError::Internal(_) => "Internal server error".to_string(),
+            other => other.to_string(),
+        }
+    }
+
+    /// Status and error body for any response type, used where the body is
+    /// also stored, e.g. for idempotent replays.
+    pub fn to_response<T>(&self) -> (StatusCode, BaseResponse<T>) {
+        let status = self.status();
+        if status.is_server_error() {
+            tracing::error!("{}: {:#}", self.code(), self);
+        } else {
+            tracing::warn!("{}: {}", self.code(), self);
+        }
+        (
+            status,
+            BaseResponse::error(current_trace_id(), self.code(), self.public_message()),
+        )
+    }
+}
+
+impl IntoResponse for WalletServiceError {
+    fn into_response(self) -> Response {
+        let (status, body) = self.to_response::<()>();
+        (status, Json(body)).into_response()
+    }
+}
+
+impl From<AuthError> for WalletServiceError {
+    fn from(value: AuthError) -> Self {
+        match value {
+            AuthError::Forbidden(message) => WalletServiceError::Forbidden(message),
+            AuthError::InvalidConfig(_) => WalletServiceError::Internal(value.into()),
+            other => WalletServiceError::Unauthorized(other.public_message()),
+        }
+    }
+}
+
+impl From<WalletError> for WalletServiceError {
+    fn from(value: WalletError) -> Self {
+        let message = value.to_string();
+        match value {
+            WalletError::WalletNotFound(_)
+            | WalletError::HoldNotFound(_)
+            | WalletError::UserNotFound(_)
+            | WalletError::NorekNotFound(_) => {
+                WalletServiceError::NotFound(message)
+            }
+            WalletError::InsufficientBalance(_, _) => WalletServiceError::InsufficientFunds(message),
+            WalletError::InvalidAmount(_)
+            | WalletError::MalformedAmount(_)
+            | WalletError::UnsupportedCurrency(_)
+            | WalletError::CurrencyMismatch(_, _)
+            | WalletError::CurrencyNotHeld(_)
+            | WalletError::CaptureExceedsHold(_, _)
+            | WalletError::Overflow => WalletServiceError::InvalidAmount(message),
+            WalletError::HoldNotActive(_)
+            | WalletError::HoldExpired(_)
+            | WalletError::HoldNotExpired(_)
+            | WalletError::IllegalStatusTransition(_, _)
+            | WalletError::StatusConflict(_)
+            | WalletError::HoldsOutstanding(_)
+            | WalletError::PendingTransfers(_, _)
+            | WalletError::AlreadyApplied(_) => WalletServiceError::Conflict(message),
+            WalletError::InvalidSweepTarget(_)
+            | WalletError::InvalidNorek(_, _)
+            | WalletError::InvalidHoldExpiry(_, _) => {
+                WalletServiceError::InvalidRequest(message)
+            }
+            WalletError::LimitExceeded(_) => WalletServiceError::LimitExceeded(message),
+            WalletError::UserInactive(_) => WalletServiceError::UserInactive(message),
+            WalletError::DebitsBlocked(_) | WalletError::CreditsBlocked(_) => {
+                WalletServiceError::WalletBlocked(message)
+            }
+            WalletError::InvalidSpread(_)
+            | WalletError::InvalidNorekFormat(_)
+            | WalletError::NorekExhausted(_) => WalletServiceError::Internal(value.into()),
+        }
+    }
+}
+
+impl From<TransferError> for WalletServiceError {
+    fn from(value: TransferError) -> Self {
+        match value {
+            TransferError::Amount(e) => e.into(),
+            other => WalletServiceError::Internal(other.into()),
+        }
+    }
+}
+
+impl From<LedgerError> for WalletServiceError {
+    fn from(value: LedgerError) -> Self {
+        match value {
+            LedgerError::Amount(e) => e.into(),
+            other => WalletServiceError::Internal(other.into()),
+        }
+    }
+}
+
+impl From<HttpClientError> for WalletServiceError {
+    fn from(value: HttpClientError) -> Self {
+        WalletServiceError::Upstream(value.to_string())
+    }
+}
+
+/// Repositories report through `anyhow`; typed causes are recovered here and
+/// everything else is internal.
+impl From<anyhow::Error> for WalletServiceError {
+    fn from(value: anyhow::Error) -> Self {
+        let value = match value.downcast::<WalletError>() {
+            Ok(e) => return e.into(),
+            Err(value) => value,
+        };
+        let value = match value.downcast::<LedgerError>() {
+            Ok(e) => return e.into(),
+            Err(value) => value,
+        };
+        let value = match value.downcast::<TransferError>() {
+            Ok(e) => return e.into(),
+            Err(value) => value,
+        };
+        match value.downcast::<HttpClientError>() {
+            Ok(e) => e.into(),
+            Err(value) => WalletServiceError::Internal(value),
+        }
+    }
+}
@@ -1,3 +1,12 @@
+use axum::Json;
+use lib::http_client::breaker::{breaker_states, BreakerState};
+use std::collections::HashMap;
+
 pub async fn health_check() -> &'static str {
     "Pong! Wallet service is healthy!"
 }
+
+/// State of the circuit breaker of every downstream service called so far.
+pub async fn dependencies() -> Json<HashMap<String, BreakerState>> {
+    Json(breaker_states())
+}
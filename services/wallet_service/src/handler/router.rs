@@ -1,12 +1,34 @@
 use crate::app::AppState;
-use crate::handler::wallet::{delete_wallet, get_wallet_by_id, transfer_wallet};
-use axum::routing::{get, post};
+use crate::handler::health::{dependencies, health_check};
+use crate::handler::wallet::{
+    capture_hold, close_wallet, credit_wallet, debit_wallet, delete_wallet, freeze_wallet, get_status_history,
+    get_wallet_by_id, get_wallet_by_norek, get_wallet_history, open_pocket, place_hold, release_hold, set_limits,
+    transfer_wallet, unfreeze_wallet,
+};
+use axum::routing::{delete, get, post};
 use axum::Router;
+use lib::trace::trace_id::TraceIdLayer;
 
 pub fn routes(app_state: AppState) -> Router {
     Router::new()
+        .route("/health", get(health_check))
+        .route("/health/dependencies", get(dependencies))
         .route("/wallet/transfer", post(transfer_wallet))
-        .route("/wallet/delete/:id", get(delete_wallet))
-        .route("/wallet/inquiry/:id", get(get_wallet_by_id))
+        .route("/wallet/debit", post(debit_wallet))
+        .route("/wallet/credit", post(credit_wallet))
+        .route("/wallet/pocket", post(open_pocket))
+        .route("/wallet/limit", post(set_limits))
+        .route("/wallet/hold", post(place_hold))
+        .route("/wallet/hold/{reference}/capture", post(capture_hold))
+        .route("/wallet/hold/{reference}/release", post(release_hold))
+        .route("/wallet/freeze/{id}", post(freeze_wallet))
+        .route("/wallet/unfreeze/{id}", post(unfreeze_wallet))
+        .route("/wallet/status/{id}", get(get_status_history))
+        .route("/wallet/close/{id}", post(close_wallet))
+        .route("/wallet/delete/{id}", delete(delete_wallet))
+        .route("/wallet/inquiry/{id}", get(get_wallet_by_id))
+        .route("/wallet/norek/{norek}", get(get_wallet_by_norek))
+        .route("/wallet/history/{id}", get(get_wallet_history))
+        .layer(TraceIdLayer)
         .with_state(app_state)
 }
\ No newline at end of file
@@ -1,17 +1,34 @@
 use crate::app::AppState;
-use crate::domain::dto::TransferRequest;
+use crate::domain::dto::{
+    CaptureHoldRequest, CloseWalletRequest, LimitRequest, MovementRequest, PlaceHoldRequest, PocketRequest, StatusChangeRequest,
+    TransferRequest, TransferResponse,
+};
+use crate::domain::error::{ServiceResult, WalletServiceError};
+use crate::usecase::idempotency::{IdempotencyUsecase, IdempotentOutcome, IDEMPOTENCY_KEY_HEADER};
 use crate::usecase::wallet::Wallet;
 use axum::extract::{Path, State};
+use axum::http::HeaderMap;
 use axum::Json;
 use domain::base::base::BaseResponse;
+use lib::auth::extractor::AuthUser;
+use lib::trace::trace_id::current_trace_id;
+use domain::ledger::ledger::JournalEntry;
+use domain::wallet::hold::Hold;
 use domain::wallet::wallet::Wallet as WalletDomain;
+use domain::wallet::closure::ClosureReceipt;
+use domain::wallet::error::WalletError;
+use domain::wallet::wallet::{StatusReason, WalletStatusTransition};
 use reqwest::StatusCode;
 
+/// resource name of the `wallet:admin` scope, which may act on any wallet
+const WALLET: &str = "wallet";
+
 /// Transfer between 2 wallets
 /// request :
 ///   - sender id
-///   - receiver id
+///   - receiver id, or receiver account number (norek)
 ///   - amount
+///   - target currency (optional, converts the amount for the receiver)
 ///
 /// Validates the sender's balance.
 ///
@@ -22,26 +39,202 @@ use reqwest::StatusCode;
 /// - Proceeds with the transaction.
 /// - Deducts the specified amount from the sender's balance.
 /// - Adds the specified amount to the receiver's balance.
+///
+/// When the request carries an `Idempotency-Key` header, a retry with the same
+/// key and body gets the stored response instead of moving money again, and a
+/// reused key with a different body is rejected with 409, as is a retry while
+/// the first request has no outcome yet. Keys are scoped to the caller and
+/// forgotten after `IDEMPOTENCY_KEY_TTL_SECONDS`; the transaction id is derived
+/// from the key, so even then the same transfer is never booked twice.
+///
+/// Only the sender, or a `wallet:admin`, may transfer out of a wallet.
 pub async fn transfer_wallet(
     State(state): State<AppState>,
+    auth: AuthUser,
+    headers: HeaderMap,
     Json(request): Json<TransferRequest>,
-) -> (StatusCode, Json<BaseResponse<WalletDomain>>) {
+) -> (StatusCode, Json<BaseResponse<TransferResponse>>) {
     tracing::info!("inquiry wallet for request: {:?}", request);
+    if let Err(e) = auth.ensure_owner(WALLET, request.from_id) {
+        return error_response(e.into());
+    }
+    let key = headers
+        .get(IDEMPOTENCY_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let Some(key) = key else {
+        return execute_transfer(&state, &request, None).await;
+    };
+
+    let request_hash = match IdempotencyUsecase::request_hash(&request) {
+        Ok(request_hash) => request_hash,
+        Err(e) => return error_response(e.into()),
+    };
+    match state.idempotency.begin(&auth.subject, &key, &request_hash).await {
+        Ok(IdempotentOutcome::Proceed) => {
+            let transaction_id = IdempotencyUsecase::transaction_id(&auth.subject, &key, &request_hash);
+            let (status, Json(response)) = execute_transfer(&state, &request, Some(transaction_id)).await;
+            if let Err(e) = state
+                .idempotency
+                .complete(&auth.subject, &key, status.as_u16(), &response)
+                .await
+            {
+                tracing::error!("failed to store response for idempotency key {:?}: {}", key, e);
+            }
+            (status, Json::from(response))
+        }
+        Ok(IdempotentOutcome::Replay(status_code, stored)) => {
+            match serde_json::from_value::<BaseResponse<TransferResponse>>(stored) {
+                Ok(mut response) => {
+                    response.trace_id = current_trace_id();
+                    (
+                        StatusCode::from_u16(status_code).unwrap_or(StatusCode::OK),
+                        Json::from(response),
+                    )
+                }
+                Err(e) => error_response(WalletServiceError::Internal(e.into())),
+            }
+        }
+        Ok(IdempotentOutcome::Conflict(message)) => {
+            error_response(WalletServiceError::Conflict(message.to_string()))
+        }
+        Err(e) => error_response(e.into()),
+    }
+}
+
+async fn execute_transfer(
+    state: &AppState,
+    request: &TransferRequest,
+    transaction_id: Option<String>,
+) -> (StatusCode, Json<BaseResponse<TransferResponse>>) {
+    let to_id = match state
+        .usecase
+        .resolve_recipient(request.to_id, request.to_norek.as_deref())
+        .await
+    {
+        Ok(to_id) => to_id,
+        Err(e) => return error_response(e),
+    };
     match state
         .usecase
-        .transfer_balance(request.from_id, request.to_id, request.amount)
+        .transfer_balance(
+            request.from_id,
+            to_id,
+            request.amount,
+            request.target_currency,
+            transaction_id,
+        )
         .await
     {
         Ok(data) => {
-            let response: BaseResponse<WalletDomain> =
-                BaseResponse::new("".to_string(), "Success".to_string(), Some(data));
+            let response: BaseResponse<TransferResponse> =
+                BaseResponse::new(current_trace_id(), "Success".to_string(), Some(data));
             (StatusCode::OK, Json::from(response))
         }
-        Err(e) => {
-            let response = BaseResponse::new("".to_string(), format!("{e}"), None);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json::from(response))
-        }
+        Err(e) => error_response(e),
+    }
+}
+
+/// Debits the wallet as one leg of a transfer orchestrated by transfer-service.
+///
+/// Business rejections (unknown wallet, insufficient balance) answer 422 so
+/// the caller knows nothing was debited; a retry with the same reference is a no-op.
+///
+/// Users move money out through `/wallet/transfer`, so this needs `wallet:admin`.
+pub async fn debit_wallet(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(request): Json<MovementRequest>,
+) -> ServiceResult<Json<BaseResponse<WalletDomain>>> {
+    tracing::info!("debit wallet for request: {:?}", request);
+    auth.ensure_admin(WALLET)?;
+    let result = state
+        .usecase
+        .debit_wallet(request.user_id, request.amount, &request.reference, request.compensation)
+        .await;
+    success(result?)
+}
+
+/// Credits the wallet as one leg of a transfer orchestrated by transfer-service,
+/// also used to refund a sender when the transfer is compensated.
+///
+/// Money only enters a wallet through a transfer, so this needs `wallet:admin`.
+pub async fn credit_wallet(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(request): Json<MovementRequest>,
+) -> ServiceResult<Json<BaseResponse<WalletDomain>>> {
+    tracing::info!("credit wallet for request: {:?}", request);
+    auth.ensure_admin(WALLET)?;
+    let result = state
+        .usecase
+        .credit_wallet(request.user_id, request.amount, &request.reference, request.compensation)
+        .await;
+    success(result?)
+}
+
+/// Reserves funds on the wallet; they stop counting towards the available
+/// balance until the hold is captured, released or expires.
+pub async fn place_hold(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(request): Json<PlaceHoldRequest>,
+) -> ServiceResult<Json<BaseResponse<Hold>>> {
+    tracing::info!("place hold for request: {:?}", request);
+    auth.ensure_owner(WALLET, request.user_id)?;
+    let result = state
+        .holds
+        .place_hold(request.user_id, request.amount, request.expires_in_seconds)
+        .await;
+    success(result?)
+}
+
+/// Debits the captured amount from the wallet and releases the rest of the hold.
+pub async fn capture_hold(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(reference): Path<String>,
+    request: Option<Json<CaptureHoldRequest>>,
+) -> ServiceResult<Json<BaseResponse<Hold>>> {
+    let Json(request) = request.unwrap_or_default();
+    tracing::info!("capture hold {:?} for request: {:?}", reference, request);
+    ensure_hold_owner(&state, &auth, &reference).await?;
+    let result = state.holds.capture_hold(&reference, request.amount).await;
+    success(result?)
+}
+
+/// Gives the held funds back to the available balance.
+pub async fn release_hold(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(reference): Path<String>,
+) -> ServiceResult<Json<BaseResponse<Hold>>> {
+    tracing::info!("release hold {:?}", reference);
+    ensure_hold_owner(&state, &auth, &reference).await?;
+    let result = state.holds.release_hold(&reference).await;
+    success(result?)
+}
+
+/// holds are acted on by the owner of the held wallet, or a `wallet:admin`
+async fn ensure_hold_owner(state: &AppState, auth: &AuthUser, reference: &str) -> ServiceResult<()> {
+    if auth.is_admin(WALLET) {
+        return Ok(());
     }
+    let hold = state.holds.get_hold(reference).await?;
+    Ok(auth.ensure_owner(WALLET, hold.user_id)?)
+}
+
+fn success<T>(data: T) -> ServiceResult<Json<BaseResponse<T>>> {
+    Ok(Json::from(BaseResponse::new(
+        current_trace_id(),
+        "Success".to_string(),
+        Some(data),
+    )))
+}
+
+fn error_response<T>(error: WalletServiceError) -> (StatusCode, Json<BaseResponse<T>>) {
+    let (status, response) = error.to_response();
+    (status, Json::from(response))
 }
 
 /// Retrieves the wallet by its ID.
@@ -49,44 +242,164 @@ pub async fn transfer_wallet(
 /// If the wallet does not exist, a new wallet is created with a balance of 0.
 pub async fn get_wallet_by_id(
     State(state): State<AppState>,
+    auth: AuthUser,
     Path(id): Path<i32>,
-) -> (StatusCode, Json<BaseResponse<WalletDomain>>) {
+) -> ServiceResult<Json<BaseResponse<WalletDomain>>> {
     tracing::info!("inquiry wallet for id: {:?}", id);
-    match state.usecase.get_or_create_wallet(id).await {
-        Ok(data) => {
-            let response = BaseResponse::new("".to_string(), "Success".to_string(), Some(data));
-            (StatusCode::OK, Json::from(response))
-        }
-        Err(e) => {
-            let response = BaseResponse::new("".to_string(), format!("{e}"), None);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json::from(response))
-        }
+    auth.ensure_owner(WALLET, id)?;
+    success(state.usecase.get_or_create_wallet(id).await?)
+}
+
+/// Looks a wallet up by its account number (norek), as given to payers.
+///
+/// Spaces and dashes in the number are ignored; a number failing its check
+/// digit answers 400. Only the owner, or a `wallet:admin`, sees the wallet;
+/// anyone else gets the same 404 as for an unknown number, so the endpoint
+/// cannot be used to find out which numbers exist.
+pub async fn get_wallet_by_norek(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(norek): Path<String>,
+) -> ServiceResult<Json<BaseResponse<WalletDomain>>> {
+    tracing::info!("inquiry wallet for norek: {:?}", norek);
+    let wallet = state.usecase.get_wallet_by_norek(&norek).await?;
+    if auth.ensure_owner(WALLET, wallet.user_id).is_err() {
+        return Err(WalletError::NorekNotFound(wallet.norek).into());
     }
+    success(wallet)
+}
+
+/// Opens a new currency pocket on the user's wallet.
+///
+/// Opening a currency the wallet already holds returns the wallet unchanged.
+pub async fn open_pocket(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(request): Json<PocketRequest>,
+) -> ServiceResult<Json<BaseResponse<WalletDomain>>> {
+    tracing::info!("open pocket for request: {:?}", request);
+    auth.ensure_owner(WALLET, request.user_id)?;
+    success(state
+        .usecase
+        .open_pocket(request.user_id, request.currency)
+        .await?)
+}
+
+/// Retrieves the ledger history of the wallet by its user ID.
+///
+/// Each journal entry lists all of its postings, so the counterparty of
+/// every balance change can be reconstructed.
+pub async fn get_wallet_history(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<i32>,
+) -> ServiceResult<Json<BaseResponse<Vec<JournalEntry>>>> {
+    tracing::info!("history wallet for id: {:?}", id);
+    auth.ensure_owner(WALLET, id)?;
+    success(state.usecase.get_wallet_history(id).await?)
+}
+
+/// Sets the user's tier and/or overrides the spending limits of one pocket.
+///
+/// Transfers over a limit are rejected with the name of the rule they hit.
+/// Needs `wallet:admin`.
+pub async fn set_limits(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(request): Json<LimitRequest>,
+) -> ServiceResult<Json<BaseResponse<bool>>> {
+    tracing::info!("set limits for request: {:?}", request);
+    auth.ensure_admin(WALLET)?;
+    let result = state
+        .usecase
+        .set_limits(request.user_id, request.tier, request.limits)
+        .await
+        .map(|_| true);
+    success(result?)
 }
 
-/// Deletes the wallet by its ID.
-/// If the wallet exists, its status is marked as inactive.
+/// Freezes the wallet: debits, new holds and hold captures are refused
+/// while credits still land. Needs `wallet:admin`; the admin is recorded
+/// as the actor in the status history.
+pub async fn freeze_wallet(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<i32>,
+    Json(request): Json<StatusChangeRequest>,
+) -> ServiceResult<Json<BaseResponse<WalletDomain>>> {
+    tracing::info!("freeze wallet for id: {:?} request: {:?}", id, request);
+    auth.ensure_admin(WALLET)?;
+    success(state
+        .usecase
+        .freeze_wallet(id, request.reason, request.note, &auth.subject)
+        .await?)
+}
+
+/// Puts a frozen wallet back to active. Needs `wallet:admin`.
+pub async fn unfreeze_wallet(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<i32>,
+    Json(request): Json<StatusChangeRequest>,
+) -> ServiceResult<Json<BaseResponse<WalletDomain>>> {
+    tracing::info!("unfreeze wallet for id: {:?} request: {:?}", id, request);
+    auth.ensure_admin(WALLET)?;
+    success(state
+        .usecase
+        .unfreeze_wallet(id, request.reason, request.note, &auth.subject)
+        .await?)
+}
+
+/// Retrieves every status change of the wallet with its reason and actor.
+/// Needs `wallet:admin`.
+pub async fn get_status_history(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<i32>,
+) -> ServiceResult<Json<BaseResponse<Vec<WalletStatusTransition>>>> {
+    tracing::info!("status history wallet for id: {:?}", id);
+    auth.ensure_admin(WALLET)?;
+    success(state.usecase.get_status_history(id).await?)
+}
+
+/// Closes the wallet by its ID
+/// request (optional) :
+///   - sweep_to (user id whose wallet receives the remaining balance;
+///     the system suspense account when omitted)
+///   - reason, note
+///
+/// Refused with 409 while funds are on hold or transfers from or to the
+/// wallet are pending. Every pocket is swept, the wallet ends Closed and can
+/// never be reactivated; the closure receipt is returned and kept.
+/// Only the owner, or a `wallet:admin`, may close a wallet.
+pub async fn close_wallet(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<i32>,
+    request: Option<Json<CloseWalletRequest>>,
+) -> ServiceResult<Json<BaseResponse<ClosureReceipt>>> {
+    let Json(request) = request.unwrap_or_default();
+    tracing::info!("close wallet for id: {:?} request: {:?}", id, request);
+    auth.ensure_owner(WALLET, id)?;
+    success(state
+        .usecase
+        .close_wallet(id, request.sweep_to, request.reason, request.note, &auth.subject)
+        .await?)
+}
+
+/// Deletes the wallet by its ID (`DELETE /wallet/delete/{id}`).
+/// The wallet is closed like [`close_wallet`] without a nominated wallet,
+/// so any remaining balance is swept to the suspense account.
 pub async fn delete_wallet(
     State(state): State<AppState>,
+    auth: AuthUser,
     Path(id): Path<i32>,
-) -> (StatusCode, Json<BaseResponse<bool>>) {
+) -> ServiceResult<Json<BaseResponse<bool>>> {
     tracing::info!("delete wallet for id: {:?}", id);
-    match state.usecase.delete_wallet(id).await {
-        Ok(_) => (
-            StatusCode::OK,
-            Json::from(BaseResponse::new(
-                "".to_string(),
-                "Success".to_string(),
-                Some(true),
-            )),
-        ),
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json::from(BaseResponse::new(
-                "".to_string(),
-                format!("{e}"),
-                Some(false),
-            )),
-        ),
-    }
+    auth.ensure_owner(WALLET, id)?;
+    state
+        .usecase
+        .close_wallet(id, None, StatusReason::CustomerRequest, None, &auth.subject)
+        .await?;
+    success(true)
 }
@@ -1,19 +1,43 @@
 mod app;
 
+use crate::app::{AppConfig, AppState};
+use crate::handler::router::routes;
+use crate::repository::db::hold::HoldRepository;
+use crate::repository::db::idempotency::IdempotencyRepository;
+use crate::repository::db::ledger::LedgerRepository;
+use crate::repository::db::limit::LimitRepository;
+use crate::repository::db::postgres::{WalletProvider, WalletRepository};
+use crate::repository::fx::rate_provider::StaticRateProvider;
+use crate::repository::http::transfer_gateway::RestTransferRepository;
+use crate::repository::http::user_gateway::RestRepository;
+use crate::repository::limit::tier_limits::StaticTierLimits;
+use crate::usecase::hold::HoldUsecase;
+use crate::usecase::idempotency::IdempotencyUsecase;
+use crate::usecase::wallet::Usecase;
+use ::domain::wallet::norek::NorekFormat;
+use lib::auth::jwt::init_auth;
 use lib::db::postgres::init_pool;
 use lib::http_client::client::init_http_client;
 use lib::log::logging::init;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
 
 mod repository {
     pub mod db;
+    pub mod fx;
     pub mod http;
+    pub mod limit;
 }
 
 mod usecase {
+    pub mod hold;
+    pub mod idempotency;
     pub mod wallet;
 }
 mod domain {
     pub mod dto;
+    pub mod error;
 }
 
 mod handler {
@@ -22,9 +46,101 @@ mod handler {
     pub mod wallet;
 }
 
-const SERVICE_NAME: &'static str = "WALLET_SERVICE";
+const SERVICE_NAME: &str = "WALLET_SERVICE";
+/// how often expired idempotency keys are deleted
+const IDEMPOTENCY_PURGE_INTERVAL_SECONDS: u64 = 3600;
 #[tokio::main]
 async fn main() {
     init(SERVICE_NAME);
     tracing::info!("starting wallet service ...!");
+    init_http_client();
+    init_auth();
+
+    let config = AppConfig::from_env();
+    let pool = init_pool().clone();
+    let rates = StaticRateProvider::from_env().expect("load exchange rates");
+    let tier_limits = StaticTierLimits::from_env().expect("load spending limits");
+
+    let norek = NorekFormat::new(&config.norek_prefix, config.norek_length, config.norek_check_digit)
+        .expect("valid account number format");
+    let limits = LimitRepository::new(pool.clone(), Arc::new(tier_limits));
+    let wallets = WalletRepository::new(pool.clone(), limits.clone(), norek);
+    let numbered = wallets
+        .assign_missing_noreks()
+        .await
+        .expect("assign account numbers");
+    if numbered > 0 {
+        tracing::info!("assigned account numbers to {} existing wallets", numbered);
+    }
+    let usecase = Usecase::new(
+        wallets.clone(),
+        LedgerRepository::new(pool.clone()),
+        Arc::new(rates),
+        limits.clone(),
+        Arc::new(RestRepository::new(Duration::from_secs(config.user_cache_ttl_seconds))),
+        Arc::new(RestTransferRepository),
+    );
+    let idempotency = IdempotencyUsecase::new(
+        IdempotencyRepository::new(pool.clone()),
+        Duration::from_secs(config.idempotency_key_ttl_seconds),
+    );
+    let holds = HoldUsecase::new(HoldRepository::new(pool, limits), wallets);
+
+    let hold_expiry =
+        holds.spawn_expiry_task(Duration::from_secs(config.hold_expiry_interval_seconds));
+    let key_purge = idempotency.spawn_purge_task(Duration::from_secs(IDEMPOTENCY_PURGE_INTERVAL_SECONDS));
+
+    let port = config.port;
+    let grace = Duration::from_secs(config.shutdown_grace_seconds);
+    let app = routes(AppState {
+        usecase,
+        idempotency,
+        holds,
+    });
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port))
+        .await
+        .expect("bind wallet service port");
+    tracing::info!("wallet service listening on {}", port);
+
+    // stop accepting connections on SIGTERM/ctrl-c, then give in-flight
+    // requests up to the grace period to finish
+    let (stopping_tx, mut stopping) = watch::channel(false);
+    let server = axum::serve(listener, app).with_graceful_shutdown(async move {
+        shutdown_signal().await;
+        let _ = stopping_tx.send(true);
+    });
+    tokio::select! {
+        result = async { server.await } => result.expect("run wallet service"),
+        _ = async {
+            let _ = stopping.wait_for(|stopping| *stopping).await;
+            tokio::time::sleep(grace).await;
+        } => tracing::warn!("grace period elapsed, dropping remaining connections"),
+    }
+
+    hold_expiry.abort();
+    key_purge.abort();
+    tracing::info!("wallet service stopped");
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("install ctrl-c handler");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("install SIGTERM handler")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    tracing::info!("shutdown signal received, draining in-flight requests");
 }
@@ -0,0 +1,255 @@
+use crate::repository::db::ledger::{LedgerProvider, LedgerRepository};
+use crate::repository::db::limit::{LimitProvider, LimitRepository};
+use crate::repository::db::postgres::WalletRepository;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{GenericClient, Transaction};
+use domain::base::base::AuditMetadata;
+use domain::ledger::ledger::{JournalEntry, LedgerAccount};
+use domain::money::money::{Currency, Money};
+use domain::wallet::error::WalletError;
+use domain::wallet::hold::Hold;
+use domain::wallet::wallet::WalletStatus;
+use mockall::automock;
+use tokio_postgres::Row;
+
+#[derive(Debug, Clone)]
+pub struct HoldRepository {
+    pool: deadpool_postgres::Pool,
+    ledger: LedgerRepository,
+    limits: LimitRepository,
+}
+
+#[async_trait]
+pub trait HoldProvider {
+    /// Stores the hold and reserves its amount on the wallet pocket.
+    async fn place_hold(&self, hold: &Hold) -> Result<Hold>;
+    async fn get_hold(&self, reference: &str) -> Result<Option<Hold>>;
+    /// Debits the captured amount, counting it against the spending limits,
+    /// and releases the reservation.
+    async fn capture_hold(&self, reference: &str, amount: Money) -> Result<Hold>;
+    /// Gives the reservation back to the available balance.
+    async fn release_hold(&self, reference: &str) -> Result<Hold>;
+    /// Releases every active hold past its expiry, returns the expired holds.
+    async fn expire_holds(&self, now: DateTime<Utc>) -> Result<Vec<Hold>>;
+}
+
+const HOLD_COLUMNS: &str =
+    "id, reference, user_id, currency, amount, captured_amount, status, expires_at, created_date, updated_date";
+
+impl HoldRepository {
+    pub fn new(pool: deadpool_postgres::Pool, limits: LimitRepository) -> Self {
+        Self {
+            ledger: LedgerRepository::new(pool.clone()),
+            limits,
+            pool,
+        }
+    }
+
+    fn hold_from_row(row: &Row) -> Hold {
+        let currency: Currency = row.get("currency");
+        let amount: Money = row.get("amount");
+        let captured_amount: Option<Money> = row.get("captured_amount");
+        Hold {
+            id: Some(row.get("id")),
+            reference: row.get("reference"),
+            user_id: row.get("user_id"),
+            amount: amount.with_currency(currency),
+            captured_amount: captured_amount.map(|c| c.with_currency(currency)),
+            status: row.get("status"),
+            expires_at: row.get("expires_at"),
+            audit: AuditMetadata {
+                created_date: row.get("created_date"),
+                updated_date: row.get("updated_date"),
+            },
+        }
+    }
+
+    async fn lock_hold(tx: &Transaction<'_>, reference: &str) -> Result<Hold> {
+        let row = tx
+            .query_opt(
+                &format!("SELECT {} FROM WALLET_DIGITAL.DATA_HOLD WHERE reference = $1 FOR UPDATE", HOLD_COLUMNS),
+                &[&reference],
+            )
+            .await?
+            .ok_or_else(|| WalletError::HoldNotFound(reference.to_string()))?;
+        Ok(Self::hold_from_row(&row))
+    }
+
+    async fn save_hold_status(tx: &Transaction<'_>, hold: &Hold) -> Result<()> {
+        tx.execute(
+            "UPDATE WALLET_DIGITAL.DATA_HOLD SET status = $1, captured_amount = $2, updated_date = $3
+             WHERE reference = $4",
+            &[&hold.status, &hold.captured_amount, &hold.audit.updated_date, &hold.reference],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// takes the hold's amount out of the pocket's reservation
+    async fn unreserve(tx: &Transaction<'_>, hold: &Hold) -> Result<()> {
+        let now = Utc::now();
+        let currency = hold.amount.currency();
+        tx.execute(
+            "UPDATE WALLET_DIGITAL.DATA_WALLET SET held = held - $1, updated_date = $2
+             WHERE user_id = $3 AND currency = $4",
+            &[&hold.amount, &now, &hold.user_id, &currency],
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn finish_hold(&self, reference: &str, finish: impl FnOnce(&mut Hold) -> Result<(), WalletError> + Send) -> Result<Hold> {
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
+        let mut hold = Self::lock_hold(&tx, reference).await?;
+        finish(&mut hold)?;
+        Self::unreserve(&tx, &hold).await?;
+        Self::save_hold_status(&tx, &hold).await?;
+        tx.commit().await?;
+        Ok(hold)
+    }
+}
+
+#[async_trait]
+#[automock]
+impl HoldProvider for HoldRepository {
+    async fn place_hold(&self, hold: &Hold) -> Result<Hold> {
+        tracing::info!("place hold {} of {} for user_id {:?}", hold.reference, hold.amount, hold.user_id);
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
+        let currency = hold.amount.currency();
+
+        let pocket = tx
+            .query_opt(
+                "SELECT balance, held, status FROM WALLET_DIGITAL.DATA_WALLET
+             WHERE user_id = $1 AND currency = $2 FOR UPDATE",
+                &[&hold.user_id, &currency],
+            )
+            .await?
+            .ok_or(WalletError::CurrencyNotHeld(currency))?;
+        let status: WalletStatus = pocket.get("status");
+        status.ensure_debit_allowed()?;
+        let balance: Money = pocket.get("balance");
+        let held: Money = pocket.get("held");
+        let available = balance.with_currency(currency).checked_sub(held.with_currency(currency))?;
+        if available.checked_sub(hold.amount)?.is_negative() {
+            return Err(WalletError::InsufficientBalance(hold.amount, available).into());
+        }
+
+        tx.execute(
+            "UPDATE WALLET_DIGITAL.DATA_WALLET SET held = held + $1, updated_date = $2
+             WHERE user_id = $3 AND currency = $4",
+            &[&hold.amount, &hold.audit.created_date, &hold.user_id, &currency],
+        )
+        .await?;
+        let row = tx
+            .query_one(
+                &format!(
+                    "INSERT INTO WALLET_DIGITAL.DATA_HOLD (reference, user_id, currency, amount, status, expires_at, created_date)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             RETURNING {}",
+                    HOLD_COLUMNS
+                ),
+                &[
+                    &hold.reference,
+                    &hold.user_id,
+                    &currency,
+                    &hold.amount,
+                    &hold.status,
+                    &hold.expires_at,
+                    &hold.audit.created_date,
+                ],
+            )
+            .await?;
+        tx.commit().await?;
+        Ok(Self::hold_from_row(&row))
+    }
+
+    async fn get_hold(&self, reference: &str) -> Result<Option<Hold>> {
+        tracing::info!("get hold {:?}", reference);
+        let client = self.pool.get().await?;
+        let row = client
+            .query_opt(
+                &format!("SELECT {} FROM WALLET_DIGITAL.DATA_HOLD WHERE reference = $1", HOLD_COLUMNS),
+                &[&reference],
+            )
+            .await?;
+        Ok(row.as_ref().map(Self::hold_from_row))
+    }
+
+    async fn capture_hold(&self, reference: &str, amount: Money) -> Result<Hold> {
+        tracing::info!("capture {} on hold {:?}", amount, reference);
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
+        let mut hold = Self::lock_hold(&tx, reference).await?;
+        hold.capture(amount, Utc::now())?;
+        // a frozen wallet keeps its holds, they can only be released
+        WalletRepository::lock_pocket_status(&tx, hold.user_id, amount.currency())
+            .await?
+            .ensure_debit_allowed()?;
+        let now = Utc::now();
+        self.limits.reserve_spend(&tx, hold.user_id, amount, now).await?;
+        Self::unreserve(&tx, &hold).await?;
+
+        let currency = amount.currency();
+        let row = tx
+            .query_one(
+                "UPDATE WALLET_DIGITAL.DATA_WALLET SET balance = balance - $1, updated_date = $2
+             WHERE user_id = $3 AND currency = $4
+             RETURNING balance",
+                &[&amount, &now, &hold.user_id, &currency],
+            )
+            .await?;
+        let new_balance: Money = row.get("balance");
+
+        let negated = Money::zero(currency).checked_sub(amount)?;
+        let entry = JournalEntry::against(
+            &format!("hold-capture-{}", hold.reference),
+            "hold capture",
+            hold.user_id,
+            negated,
+            LedgerAccount::External,
+        )?;
+        self.ledger.record_entry(&tx, &entry).await?;
+        self.ledger
+            .verify_balance(&tx, hold.user_id, new_balance.with_currency(currency))
+            .await?;
+
+        Self::save_hold_status(&tx, &hold).await?;
+        tx.commit().await?;
+        Ok(hold)
+    }
+
+    async fn release_hold(&self, reference: &str) -> Result<Hold> {
+        tracing::info!("release hold {:?}", reference);
+        self.finish_hold(reference, |hold| hold.release()).await
+    }
+
+    async fn expire_holds(&self, now: DateTime<Utc>) -> Result<Vec<Hold>> {
+        let client = self.pool.get().await?;
+        let rows = client
+            .query(
+                "SELECT reference FROM WALLET_DIGITAL.DATA_HOLD
+             WHERE status = 'Active' AND expires_at <= $1 ORDER BY expires_at",
+                &[&now],
+            )
+            .await?;
+
+        let mut expired = Vec::with_capacity(rows.len());
+        for row in rows {
+            let reference: String = row.get("reference");
+            match self.finish_hold(&reference, |hold| hold.expire(now)).await {
+                Ok(hold) => expired.push(hold),
+                // captured or released concurrently, nothing left to expire
+                Err(e) => tracing::warn!("skip expiring hold {:?}: {}", reference, e),
+            }
+        }
+        if !expired.is_empty() {
+            tracing::info!("expired {} holds", expired.len());
+        }
+        Ok(expired)
+    }
+}
+
@@ -0,0 +1,141 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::GenericClient;
+use mockall::automock;
+
+#[derive(Debug, Clone)]
+pub struct IdempotencyRepository {
+    pool: deadpool_postgres::Pool,
+}
+
+/// Stored outcome of a request made with an `Idempotency-Key`.
+/// `response` stays empty while the first request is still being processed.
+#[derive(Debug, Clone)]
+pub struct IdempotencyRecord {
+    pub key: String,
+    pub request_hash: String,
+    pub status_code: Option<u16>,
+    pub response: Option<serde_json::Value>,
+    pub created_date: DateTime<Utc>,
+    /// when the request currently working on the key started
+    pub claimed_at: DateTime<Utc>,
+}
+
+#[async_trait]
+pub trait IdempotencyProvider {
+    /// Claims the caller's key for this request. Returns `None` when the key
+    /// is now claimed: it is new or it expired (first used before
+    /// `expired_before`). Otherwise returns the record left by the earlier
+    /// request, which may still be running.
+    async fn claim_key(
+        &self,
+        caller: &str,
+        key: &str,
+        request_hash: &str,
+        now: DateTime<Utc>,
+        expired_before: DateTime<Utc>,
+    ) -> Result<Option<IdempotencyRecord>>;
+    async fn save_response(&self, caller: &str, key: &str, status_code: u16, response: &serde_json::Value) -> Result<()>;
+    /// Forgets the key so the client can retry, used when the request failed unexpectedly.
+    async fn release_key(&self, caller: &str, key: &str) -> Result<()>;
+    /// Deletes keys first used before `expired_before`, returns how many.
+    async fn purge_expired(&self, expired_before: DateTime<Utc>) -> Result<u64>;
+}
+
+impl IdempotencyRepository {
+    pub fn new(pool: deadpool_postgres::Pool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+#[automock]
+impl IdempotencyProvider for IdempotencyRepository {
+    async fn claim_key(
+        &self,
+        caller: &str,
+        key: &str,
+        request_hash: &str,
+        now: DateTime<Utc>,
+        expired_before: DateTime<Utc>,
+    ) -> Result<Option<IdempotencyRecord>> {
+        tracing::info!("claim idempotency key {:?} of {:?}", key, caller);
+        let client = self.pool.get().await?;
+        // xmax is 0 for a freshly inserted row, set when an expired one was reused.
+        // A claim without an outcome is never taken over: its transfer may have
+        // committed after all, e.g. when the process died before saving the response.
+        let claimed = client
+            .query_opt(
+                "INSERT INTO WALLET_DIGITAL.IDEMPOTENCY_KEY (caller, idempotency_key, request_hash, created_date, claimed_at)
+             VALUES ($1, $2, $3, $4, $4)
+             ON CONFLICT (caller, idempotency_key) DO UPDATE SET
+                request_hash = EXCLUDED.request_hash, status_code = NULL, response = NULL,
+                created_date = EXCLUDED.created_date, claimed_at = EXCLUDED.claimed_at
+             WHERE IDEMPOTENCY_KEY.created_date < $5
+             RETURNING xmax = 0 AS inserted",
+                &[&caller, &key, &request_hash, &now, &expired_before],
+            )
+            .await?;
+        if let Some(row) = claimed {
+            if !row.get::<_, bool>("inserted") {
+                tracing::info!("idempotency key {:?} of {:?} expired, claimed again", key, caller);
+            }
+            return Ok(None);
+        }
+
+        let row = client
+            .query_one(
+                "SELECT idempotency_key, request_hash, status_code, response, created_date, claimed_at
+             FROM WALLET_DIGITAL.IDEMPOTENCY_KEY WHERE caller = $1 AND idempotency_key = $2",
+                &[&caller, &key],
+            )
+            .await?;
+        let status_code: Option<i16> = row.get("status_code");
+        Ok(Some(IdempotencyRecord {
+            key: row.get("idempotency_key"),
+            request_hash: row.get("request_hash"),
+            status_code: status_code.map(|s| s as u16),
+            response: row.get("response"),
+            created_date: row.get("created_date"),
+            claimed_at: row.get("claimed_at"),
+        }))
+    }
+
+    async fn save_response(&self, caller: &str, key: &str, status_code: u16, response: &serde_json::Value) -> Result<()> {
+        tracing::info!("save response {} for idempotency key {:?}", status_code, key);
+        let client = self.pool.get().await?;
+        let status_code = status_code as i16;
+        client
+            .execute(
+                "UPDATE WALLET_DIGITAL.IDEMPOTENCY_KEY SET status_code = $1, response = $2
+             WHERE caller = $3 AND idempotency_key = $4",
+                &[&status_code, response, &caller, &key],
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn release_key(&self, caller: &str, key: &str) -> Result<()> {
+        tracing::info!("release idempotency key {:?}", key);
+        let client = self.pool.get().await?;
+        client
+            .execute(
+                "DELETE FROM WALLET_DIGITAL.IDEMPOTENCY_KEY WHERE caller = $1 AND idempotency_key = $2",
+                &[&caller, &key],
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn purge_expired(&self, expired_before: DateTime<Utc>) -> Result<u64> {
+        let client = self.pool.get().await?;
+        let purged = client
+            .execute(
+                "DELETE FROM WALLET_DIGITAL.IDEMPOTENCY_KEY WHERE created_date < $1",
+                &[&expired_before],
+            )
+            .await?;
+        Ok(purged)
+    }
+}
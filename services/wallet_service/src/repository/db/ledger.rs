@@ -0,0 +1,140 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use deadpool_postgres::{GenericClient, Transaction};
+use domain::ledger::ledger::{JournalEntry, LedgerAccount, Posting};
+use domain::money::money::{Currency, Money};
+use mockall::automock;
+
+#[derive(Debug, Clone)]
+pub struct LedgerRepository {
+    pool: deadpool_postgres::Pool,
+}
+
+#[async_trait]
+pub trait LedgerProvider {
+    /// Writes the entry inside the caller's transaction so postings commit
+    /// (or roll back) together with the balance update they explain.
+    async fn record_entry(&self, tx: &Transaction<'_>, entry: &JournalEntry) -> Result<i64>;
+    /// Whether an entry with this reference was already booked.
+    async fn entry_exists(&self, tx: &Transaction<'_>, reference: &str) -> Result<bool>;
+    /// Balance implied by the postings of `account`, read inside the caller's transaction.
+    async fn ledger_balance(&self, tx: &Transaction<'_>, account: &LedgerAccount, currency: Currency) -> Result<Money>;
+    /// Every journal entry touching `account`, oldest first.
+    async fn get_entries_by_account(&self, account: &LedgerAccount) -> Result<Vec<JournalEntry>>;
+}
+
+impl LedgerRepository {
+    pub fn new(pool: deadpool_postgres::Pool) -> Self {
+        Self { pool }
+    }
+
+    /// Fails when the stored balance of a wallet pocket disagrees with its ledger.
+    pub async fn verify_balance(&self, tx: &Transaction<'_>, user_id: i32, balance: Money) -> Result<()> {
+        let account = LedgerAccount::Wallet(user_id);
+        let ledger_balance = self.ledger_balance(tx, &account, balance.currency()).await?;
+        if ledger_balance != balance {
+            tracing::error!(
+                "ledger mismatch for {}: balance {} but ledger {}",
+                account,
+                balance,
+                ledger_balance
+            );
+            return Err(anyhow!("Ledger mismatch for {}", account));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+#[automock]
+impl LedgerProvider for LedgerRepository {
+    async fn record_entry(&self, tx: &Transaction<'_>, entry: &JournalEntry) -> Result<i64> {
+        entry.validate()?;
+        tracing::info!(
+            "record journal entry {} with {} postings",
+            entry.reference,
+            entry.postings.len()
+        );
+
+        let journal = tx
+            .query_one(
+                "INSERT INTO WALLET_DIGITAL.LEDGER_JOURNAL (reference, description, created_date)
+             VALUES ($1, $2, $3)
+             RETURNING id",
+                &[&entry.reference, &entry.description, &entry.created_date],
+            )
+            .await?;
+        let journal_id: i64 = journal.get("id");
+
+        for posting in &entry.postings {
+            let account = posting.account.to_string();
+            let currency = posting.amount.currency();
+            tx.execute(
+                "INSERT INTO WALLET_DIGITAL.LEDGER_POSTING (journal_id, account, currency, amount, created_date)
+             VALUES ($1, $2, $3, $4, $5)",
+                &[&journal_id, &account, &currency, &posting.amount, &entry.created_date],
+            )
+            .await?;
+        }
+        Ok(journal_id)
+    }
+
+    async fn entry_exists(&self, tx: &Transaction<'_>, reference: &str) -> Result<bool> {
+        let row = tx
+            .query_opt(
+                "SELECT id FROM WALLET_DIGITAL.LEDGER_JOURNAL WHERE reference = $1",
+                &[&reference],
+            )
+            .await?;
+        Ok(row.is_some())
+    }
+
+    async fn ledger_balance(&self, tx: &Transaction<'_>, account: &LedgerAccount, currency: Currency) -> Result<Money> {
+        let account = account.to_string();
+        let row = tx
+            .query_one(
+                "SELECT COALESCE(SUM(amount), 0)::NUMERIC(20,2) AS balance FROM WALLET_DIGITAL.LEDGER_POSTING
+             WHERE account = $1 AND currency = $2",
+                &[&account, &currency],
+            )
+            .await?;
+        let balance: Money = row.get("balance");
+        Ok(balance.with_currency(currency))
+    }
+
+    async fn get_entries_by_account(&self, account: &LedgerAccount) -> Result<Vec<JournalEntry>> {
+        tracing::info!("get journal entries for {}", account);
+        let client = self.pool.get().await?;
+        let account = account.to_string();
+        let rows = client
+            .query(
+                "SELECT j.id, j.reference, j.description, j.created_date, p.account, p.currency, p.amount
+             FROM WALLET_DIGITAL.LEDGER_JOURNAL j
+             JOIN WALLET_DIGITAL.LEDGER_POSTING p ON p.journal_id = j.id
+             WHERE j.id IN (SELECT journal_id FROM WALLET_DIGITAL.LEDGER_POSTING WHERE account = $1)
+             ORDER BY j.id, p.id",
+                &[&account],
+            )
+            .await?;
+
+        let mut entries: Vec<JournalEntry> = Vec::new();
+        for row in rows {
+            let journal_id: i64 = row.get("id");
+            let currency: Currency = row.get("currency");
+            let amount: Money = row.get("amount");
+            let account: String = row.get("account");
+            let posting = Posting::new(account.parse()?, amount.with_currency(currency));
+            match entries.last_mut() {
+                Some(entry) if entry.id == Some(journal_id) => entry.postings.push(posting),
+                _ => entries.push(JournalEntry {
+                    id: Some(journal_id),
+                    reference: row.get("reference"),
+                    description: row.get("description"),
+                    postings: vec![posting],
+                    created_date: row.get("created_date"),
+                }),
+            }
+        }
+        Ok(entries)
+    }
+}
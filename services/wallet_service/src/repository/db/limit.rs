@@ -0,0 +1,164 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{GenericClient, Transaction};
+use domain::money::money::{Currency, Money};
+use domain::user::user::UserTier;
+use domain::wallet::limit::{SpendingCounters, SpendingLimits};
+use mockall::automock;
+use std::sync::Arc;
+use crate::repository::limit::tier_limits::TierLimitProvider;
+
+#[derive(Debug, Clone)]
+pub struct LimitRepository {
+    pool: deadpool_postgres::Pool,
+    tier_limits: Arc<dyn TierLimitProvider>,
+}
+
+#[async_trait]
+pub trait LimitProvider {
+    /// Checks one more outgoing payment of `amount` against the pocket's
+    /// limits and adds it to the counters, inside the caller's transaction so
+    /// it only counts when the payment commits. The counters stay locked until
+    /// then, concurrent payments from the pocket are checked one after another.
+    async fn reserve_spend(&self, tx: &Transaction<'_>, user_id: i32, amount: Money, now: DateTime<Utc>) -> Result<()>;
+    async fn set_tier(&self, user_id: i32, tier: UserTier) -> Result<()>;
+    async fn set_overrides(&self, user_id: i32, overrides: &SpendingLimits) -> Result<()>;
+}
+
+impl LimitRepository {
+    pub fn new(pool: deadpool_postgres::Pool, tier_limits: Arc<dyn TierLimitProvider>) -> Self {
+        Self { pool, tier_limits }
+    }
+
+    /// Limits of the pocket: its tier's defaults with any per-wallet overrides applied.
+    async fn limits(&self, client: &impl GenericClient, user_id: i32, currency: Currency) -> Result<SpendingLimits> {
+        let row = client
+            .query_opt(
+                "SELECT tier FROM WALLET_DIGITAL.USER_TIER WHERE user_id = $1",
+                &[&user_id],
+            )
+            .await?;
+        // `Basic` until a tier is assigned
+        let tier: UserTier = row.map(|r| r.get("tier")).unwrap_or_default();
+        let limits = self.tier_limits.tier_limits(tier, currency);
+
+        let row = client
+            .query_opt(
+                "SELECT max_single_transfer, daily_outgoing, monthly_outgoing, max_transfers_per_hour
+             FROM WALLET_DIGITAL.SPENDING_LIMIT WHERE user_id = $1 AND currency = $2",
+                &[&user_id, &currency],
+            )
+            .await?;
+        let Some(r) = row else {
+            return Ok(limits);
+        };
+        let amount = |column: &str| r.get::<_, Option<Money>>(column).map(|m| m.with_currency(currency));
+        let per_hour: Option<i32> = r.get("max_transfers_per_hour");
+        let overrides = SpendingLimits {
+            currency,
+            max_single_transfer: amount("max_single_transfer"),
+            daily_outgoing: amount("daily_outgoing"),
+            monthly_outgoing: amount("monthly_outgoing"),
+            max_transfers_per_hour: per_hour.map(|n| n.max(0) as u32),
+        };
+        Ok(limits.overridden_by(&overrides))
+    }
+
+    /// What the pocket sent in the calendar hour, day and month containing
+    /// `now`; the counter rows are created if missing and locked.
+    async fn lock_counters(tx: &Transaction<'_>, user_id: i32, currency: Currency, now: DateTime<Utc>) -> Result<SpendingCounters> {
+        tx.execute(
+            "INSERT INTO WALLET_DIGITAL.SPENDING_COUNTER (user_id, currency, period, period_start, total, count)
+             SELECT $1, $2, period, date_trunc(period, $3::timestamptz), 0, 0
+             FROM unnest(ARRAY['hour', 'day', 'month']) AS period
+             ON CONFLICT (user_id, currency, period, period_start) DO NOTHING",
+            &[&user_id, &currency, &now],
+        )
+        .await?;
+        let rows = tx
+            .query(
+                "SELECT period, total, count FROM WALLET_DIGITAL.SPENDING_COUNTER
+             WHERE user_id = $1 AND currency = $2 AND period_start = date_trunc(period, $3::timestamptz)
+             FOR UPDATE",
+                &[&user_id, &currency, &now],
+            )
+            .await?;
+
+        let mut counters = SpendingCounters::zero(currency);
+        for row in rows {
+            let period: String = row.get("period");
+            let total: Money = row.get("total");
+            let count: i32 = row.get("count");
+            match period.as_str() {
+                "hour" => counters.hourly_count = count.max(0) as u32,
+                "day" => counters.daily_outgoing = total.with_currency(currency),
+                "month" => counters.monthly_outgoing = total.with_currency(currency),
+                _ => {}
+            }
+        }
+        Ok(counters)
+    }
+}
+
+#[async_trait]
+#[automock]
+impl LimitProvider for LimitRepository {
+    async fn reserve_spend(&self, tx: &Transaction<'_>, user_id: i32, amount: Money, now: DateTime<Utc>) -> Result<()> {
+        let currency = amount.currency();
+        let counters = Self::lock_counters(tx, user_id, currency, now).await?;
+        let limits = self.limits(tx, user_id, currency).await?;
+        if let Err(e) = limits.check(amount, &counters) {
+            tracing::warn!("payment of {} by user_id {} rejected: {}", amount, user_id, e);
+            return Err(e.into());
+        }
+        tx.execute(
+            "UPDATE WALLET_DIGITAL.SPENDING_COUNTER SET total = total + $3, count = count + 1
+             WHERE user_id = $1 AND currency = $2 AND period_start = date_trunc(period, $4::timestamptz)",
+            &[&user_id, &currency, &amount, &now],
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn set_tier(&self, user_id: i32, tier: UserTier) -> Result<()> {
+        tracing::info!("set tier of user_id {} to {:?}", user_id, tier);
+        let client = self.pool.get().await?;
+        client
+            .execute(
+                "INSERT INTO WALLET_DIGITAL.USER_TIER (user_id, tier, updated_date) VALUES ($1, $2, now())
+             ON CONFLICT (user_id) DO UPDATE SET tier = EXCLUDED.tier, updated_date = EXCLUDED.updated_date",
+                &[&user_id, &tier],
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn set_overrides(&self, user_id: i32, overrides: &SpendingLimits) -> Result<()> {
+        tracing::info!("set {} limits of user_id {}", overrides.currency, user_id);
+        let client = self.pool.get().await?;
+        let per_hour = overrides.max_transfers_per_hour.map(|n| n.min(i32::MAX as u32) as i32);
+        client
+            .execute(
+                "INSERT INTO WALLET_DIGITAL.SPENDING_LIMIT
+                (user_id, currency, max_single_transfer, daily_outgoing, monthly_outgoing, max_transfers_per_hour, updated_date)
+             VALUES ($1, $2, $3, $4, $5, $6, now())
+             ON CONFLICT (user_id, currency) DO UPDATE SET
+                max_single_transfer = EXCLUDED.max_single_transfer,
+                daily_outgoing = EXCLUDED.daily_outgoing,
+                monthly_outgoing = EXCLUDED.monthly_outgoing,
+                max_transfers_per_hour = EXCLUDED.max_transfers_per_hour,
+                updated_date = EXCLUDED.updated_date",
+                &[
+                    &user_id,
+                    &overrides.currency,
+                    &overrides.max_single_transfer,
+                    &overrides.daily_outgoing,
+                    &overrides.monthly_outgoing,
+                    &per_hour,
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+}
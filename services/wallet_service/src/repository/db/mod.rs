@@ -1 +1,5 @@
-pub mod postgres;
\ No newline at end of file
+pub mod postgres;
+pub mod ledger;
+pub mod idempotency;
+pub mod hold;
+pub mod limit;
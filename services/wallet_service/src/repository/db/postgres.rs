@@ -1,34 +1,228 @@
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use chrono::Utc;
-use deadpool_postgres::GenericClient;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{GenericClient, Transaction};
+use crate::repository::db::ledger::{LedgerProvider, LedgerRepository};
+use crate::repository::db::limit::{LimitProvider, LimitRepository};
 use domain::base::base::AuditMetadata;
-use domain::wallet::wallet::{Wallet, WalletStatus};
+use domain::ledger::ledger::{JournalEntry, LedgerAccount, Posting};
+use domain::money::money::{Currency, Money};
+use domain::wallet::error::WalletError;
+use domain::wallet::closure::{ClosureReceipt, SweepDestination};
+use domain::wallet::norek::NorekFormat;
+use domain::wallet::wallet::{StatusReason, Wallet, WalletStatus, WalletStatusTransition};
 use mockall::automock;
+use tokio_postgres::error::SqlState;
+use tokio_postgres::Row;
 
 #[derive(Debug, Clone)]
 pub struct WalletRepository {
     pool: deadpool_postgres::Pool,
+    ledger: LedgerRepository,
+    limits: LimitRepository,
+    norek: NorekFormat,
 }
 
 #[async_trait]
 pub trait WalletProvider {
     async fn get_wallet_by_userid(&self, user_id: i32) -> Result<Option<Wallet>>;
-    async fn create_wallet(&self, user_id: i32, balance: f64) -> Result<Wallet>;
-    async fn update_balance(&self, user_id: i32, upcoming_balance: f64) -> Result<()>;
-    async fn transfer_balance(&self, from_id: i32, to_id: i32, amount: f64) -> Result<(f64, f64)>;
-    async fn delete_wallet(&self, id: i32) -> Result<()>;
+    /// `norek` must already be normalized, see [`NorekFormat::validate`]
+    async fn get_wallet_by_norek(&self, norek: &str) -> Result<Option<Wallet>>;
+    async fn create_wallet(&self, user_id: i32, balance: Money) -> Result<Wallet>;
+    async fn open_pocket(&self, user_id: i32, currency: Currency) -> Result<Wallet>;
+    /// Fails with `AlreadyApplied` when `reference` was booked before.
+    async fn transfer_balance(&self, from_id: i32, to_id: i32, debit: Money, credit: Money, reference: &str) -> Result<(Money, Money)>;
+    /// Applies a signed change booked against the transit account, used for
+    /// the debit and credit legs of transfers orchestrated elsewhere.
+    /// Returns `false` without touching the balance when `reference` was already applied.
+    /// Debits count against the spending limits. A `compensation` credit
+    /// (refund) is accepted in every status but the terminal ones; a
+    /// `compensation` debit (reversal) is not spending and skips the limits.
+    async fn move_balance(&self, user_id: i32, amount: Money, reference: &str, description: &str, compensation: bool) -> Result<bool>;
+    /// Whether `reference` was booked; waits for a transaction still moving
+    /// the pocket, e.g. the first call of a retried leg.
+    async fn is_applied(&self, user_id: i32, currency: Currency, reference: &str) -> Result<bool>;
+    /// Sweeps every pocket to `destination` and closes the wallet, recording
+    /// the receipt, in one transaction. The wallet must be PendingClosure.
+    /// A sweep to another wallet counts against the spending limits and opens
+    /// the pockets the recipient is missing.
+    async fn close_wallet(
+        &self,
+        user_id: i32,
+        destination: SweepDestination,
+        reason: StatusReason,
+        note: Option<String>,
+        actor: &str,
+    ) -> Result<ClosureReceipt>;
+    async fn get_closure(&self, user_id: i32) -> Result<Option<ClosureReceipt>>;
+    /// Applies the transition to every pocket and appends it to the status history;
+    /// fails with `StatusConflict` when the wallet is no longer in `transition.from`.
+    async fn set_status(&self, transition: &WalletStatusTransition) -> Result<()>;
+    async fn get_status_history(&self, user_id: i32) -> Result<Vec<WalletStatusTransition>>;
+    /// Gives wallets created before account numbers were generated their own;
+    /// returns how many were numbered.
+    async fn assign_missing_noreks(&self) -> Result<usize>;
 }
 
-#[async_trait]
-pub trait DbProvider: WalletProvider + Send + Sync {}
+impl WalletRepository {
+    pub fn new(pool: deadpool_postgres::Pool, limits: LimitRepository, norek: NorekFormat) -> Self {
+        Self {
+            ledger: LedgerRepository::new(pool.clone()),
+            limits,
+            pool,
+            norek,
+        }
+    }
 
-#[async_trait]
-impl<T: WalletProvider + Send + Sync> DbProvider for T {}
+    pub fn norek_format(&self) -> &NorekFormat {
+        &self.norek
+    }
 
-impl WalletRepository {
-    pub fn new(pool: deadpool_postgres::Pool) -> Self {
-        Self { pool }
+    /// next account number, drawn from the database sequence so concurrent
+    /// wallet creations never get the same one
+    async fn next_norek(&self, client: &impl GenericClient) -> Result<String> {
+        let row = client
+            .query_one("SELECT nextval('WALLET_DIGITAL.NOREK_SEQ') AS serial", &[])
+            .await?;
+        let serial: i64 = row.get("serial");
+        Ok(self.norek.generate(serial as u64)?)
+    }
+
+    /// DATA_WALLET keeps one row per (user_id, currency); fold them back into one wallet
+    fn wallet_from_rows(rows: &[Row]) -> Option<Wallet> {
+        let first = rows.first()?;
+        let mut wallet = Wallet {
+            id: Some(first.get("id")),
+            norek: first.get("norek"),
+            user_id: first.get("user_id"),
+            balances: Default::default(),
+            held: Default::default(),
+            status: first.get("status"),
+            audit: AuditMetadata {
+                created_date: first.get("created_date"),
+                updated_date: first.get("updated_date"),
+            },
+        };
+        for row in rows {
+            let currency: Currency = row.get("currency");
+            let balance: Money = row.get("balance");
+            let held: Money = row.get("held");
+            wallet.balances.insert(currency, balance.with_currency(currency));
+            if !held.is_zero() {
+                wallet.held.insert(currency, held.with_currency(currency));
+            }
+        }
+        Some(wallet)
+    }
+
+    /// Adds an empty `currency` pocket to the user's wallet unless it has one.
+    async fn insert_pocket(client: &impl GenericClient, user_id: i32, currency: Currency, now: DateTime<Utc>) -> Result<()> {
+        let zero = Money::zero(currency);
+        client.execute(
+            "INSERT INTO WALLET_DIGITAL.DATA_WALLET (user_id, currency, balance, norek, status, created_date, updated_date)
+             SELECT user_id, $2, $3, norek, status, $4, $4 FROM WALLET_DIGITAL.DATA_WALLET WHERE user_id = $1 LIMIT 1
+             ON CONFLICT (user_id, currency) DO NOTHING",
+             &[&user_id, &currency, &zero, &now]
+        ).await?;
+        Ok(())
+    }
+
+    /// Locks the pocket row and returns the wallet's status; a status change
+    /// updates every pocket, so it waits for the caller's transaction.
+    pub(crate) async fn lock_pocket_status(tx: &Transaction<'_>, user_id: i32, currency: Currency) -> Result<WalletStatus> {
+        let row = tx
+            .query_opt(
+                "SELECT status FROM WALLET_DIGITAL.DATA_WALLET
+             WHERE user_id = $1 AND currency = $2 FOR UPDATE",
+                &[&user_id, &currency],
+            )
+            .await?
+            .ok_or(WalletError::CurrencyNotHeld(currency))?;
+        Ok(row.get("status"))
+    }
+
+    /// Locks every pocket of the users in (user_id, currency) order, the order
+    /// transfer_balance takes them in, so the two cannot deadlock.
+    async fn lock_pockets(tx: &Transaction<'_>, user_ids: &[i32]) -> Result<()> {
+        let rows = tx
+            .query(
+                "SELECT user_id, currency FROM WALLET_DIGITAL.DATA_WALLET WHERE user_id = ANY($1)",
+                &[&user_ids],
+            )
+            .await?;
+        let mut pockets: Vec<(i32, Currency)> = rows
+            .iter()
+            .map(|row| (row.get("user_id"), row.get("currency")))
+            .collect();
+        pockets.sort();
+        for (user_id, currency) in pockets {
+            Self::lock_pocket_status(tx, user_id, currency).await?;
+        }
+        Ok(())
+    }
+
+    async fn insert_status_history(tx: &Transaction<'_>, transition: &WalletStatusTransition) -> Result<()> {
+        tx.execute(
+            "INSERT INTO WALLET_DIGITAL.WALLET_STATUS_HISTORY (user_id, from_status, to_status, reason, note, actor, created_date)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+            &[
+                &transition.user_id,
+                &transition.from,
+                &transition.to,
+                &transition.reason,
+                &transition.note,
+                &transition.actor,
+                &transition.at,
+            ],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Moves one swept pocket balance out of the closing wallet.
+    async fn sweep_pocket(&self, tx: &Transaction<'_>, receipt: &ClosureReceipt, amount: Money) -> Result<()> {
+        let currency = amount.currency();
+        let now = receipt.closed_at;
+        let reference = format!("{}-{}", receipt.reference, currency);
+        let negated = Money::zero(currency).checked_sub(amount)?;
+        tx.execute(
+            "UPDATE WALLET_DIGITAL.DATA_WALLET SET balance = 0, updated_date = $1
+             WHERE user_id = $2 AND currency = $3",
+            &[&now, &receipt.user_id, &currency],
+        )
+        .await?;
+
+        let postings = vec![
+            Posting::new(LedgerAccount::Wallet(receipt.user_id), negated),
+            Posting::new(receipt.destination.account(), amount),
+        ];
+        let entry = JournalEntry::new(&reference, "closure sweep", postings)?;
+        self.ledger.record_entry(tx, &entry).await?;
+        self.ledger
+            .verify_balance(tx, receipt.user_id, Money::zero(currency))
+            .await?;
+
+        if let SweepDestination::Wallet(to_id) = receipt.destination {
+            // paying another user out is spending, and they may not hold the currency yet
+            self.limits.reserve_spend(tx, receipt.user_id, amount, now).await?;
+            Self::insert_pocket(tx, to_id, currency, now).await?;
+            Self::lock_pocket_status(tx, to_id, currency)
+                .await?
+                .ensure_credit_allowed()?;
+            let row = tx
+                .query_one(
+                    "UPDATE WALLET_DIGITAL.DATA_WALLET SET balance = balance + $1, updated_date = $2
+                 WHERE user_id = $3 AND currency = $4
+                 RETURNING balance",
+                    &[&amount, &now, &to_id, &currency],
+                )
+                .await?;
+            let new_balance: Money = row.get("balance");
+            self.ledger
+                .verify_balance(tx, to_id, new_balance.with_currency(currency))
+                .await?;
+        }
+        Ok(())
     }
 }
 
@@ -38,123 +232,390 @@ impl WalletProvider for WalletRepository {
     async fn get_wallet_by_userid(&self, user_id: i32) -> Result<Option<Wallet>> {
         tracing::info!("get wallet by user id {:?}", user_id);
         let client = self.pool.get().await?;
-        let result_opt = client.query_opt(
-            "SELECT id, norek, user_id, balance, status, created_date, updated_date from WALLET_DIGITAL.DATA_WALLET WHERE user_id = $1", &[&user_id])
+        let rows = client.query(
+            "SELECT id, norek, user_id, currency, balance, held, status, created_date, updated_date from WALLET_DIGITAL.DATA_WALLET WHERE user_id = $1 ORDER BY id", &[&user_id])
             .await?;
-        Ok(result_opt.map(|row| Wallet {
-            id: Some(row.get("id")),
-            norek: row.get("norek"),
-            user_id,
-            balance: row.get("ballance"),
-            status: row.get("status"),
-            audit: AuditMetadata {
-                created_date: row.get("created_date"),
-                updated_date: Some(row.get("updated_date")),
-            },
-        }))
+        Ok(Self::wallet_from_rows(&rows))
     }
 
-    async fn create_wallet(&self, user_id: i32, balance: f64) -> Result<Wallet> {
+    async fn get_wallet_by_norek(&self, norek: &str) -> Result<Option<Wallet>> {
+        tracing::info!("get wallet by norek {:?}", norek);
+        let client = self.pool.get().await?;
+        let rows = client.query(
+            "SELECT id, norek, user_id, currency, balance, held, status, created_date, updated_date from WALLET_DIGITAL.DATA_WALLET
+             WHERE user_id = (SELECT user_id FROM WALLET_DIGITAL.DATA_WALLET WHERE norek = $1 LIMIT 1) ORDER BY id", &[&norek])
+            .await?;
+        Ok(Self::wallet_from_rows(&rows))
+    }
+
+    async fn create_wallet(&self, user_id: i32, balance: Money) -> Result<Wallet> {
         tracing::info!("create wallet for user id : {:?}", user_id);
 
-        let client = self.pool.get().await?;
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
         let now = Utc::now();
         let status = WalletStatus::Active;
-        let norek = "";
+        let norek = self.next_norek(&tx).await?;
+        let currency = balance.currency();
 
-        let result = client.query_one(
-            "INSERT INTO WALLET_DIGITAL.DATA_WALLET (user_id, balance, norek, status, created_date, updated_date)
-             VALUES ($1, $2, $3, $4, $5, $6)
-             RETURNING id, user_id, balance, status, created_date, updated_date",
-             &[&user_id, &balance, &norek, &status, &now, &now]
+        let result = tx.query_one(
+            "INSERT INTO WALLET_DIGITAL.DATA_WALLET (user_id, currency, balance, norek, status, created_date, updated_date)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             RETURNING id, norek, user_id, currency, balance, held, status, created_date, updated_date",
+             &[&user_id, &currency, &balance, &norek, &status, &now, &now]
         ).await?;
-        Ok(Wallet {
-            id: Some(result.get("id")),
-            norek: result.get("norek"),
-            user_id: result.get("user_id"),
-            balance: result.get("balance"),
-            status: result.get("status"),
-            audit: AuditMetadata {
-                created_date: result.get("created_date"),
-                updated_date: Some(result.get("updated_date")),
-            },
-        })
+
+        if !balance.is_zero() {
+            let wallet_id: i32 = result.get("id");
+            let entry = JournalEntry::adjustment(&format!("opening-{}", wallet_id), user_id, balance)?;
+            self.ledger.record_entry(&tx, &entry).await?;
+        }
+        tx.commit().await?;
+
+        Self::wallet_from_rows(&[result]).ok_or_else(|| anyhow!("Failed to create wallet"))
     }
 
-    async fn update_balance(&self, user_id: i32, upcoming_balance: f64) -> Result<()> {
-        tracing::info!(
-            "update balance for user_id : {:?} with balance : {:?}",
-            user_id,
-            upcoming_balance
-        );
+    async fn open_pocket(&self, user_id: i32, currency: Currency) -> Result<Wallet> {
+        tracing::info!("open {} pocket for user id : {:?}", currency, user_id);
+
         let client = self.pool.get().await?;
-        let now = Utc::now();
-        client
-            .execute(
-                "UPDATE WALLET_DIGITAL.DATA_WALLET
-            SET balance = balance + $1
-            updated_date = $2
-            WHERE user_id = $3",
-                &[&upcoming_balance, &now, &user_id],
-            )
-            .await?;
-        Ok(())
+        Self::insert_pocket(&client, user_id, currency, Utc::now()).await?;
+        self.get_wallet_by_userid(user_id)
+            .await?
+            .ok_or_else(|| WalletError::WalletNotFound(user_id).into())
     }
 
-    async fn transfer_balance(&self, from_id: i32, to_id: i32, amount: f64) -> Result<(f64, f64)> {
+    async fn transfer_balance(&self, from_id: i32, to_id: i32, debit: Money, credit: Money, reference: &str) -> Result<(Money, Money)> {
         tracing::info!(
-            "transfer balance from {:?} to {:?} with value {:?}",
+            "transfer balance from {:?} to {:?} debit {} credit {}",
             from_id,
             to_id,
-            amount
+            debit,
+            credit
         );
 
         let mut client = self.pool.get().await?;
         let tx = client.transaction().await?;
         let now = Utc::now();
+        let debit_currency = debit.currency();
+        let credit_currency = credit.currency();
+        // both pockets are locked in (user_id, currency) order so that
+        // transfers in opposite directions cannot deadlock
+        let sender = (from_id, debit_currency);
+        let receiver = (to_id, credit_currency);
+        let (first, second) = if sender <= receiver { (sender, receiver) } else { (receiver, sender) };
+        let first_status = Self::lock_pocket_status(&tx, first.0, first.1).await?;
+        let second_status = Self::lock_pocket_status(&tx, second.0, second.1).await?;
+        let (sender_status, receiver_status) =
+            if sender <= receiver { (first_status, second_status) } else { (second_status, first_status) };
+        sender_status.ensure_debit_allowed()?;
+        receiver_status.ensure_credit_allowed()?;
+        // checked under the sender's lock, a repeated transaction id waits for the first run
+        if self.ledger.entry_exists(&tx, reference).await? {
+            return Err(WalletError::AlreadyApplied(reference.to_string()).into());
+        }
+        self.limits.reserve_spend(&tx, from_id, debit, now).await?;
 
         let sender_result = tx
             .query_opt(
                 "UPDATE WALLET_DIGITAL.DATA_WALLET
             SET balance = balance - $1, updated_date = $2
-            WHERE user_id = $3 AND balance >= $3
+            WHERE user_id = $3 AND currency = $4 AND balance - held >= $1
             RETURNING balance",
-                &[&amount, &now, &from_id],
+                &[&debit, &now, &from_id, &debit_currency],
             )
             .await?;
 
-        let sender_result = sender_result.ok_or_else(|| anyhow!("Insufficient balance"))?;
-        let sender_new_balance: f64 = sender_result.get("balance");
+        let Some(sender_result) = sender_result else {
+            // the guard failed: tell a missing pocket apart from a short balance
+            let available = tx
+                .query_opt(
+                    "SELECT balance - held AS available FROM WALLET_DIGITAL.DATA_WALLET
+                WHERE user_id = $1 AND currency = $2",
+                    &[&from_id, &debit_currency],
+                )
+                .await?
+                .ok_or(WalletError::CurrencyNotHeld(debit_currency))?;
+            let available: Money = available.get("available");
+            return Err(WalletError::InsufficientBalance(debit, available.with_currency(debit_currency)).into());
+        };
+        let sender_new_balance: Money = sender_result.get("balance");
 
         let receiver_result = tx
-            .query_one(
+            .query_opt(
                 "UPDATE WALLET_DIGITAL.DATA_WALLET
             SET balance = balance + $1,
             updated_date = $2
-            WHERE user_id = $3
+            WHERE user_id = $3 AND currency = $4
             RETURNING balance",
-                &[&amount, &now, &to_id],
+                &[&credit, &now, &to_id, &credit_currency],
             )
             .await?;
 
-        let receiver_new_balance = receiver_result.get("balance");
+        let receiver_result =
+            receiver_result.ok_or(WalletError::CurrencyNotHeld(credit_currency))?;
+        let receiver_new_balance: Money = receiver_result.get("balance");
+        let sender_new_balance = sender_new_balance.with_currency(debit_currency);
+        let receiver_new_balance = receiver_new_balance.with_currency(credit_currency);
+
+        let entry = JournalEntry::transfer(reference, from_id, to_id, debit, credit)?;
+        self.ledger.record_entry(&tx, &entry).await.map_err(|e| {
+            match e.downcast_ref::<tokio_postgres::Error>().and_then(|e| e.code()) {
+                Some(&SqlState::UNIQUE_VIOLATION) => WalletError::AlreadyApplied(reference.to_string()).into(),
+                _ => e,
+            }
+        })?;
+        self.ledger.verify_balance(&tx, from_id, sender_new_balance).await?;
+        self.ledger.verify_balance(&tx, to_id, receiver_new_balance).await?;
 
         tx.commit().await?;
 
         Ok((sender_new_balance, receiver_new_balance))
     }
 
-    async fn delete_wallet(&self, id: i32) -> Result<()> {
-        tracing::info!("delete wallet for id : {:?}", id);
+    async fn move_balance(&self, user_id: i32, amount: Money, reference: &str, description: &str, compensation: bool) -> Result<bool> {
+        tracing::info!(
+            "move balance for user_id : {:?} amount : {} reference : {:?}",
+            user_id,
+            amount,
+            reference
+        );
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
+        let now = Utc::now();
+        let currency = amount.currency();
+        let current = tx
+            .query_opt(
+                "SELECT balance, held, status FROM WALLET_DIGITAL.DATA_WALLET
+            WHERE user_id = $1 AND currency = $2 FOR UPDATE",
+                &[&user_id, &currency],
+            )
+            .await?
+            .ok_or(WalletError::CurrencyNotHeld(currency))?;
+        // checked under the pocket lock, a retry of the same leg waits for the first one
+        if self.ledger.entry_exists(&tx, reference).await? {
+            tracing::info!("reference {:?} already applied, skipping", reference);
+            return Ok(false);
+        }
+        let status: WalletStatus = current.get("status");
+        match (amount.is_negative(), compensation) {
+            (true, _) => status.ensure_debit_allowed()?,
+            (false, true) => status.ensure_refund_allowed()?,
+            (false, false) => status.ensure_credit_allowed()?,
+        }
+        let held: Money = current.get("held");
+        let current: Money = current.get("balance");
+        let current = current.with_currency(currency);
+        let new_balance = current.checked_add(amount)?;
+        // debits may only use the available part of the balance
+        let available = current.checked_sub(held.with_currency(currency))?;
+        if available.checked_add(amount)?.is_negative() {
+            let requested = Money::zero(currency).checked_sub(amount)?;
+            return Err(WalletError::InsufficientBalance(requested, available).into());
+        }
+        if amount.is_negative() && !compensation {
+            let spent = Money::zero(currency).checked_sub(amount)?;
+            self.limits.reserve_spend(&tx, user_id, spent, now).await?;
+        }
+
+        tx.execute(
+            "UPDATE WALLET_DIGITAL.DATA_WALLET
+            SET balance = $1,
+            updated_date = $2
+            WHERE user_id = $3 AND currency = $4",
+            &[&new_balance, &now, &user_id, &currency],
+        )
+        .await?;
+
+        let entry = JournalEntry::against(reference, description, user_id, amount, LedgerAccount::Transit)?;
+        if let Err(e) = self.ledger.record_entry(&tx, &entry).await {
+            // the same reference was booked for another pocket in the meantime
+            if e.downcast_ref::<tokio_postgres::Error>().and_then(|e| e.code()) == Some(&SqlState::UNIQUE_VIOLATION) {
+                tracing::info!("reference {:?} already applied, skipping", reference);
+                return Ok(false);
+            }
+            return Err(e);
+        }
+        self.ledger.verify_balance(&tx, user_id, new_balance).await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    async fn is_applied(&self, user_id: i32, currency: Currency, reference: &str) -> Result<bool> {
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
+        tx.query_opt(
+            "SELECT id FROM WALLET_DIGITAL.DATA_WALLET WHERE user_id = $1 AND currency = $2 FOR UPDATE",
+            &[&user_id, &currency],
+        )
+        .await?;
+        self.ledger.entry_exists(&tx, reference).await
+    }
 
+    async fn close_wallet(
+        &self,
+        user_id: i32,
+        destination: SweepDestination,
+        reason: StatusReason,
+        note: Option<String>,
+        actor: &str,
+    ) -> Result<ClosureReceipt> {
+        tracing::info!("close wallet for user_id {:?} sweeping to {:?}", user_id, destination);
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
+        // the swept pockets and the destination's are all locked up front, in
+        // the order a transfer between the two wallets locks them; only a
+        // pocket opened meanwhile is locked by the read below
+        let mut users = vec![user_id];
+        if let SweepDestination::Wallet(to_id) = destination {
+            users.push(to_id);
+        }
+        Self::lock_pockets(&tx, &users).await?;
+        let rows = tx
+            .query(
+                "SELECT id, norek, user_id, currency, balance, held, status, created_date, updated_date
+             FROM WALLET_DIGITAL.DATA_WALLET WHERE user_id = $1 ORDER BY id FOR UPDATE",
+                &[&user_id],
+            )
+            .await?;
+        let mut wallet = Self::wallet_from_rows(&rows).ok_or(WalletError::WalletNotFound(user_id))?;
+        // re-checked under the lock, the caller's checks ran before it
+        let (transition, receipt) = wallet.close(destination, reason, note, actor)?;
+
+        for amount in &receipt.swept {
+            self.sweep_pocket(&tx, &receipt, *amount).await?;
+        }
+        tx.execute(
+            "UPDATE WALLET_DIGITAL.DATA_WALLET SET status = $1, updated_date = $2 WHERE user_id = $3",
+            &[&transition.to, &transition.at, &user_id],
+        )
+        .await?;
+        Self::insert_status_history(&tx, &transition).await?;
+
+        let destination_user_id = match receipt.destination {
+            SweepDestination::Wallet(to_id) => Some(to_id),
+            SweepDestination::Suspense => None,
+        };
+        tx.execute(
+            "INSERT INTO WALLET_DIGITAL.WALLET_CLOSURE (reference, user_id, destination_user_id, swept, reason, note, actor, created_date)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+            &[
+                &receipt.reference,
+                &receipt.user_id,
+                &destination_user_id,
+                &serde_json::to_value(&receipt.swept)?,
+                &receipt.reason,
+                &receipt.note,
+                &receipt.actor,
+                &receipt.closed_at,
+            ],
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(receipt)
+    }
+
+    async fn get_closure(&self, user_id: i32) -> Result<Option<ClosureReceipt>> {
+        tracing::info!("get closure for user_id {:?}", user_id);
         let client = self.pool.get().await?;
-        let status = WalletStatus::Inactive;
-        client
+        let row = client
+            .query_opt(
+                "SELECT reference, user_id, destination_user_id, swept, reason, note, actor, created_date
+             FROM WALLET_DIGITAL.WALLET_CLOSURE WHERE user_id = $1",
+                &[&user_id],
+            )
+            .await?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let destination_user_id: Option<i32> = row.get("destination_user_id");
+        Ok(Some(ClosureReceipt {
+            reference: row.get("reference"),
+            user_id: row.get("user_id"),
+            destination: destination_user_id
+                .map(SweepDestination::Wallet)
+                .unwrap_or(SweepDestination::Suspense),
+            swept: serde_json::from_value(row.get("swept"))?,
+            reason: row.get("reason"),
+            note: row.get("note"),
+            actor: row.get("actor"),
+            closed_at: row.get("created_date"),
+        }))
+    }
+
+    async fn set_status(&self, transition: &WalletStatusTransition) -> Result<()> {
+        tracing::info!(
+            "set wallet status of user_id {:?} from {:?} to {:?}",
+            transition.user_id,
+            transition.from,
+            transition.to
+        );
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
+        let updated = tx
             .execute(
-                "UPDATE WALLET_DIGITAL.DATA_WALLET SET status = $1 WHERE user_id = $2",
-                &[&status, &id],
+                "UPDATE WALLET_DIGITAL.DATA_WALLET SET status = $1, updated_date = $2
+             WHERE user_id = $3 AND status = $4",
+                &[&transition.to, &transition.at, &transition.user_id, &transition.from],
             )
             .await?;
+        if updated == 0 {
+            return Err(WalletError::StatusConflict(transition.user_id).into());
+        }
+        Self::insert_status_history(&tx, transition).await?;
+        tx.commit().await?;
         Ok(())
     }
+
+    async fn get_status_history(&self, user_id: i32) -> Result<Vec<WalletStatusTransition>> {
+        tracing::info!("get status history for user_id {:?}", user_id);
+        let client = self.pool.get().await?;
+        let rows = client
+            .query(
+                "SELECT user_id, from_status, to_status, reason, note, actor, created_date
+             FROM WALLET_DIGITAL.WALLET_STATUS_HISTORY WHERE user_id = $1 ORDER BY created_date, id",
+                &[&user_id],
+            )
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|row| WalletStatusTransition {
+                user_id: row.get("user_id"),
+                from: row.get("from_status"),
+                to: row.get("to_status"),
+                reason: row.get("reason"),
+                note: row.get("note"),
+                actor: row.get("actor"),
+                at: row.get("created_date"),
+            })
+            .collect())
+    }
+
+    async fn assign_missing_noreks(&self) -> Result<usize> {
+        let client = self.pool.get().await?;
+        let rows = client
+            .query(
+                "SELECT DISTINCT user_id FROM WALLET_DIGITAL.DATA_WALLET WHERE norek = '' ORDER BY user_id",
+                &[],
+            )
+            .await?;
+        let mut assigned = 0;
+        for row in rows {
+            let user_id: i32 = row.get("user_id");
+            let norek = self.next_norek(&client).await?;
+            // only fills blanks, a concurrent run leaves a numbered wallet alone
+            let updated = client
+                .execute(
+                    "UPDATE WALLET_DIGITAL.DATA_WALLET SET norek = $1 WHERE user_id = $2 AND norek = ''",
+                    &[&norek, &user_id],
+                )
+                .await?;
+            if updated > 0 {
+                tracing::info!("assigned norek {} to wallet of user_id {}", norek, user_id);
+                assigned += 1;
+            }
+        }
+        Ok(assigned)
+    }
 }
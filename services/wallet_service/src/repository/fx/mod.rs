@@ -0,0 +1 @@
+pub mod rate_provider;
\ No newline at end of file
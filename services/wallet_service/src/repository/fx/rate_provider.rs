@@ -0,0 +1,79 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use domain::money::exchange::ExchangeRate;
+use domain::money::money::Currency;
+use domain::wallet::error::WalletError;
+use std::collections::HashMap;
+use std::path::Path;
+
+#[async_trait]
+pub trait RateProvider: Send + Sync {
+    /// rate to convert `base` into `quote`
+    async fn get_rate(&self, base: Currency, quote: Currency) -> Result<ExchangeRate>;
+}
+
+/// Fixed rate table, either built in code or loaded from a JSON file such as
+/// `[{"base": "USD", "quote": "IDR", "rate": "15650.00", "spread_bps": 50}]`.
+#[derive(Debug, Clone, Default)]
+pub struct StaticRateProvider {
+    rates: HashMap<(Currency, Currency), ExchangeRate>,
+}
+
+impl StaticRateProvider {
+    pub fn new(rates: Vec<ExchangeRate>) -> Self {
+        Self {
+            rates: rates.into_iter().map(|r| ((r.base, r.quote), r)).collect(),
+        }
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read rates file {}: {}", path.display(), e))?;
+        let rates: Vec<ExchangeRate> = serde_json::from_str(&content)?;
+        tracing::info!("loaded {} exchange rates from {}", rates.len(), path.display());
+        Ok(Self::new(rates))
+    }
+
+    /// loads `FX_RATES_FILE` when set, otherwise starts with an empty table
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("FX_RATES_FILE") {
+            Ok(path) => Self::from_file(path),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+}
+
+#[async_trait]
+impl RateProvider for StaticRateProvider {
+    async fn get_rate(&self, base: Currency, quote: Currency) -> Result<ExchangeRate> {
+        self.rates
+            .get(&(base, quote))
+            .copied()
+            .ok_or_else(|| WalletError::UnsupportedCurrency(format!("no exchange rate for {}/{}", base, quote)).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::money::exchange::Rate;
+    use domain::money::money::Money;
+
+    #[tokio::test]
+    async fn converts_with_the_configured_pair_only() {
+        let rates: Vec<ExchangeRate> = serde_json::from_str(
+            r#"[{"base": "USD", "quote": "IDR", "rate": "15650.00", "spread_bps": 50}]"#,
+        )
+        .unwrap();
+        let provider = StaticRateProvider::new(rates);
+
+        let rate = provider.get_rate(Currency::USD, Currency::IDR).await.unwrap();
+        assert_eq!(rate.rate, Rate::parse("15650").unwrap());
+        let conversion = rate.convert(Money::new(1_000, Currency::USD)).unwrap();
+        assert_eq!(conversion.credit_amount, Money::new(15_571_750, Currency::IDR));
+
+        let err = provider.get_rate(Currency::IDR, Currency::USD).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(WalletError::UnsupportedCurrency(_))));
+    }
+}
@@ -1 +1,2 @@
-pub mod user_gateway;
\ No newline at end of file
+pub mod user_gateway;
+pub mod transfer_gateway;
\ No newline at end of file
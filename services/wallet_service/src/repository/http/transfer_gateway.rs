@@ -0,0 +1,26 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use domain::base::base::BaseResponse;
+use domain::transfer::transfer::Transfer;
+use lib::http_client::client::get_json;
+use mockall::automock;
+
+/// Asks transfer-service about sagas still moving money for a user.
+#[derive(Debug, Clone)]
+pub struct RestTransferRepository;
+
+#[async_trait]
+pub trait TransferProvider: Send + Sync {
+    /// transfers sent or received by the user that are still Initiated or Reserved
+    async fn pending_transfers(&self, user_id: i32) -> Result<Vec<Transfer>>;
+}
+
+#[automock]
+#[async_trait]
+impl TransferProvider for RestTransferRepository {
+    async fn pending_transfers(&self, user_id: i32) -> Result<Vec<Transfer>> {
+        let response: BaseResponse<Vec<Transfer>> =
+            get_json("transfer", &format!("/transfer/user/{}/pending", user_id)).await?;
+        Ok(response.data.unwrap_or_default())
+    }
+}
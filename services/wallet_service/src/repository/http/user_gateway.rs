@@ -1,22 +1,57 @@
 use anyhow::Result;
 use async_trait::async_trait;
-use domain::user::user::User;
+use domain::base::base::BaseResponse;
+use domain::user::user::{User, UserStatus};
 use lib::http_client::client::get_json;
+use lib::http_client::error::HttpClientError;
 use mockall::automock;
+use moka::future::Cache;
+use std::time::Duration;
 
+/// Looks users up on user-service, keeping found users for `ttl` so hot
+/// wallets do not cost a call per request. A status change therefore takes
+/// up to `ttl` to be seen; unknown users are not cached, nor pending ones so
+/// their wallet can be opened as soon as they verify their email.
 #[derive(Debug, Clone)]
-pub struct RestRepository;
+pub struct RestRepository {
+    cache: Cache<i32, User>,
+}
+
+impl RestRepository {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            cache: Cache::builder()
+                .max_capacity(10_000)
+                .time_to_live(ttl)
+                .build(),
+        }
+    }
+}
 
 #[async_trait]
 pub trait UserProvider: Send + Sync {
-    async fn find_user_by_id(user_id: i32) -> Result<User>;
+    /// `None` when user-service does not know the user
+    async fn find_user_by_id(&self, user_id: i32) -> Result<Option<User>>;
 }
 
 #[automock]
 #[async_trait]
 impl UserProvider for RestRepository {
-    async fn find_user_by_id(user_id: i32) -> Result<User> {
-        let user: User = get_json("user", &format!("/users/{}", user_id)).await?;
-        Ok(user)
+    async fn find_user_by_id(&self, user_id: i32) -> Result<Option<User>> {
+        if let Some(user) = self.cache.get(&user_id).await {
+            return Ok(Some(user));
+        }
+        match get_json::<BaseResponse<User>>("user", &format!("/users/{}", user_id)).await {
+            Ok(response) => {
+                if let Some(ref user) = response.data
+                    && user.status != UserStatus::Pending
+                {
+                    self.cache.insert(user_id, user.clone()).await;
+                }
+                Ok(response.data)
+            }
+            Err(e) if e.downcast_ref::<HttpClientError>().is_some_and(HttpClientError::is_not_found) => Ok(None),
+            Err(e) => Err(e),
+        }
     }
 }
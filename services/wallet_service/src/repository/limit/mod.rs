@@ -0,0 +1 @@
+pub mod tier_limits;
@@ -0,0 +1,55 @@
+use anyhow::{anyhow, Result};
+use domain::money::money::Currency;
+use domain::user::user::UserTier;
+use domain::wallet::limit::{SpendingLimits, TierLimits};
+use std::collections::HashMap;
+use std::path::Path;
+
+pub trait TierLimitProvider: Send + Sync + std::fmt::Debug {
+    /// default limits of `currency` pockets for users of `tier`
+    fn tier_limits(&self, tier: UserTier, currency: Currency) -> SpendingLimits;
+}
+
+/// Fixed limits per tier and currency, either built in code or loaded from a
+/// JSON file of [`TierLimits`]. Tiers missing from the table are unlimited.
+#[derive(Debug, Clone, Default)]
+pub struct StaticTierLimits {
+    limits: HashMap<(UserTier, Currency), SpendingLimits>,
+}
+
+impl StaticTierLimits {
+    pub fn new(limits: Vec<TierLimits>) -> Self {
+        Self {
+            limits: limits
+                .into_iter()
+                .map(|t| ((t.tier, t.limits.currency), t.limits))
+                .collect(),
+        }
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read limits file {}: {}", path.display(), e))?;
+        let limits: Vec<TierLimits> = serde_json::from_str(&content)?;
+        tracing::info!("loaded {} tier limits from {}", limits.len(), path.display());
+        Ok(Self::new(limits))
+    }
+
+    /// loads `SPENDING_LIMITS_FILE` when set, otherwise starts with an empty table
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("SPENDING_LIMITS_FILE") {
+            Ok(path) => Self::from_file(path),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+}
+
+impl TierLimitProvider for StaticTierLimits {
+    fn tier_limits(&self, tier: UserTier, currency: Currency) -> SpendingLimits {
+        self.limits
+            .get(&(tier, currency))
+            .cloned()
+            .unwrap_or_else(|| SpendingLimits::unlimited(currency))
+    }
+}
@@ -0,0 +1,76 @@
+use crate::domain::error::ServiceResult;
+use crate::repository::db::hold::{HoldProvider, HoldRepository};
+use crate::repository::db::postgres::{WalletProvider, WalletRepository};
+use chrono::Utc;
+use domain::money::money::Money;
+use domain::wallet::error::WalletError;
+use domain::wallet::hold::Hold;
+use tokio::task::JoinHandle;
+
+#[derive(Clone)]
+pub struct HoldUsecase {
+    repo: HoldRepository,
+    wallets: WalletRepository,
+}
+
+impl HoldUsecase {
+    pub fn new(repo: HoldRepository, wallets: WalletRepository) -> Self {
+        Self { repo, wallets }
+    }
+
+    /// Reserves `amount` on the wallet for `expires_in_seconds`, at most
+    /// [`Hold::MAX_EXPIRY_SECONDS`].
+    pub async fn place_hold(&self, user_id: i32, amount: Money, expires_in_seconds: i64) -> ServiceResult<Hold> {
+        tracing::info!("placing hold of {} for user_id {}", amount, user_id);
+        if !amount.is_positive() {
+            return Err(WalletError::InvalidAmount(amount).into());
+        }
+        let mut wallet = self
+            .wallets
+            .get_wallet_by_userid(user_id)
+            .await?
+            .ok_or(WalletError::WalletNotFound(user_id))?;
+        // pre-check only, the repository re-checks under the row lock
+        wallet.place_hold(amount)?;
+
+        let expires_at = Hold::expiry(Utc::now(), expires_in_seconds)?;
+        let hold = Hold::new(user_id, amount, expires_at);
+        Ok(self.repo.place_hold(&hold).await?)
+    }
+
+    pub async fn get_hold(&self, reference: &str) -> ServiceResult<Hold> {
+        self.repo
+            .get_hold(reference)
+            .await?
+            .ok_or_else(|| WalletError::HoldNotFound(reference.to_string()).into())
+    }
+
+    /// Captures `amount`, or the whole held amount when none is given.
+    pub async fn capture_hold(&self, reference: &str, amount: Option<Money>) -> ServiceResult<Hold> {
+        tracing::info!("capturing hold {}", reference);
+        let amount = match amount {
+            Some(amount) => amount,
+            None => self.get_hold(reference).await?.amount,
+        };
+        Ok(self.repo.capture_hold(reference, amount).await?)
+    }
+
+    pub async fn release_hold(&self, reference: &str) -> ServiceResult<Hold> {
+        tracing::info!("releasing hold {}", reference);
+        Ok(self.repo.release_hold(reference).await?)
+    }
+
+    /// Releases expired holds every `interval`, for the lifetime of the process.
+    pub fn spawn_expiry_task(&self, interval: std::time::Duration) -> JoinHandle<()> {
+        let usecase = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = usecase.repo.expire_holds(Utc::now()).await {
+                    tracing::error!("failed to expire holds: {}", e);
+                }
+            }
+        })
+    }
+}
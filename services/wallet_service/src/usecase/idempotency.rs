@@ -0,0 +1,218 @@
+use crate::repository::db::idempotency::{IdempotencyProvider, IdempotencyRepository};
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+#[derive(Debug)]
+pub enum IdempotentOutcome {
+    /// first time the key is seen, the request should be executed
+    Proceed,
+    /// the key was already used for the same request, answer with the stored response
+    Replay(u16, serde_json::Value),
+    /// the key was used for another request, or the first request is still running
+    Conflict(&'static str),
+}
+
+/// Keys are remembered per caller for `ttl`. A request that claimed a key and
+/// never recorded an outcome keeps it until then: whether its transfer
+/// committed is unknown, so a retry is answered as still in progress.
+#[derive(Clone)]
+pub struct IdempotencyUsecase<R = IdempotencyRepository> {
+    repo: R,
+    ttl: Duration,
+}
+
+impl IdempotencyUsecase {
+    /// sha256 of the request as serialized JSON
+    pub fn request_hash<T: Serialize>(request: &T) -> Result<String> {
+        let body = serde_json::to_vec(request)?;
+        Ok(format!("{:x}", Sha256::digest(body)))
+    }
+
+    /// Transaction id of the transfer made for this key and request. It doubles
+    /// as the ledger reference, so running the same request twice, e.g. after
+    /// a released or expired key, books it only once.
+    pub fn transaction_id(caller: &str, key: &str, request_hash: &str) -> String {
+        let name = format!("{}\n{}\n{}", caller, key, request_hash);
+        Uuid::new_v5(&Uuid::NAMESPACE_OID, name.as_bytes()).to_string()
+    }
+}
+
+impl<R: IdempotencyProvider> IdempotencyUsecase<R> {
+    pub fn new(repo: R, ttl: std::time::Duration) -> Self {
+        Self {
+            repo,
+            ttl: Duration::from_std(ttl).unwrap_or(Duration::MAX),
+        }
+    }
+
+    /// Claims `caller`'s `key` for the request; keys of different callers never collide.
+    pub async fn begin(&self, caller: &str, key: &str, request_hash: &str) -> Result<IdempotentOutcome> {
+        let now = Utc::now();
+        let claimed = self
+            .repo
+            .claim_key(caller, key, request_hash, now, now - self.ttl)
+            .await?;
+        match claimed {
+            None => Ok(IdempotentOutcome::Proceed),
+            Some(record) if record.request_hash != request_hash => {
+                tracing::warn!(
+                    "idempotency key {:?} first used at {} reused with a different request",
+                    record.key,
+                    record.created_date
+                );
+                Ok(IdempotentOutcome::Conflict(
+                    "Idempotency-Key was already used for a different request",
+                ))
+            }
+            Some(record) => match (record.status_code, record.response) {
+                (Some(status_code), Some(response)) => {
+                    tracing::info!("replaying stored response for idempotency key {:?}", key);
+                    Ok(IdempotentOutcome::Replay(status_code, response))
+                }
+                _ => {
+                    tracing::warn!(
+                        "idempotency key {:?} still in progress since {}",
+                        record.key,
+                        record.claimed_at
+                    );
+                    Ok(IdempotentOutcome::Conflict(
+                        "A request with this Idempotency-Key is still in progress",
+                    ))
+                }
+            },
+        }
+    }
+
+    /// Stores the final response for replays. Server errors release the key
+    /// instead, so a retry with the same key gets another attempt.
+    pub async fn complete<T: Serialize>(&self, caller: &str, key: &str, status_code: u16, response: &T) -> Result<()> {
+        if status_code >= 500 {
+            return self.repo.release_key(caller, key).await;
+        }
+        let response = serde_json::to_value(response)?;
+        self.repo.save_response(caller, key, status_code, &response).await
+    }
+}
+
+impl<R: IdempotencyProvider + Clone + Send + Sync + 'static> IdempotencyUsecase<R> {
+
+    /// Deletes expired keys every `interval`, for the lifetime of the process.
+    pub fn spawn_purge_task(&self, interval: std::time::Duration) -> JoinHandle<()> {
+        let usecase = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match usecase.repo.purge_expired(Utc::now() - usecase.ttl).await {
+                    Ok(0) => {}
+                    Ok(purged) => tracing::info!("purged {} expired idempotency keys", purged),
+                    Err(e) => tracing::error!("failed to purge idempotency keys: {}", e),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::db::idempotency::{IdempotencyRecord, MockIdempotencyRepository};
+    use mockall::predicate::eq;
+
+    const TTL: std::time::Duration = std::time::Duration::from_secs(3600);
+
+    fn record(request_hash: &str, status_code: Option<u16>) -> IdempotencyRecord {
+        let now = Utc::now();
+        IdempotencyRecord {
+            key: "key-1".to_string(),
+            request_hash: request_hash.to_string(),
+            status_code,
+            response: status_code.map(|_| serde_json::json!({"message": "Success"})),
+            created_date: now,
+            claimed_at: now,
+        }
+    }
+
+    fn usecase(claimed: Option<IdempotencyRecord>) -> IdempotencyUsecase<MockIdempotencyRepository> {
+        let mut repo = MockIdempotencyRepository::new();
+        repo.expect_claim_key()
+            .withf(|caller, key, _, now, expired_before| {
+                caller == "user-1" && key == "key-1" && *now - *expired_before == Duration::hours(1)
+            })
+            .times(1)
+            .return_once(move |_, _, _, _, _| Box::pin(async move { Ok(claimed) }));
+        IdempotencyUsecase::new(repo, TTL)
+    }
+
+    #[tokio::test]
+    async fn new_key_proceeds() {
+        let outcome = usecase(None).begin("user-1", "key-1", "hash-a").await.unwrap();
+        assert!(matches!(outcome, IdempotentOutcome::Proceed));
+    }
+
+    #[tokio::test]
+    async fn completed_key_replays_the_stored_response() {
+        let outcome = usecase(Some(record("hash-a", Some(200))))
+            .begin("user-1", "key-1", "hash-a")
+            .await
+            .unwrap();
+        assert!(matches!(outcome, IdempotentOutcome::Replay(200, _)));
+    }
+
+    #[tokio::test]
+    async fn key_reused_for_another_request_conflicts() {
+        let outcome = usecase(Some(record("hash-a", Some(200))))
+            .begin("user-1", "key-1", "hash-b")
+            .await
+            .unwrap();
+        assert!(matches!(outcome, IdempotentOutcome::Conflict(m) if m.contains("different request")));
+    }
+
+    #[tokio::test]
+    async fn unfinished_claim_is_never_taken_over() {
+        // however old, a claim without an outcome may belong to a committed transfer
+        let mut stale = record("hash-a", None);
+        stale.claimed_at = Utc::now() - Duration::minutes(30);
+        let outcome = usecase(Some(stale)).begin("user-1", "key-1", "hash-a").await.unwrap();
+        assert!(matches!(outcome, IdempotentOutcome::Conflict(m) if m.contains("in progress")));
+    }
+
+    #[tokio::test]
+    async fn server_errors_release_the_key() {
+        let mut repo = MockIdempotencyRepository::new();
+        repo.expect_release_key()
+            .with(eq("user-1"), eq("key-1"))
+            .times(1)
+            .returning(|_, _| Box::pin(async { Ok(()) }));
+        repo.expect_save_response().never();
+        let usecase = IdempotencyUsecase::new(repo, TTL);
+        usecase.complete("user-1", "key-1", 500, &"failed").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn other_outcomes_are_stored() {
+        let mut repo = MockIdempotencyRepository::new();
+        repo.expect_save_response()
+            .withf(|caller, key, status_code, _| caller == "user-1" && key == "key-1" && *status_code == 422)
+            .times(1)
+            .returning(|_, _, _, _| Box::pin(async { Ok(()) }));
+        repo.expect_release_key().never();
+        let usecase = IdempotencyUsecase::new(repo, TTL);
+        usecase.complete("user-1", "key-1", 422, &"rejected").await.unwrap();
+    }
+
+    #[test]
+    fn transaction_id_is_derived_from_caller_key_and_request() {
+        let id = IdempotencyUsecase::transaction_id("user-1", "key-1", "hash-a");
+        assert_eq!(id, IdempotencyUsecase::transaction_id("user-1", "key-1", "hash-a"));
+        assert_ne!(id, IdempotencyUsecase::transaction_id("user-2", "key-1", "hash-a"));
+        assert_ne!(id, IdempotencyUsecase::transaction_id("user-1", "key-2", "hash-a"));
+        assert_ne!(id, IdempotencyUsecase::transaction_id("user-1", "key-1", "hash-b"));
+    }
+}
@@ -1,54 +1,198 @@
-use crate::domain::dto::Request;
+use crate::domain::dto::TransferResponse;
+use crate::domain::error::{ServiceResult, WalletServiceError};
+use crate::repository::db::ledger::{LedgerProvider, LedgerRepository};
+use crate::repository::db::limit::{LimitProvider, LimitRepository};
 use crate::repository::db::postgres::{WalletProvider, WalletRepository};
-use anyhow::Result;
+use crate::repository::fx::rate_provider::RateProvider;
+use crate::repository::http::transfer_gateway::TransferProvider;
+use crate::repository::http::user_gateway::UserProvider;
 use domain::base::base::AuditMetadata;
+use domain::ledger::ledger::{JournalEntry, LedgerAccount};
+use domain::money::money::{Currency, Money};
+use domain::transfer::transfer::Transfer;
+use domain::user::user::UserTier;
+use domain::wallet::error::WalletError;
+use domain::wallet::limit::SpendingLimits;
 use domain::wallet::wallet::Wallet as WalletDomain;
+use domain::wallet::closure::{ClosureReceipt, SweepDestination};
+use domain::wallet::wallet::{StatusReason, WalletStatus, WalletStatusTransition};
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct Usecase {
     repo: WalletRepository,
+    ledger: LedgerRepository,
+    rates: Arc<dyn RateProvider>,
+    limits: LimitRepository,
+    users: Arc<dyn UserProvider>,
+    transfers: Arc<dyn TransferProvider>,
 }
 
 pub trait Wallet {
-    async fn get_or_create_wallet(&self, user_id: i32) -> Result<WalletDomain>;
-    async fn transfer_balance(&self, from_id: i32, to_id: i32, amount: f64)
-    -> Result<WalletDomain>;
-    async fn delete_wallet(&self, id: i32) -> Result<()>;
-    async fn update_balance(&self, user_id: i32, amount: f64) -> Result<WalletDomain>;
+    async fn get_or_create_wallet(&self, user_id: i32) -> ServiceResult<WalletDomain>;
+    async fn transfer_balance(
+        &self,
+        from_id: i32,
+        to_id: i32,
+        amount: Money,
+        target_currency: Option<Currency>,
+        transaction_id: Option<String>,
+    ) -> ServiceResult<TransferResponse>;
+    async fn open_pocket(&self, user_id: i32, currency: Currency) -> ServiceResult<WalletDomain>;
+    async fn close_wallet(
+        &self,
+        user_id: i32,
+        sweep_to: Option<i32>,
+        reason: StatusReason,
+        note: Option<String>,
+        actor: &str,
+    ) -> ServiceResult<ClosureReceipt>;
+    async fn get_wallet_history(&self, user_id: i32) -> ServiceResult<Vec<JournalEntry>>;
+    async fn debit_wallet(&self, user_id: i32, amount: Money, reference: &str, compensation: bool) -> ServiceResult<WalletDomain>;
+    async fn credit_wallet(&self, user_id: i32, amount: Money, reference: &str, compensation: bool) -> ServiceResult<WalletDomain>;
+    async fn set_limits(&self, user_id: i32, tier: Option<UserTier>, overrides: Option<SpendingLimits>) -> ServiceResult<()>;
+    async fn freeze_wallet(&self, user_id: i32, reason: StatusReason, note: Option<String>, actor: &str) -> ServiceResult<WalletDomain>;
+    async fn unfreeze_wallet(&self, user_id: i32, reason: StatusReason, note: Option<String>, actor: &str) -> ServiceResult<WalletDomain>;
+    async fn get_status_history(&self, user_id: i32) -> ServiceResult<Vec<WalletStatusTransition>>;
+    async fn get_wallet_by_norek(&self, norek: &str) -> ServiceResult<WalletDomain>;
+    async fn resolve_recipient(&self, to_id: Option<i32>, to_norek: Option<&str>) -> ServiceResult<i32>;
 }
 
 impl Usecase {
-    pub fn new(repo: WalletRepository) -> Self {
-        Self { repo }
+    pub fn new(
+        repo: WalletRepository,
+        ledger: LedgerRepository,
+        rates: Arc<dyn RateProvider>,
+        limits: LimitRepository,
+        users: Arc<dyn UserProvider>,
+        transfers: Arc<dyn TransferProvider>,
+    ) -> Self {
+        Self {
+            repo,
+            ledger,
+            rates,
+            limits,
+            users,
+            transfers,
+        }
+    }
+
+    /// The user must be known to user-service and active to get a wallet or move money.
+    async fn ensure_active_user(&self, user_id: i32) -> ServiceResult<()> {
+        match self.users.find_user_by_id(user_id).await? {
+            None => Err(WalletError::UserNotFound(user_id).into()),
+            Some(user) if !user.is_active() => Err(WalletError::UserInactive(user_id).into()),
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// User check of a saga leg. A retry of a leg that already went through
+    /// passes even when the user was deactivated since: refusing it would make
+    /// the caller fail a debit that happened or refund a credited transfer.
+    async fn ensure_active_user_for_leg(&self, user_id: i32, currency: Currency, reference: &str) -> ServiceResult<()> {
+        match self.ensure_active_user(user_id).await {
+            Err(e) if self.repo.is_applied(user_id, currency, reference).await? => {
+                tracing::info!("reference {:?} already applied, skipping user check: {}", reference, e);
+                Ok(())
+            }
+            result => result,
+        }
     }
 
     fn construct_wallet(data: WalletDomain) -> WalletDomain {
-        WalletDomain::new(
+        let mut wallet = WalletDomain::new(
             data.id,
             data.norek,
             data.user_id,
-            data.balance,
+            Money::zero(Currency::default()),
             AuditMetadata {
                 created_date: data.audit.created_date,
                 updated_date: data.audit.updated_date,
             },
-        )
+        );
+        wallet.balances = data.balances;
+        wallet.held = data.held;
+        wallet.status = data.status;
+        wallet
+    }
+
+    /// a wallet cannot close while a transfer saga may still move its money
+    async fn ensure_no_pending_transfers(transfers: &dyn TransferProvider, user_id: i32) -> ServiceResult<()> {
+        let pending = transfers.pending_transfers(user_id).await?;
+        if !pending.is_empty() {
+            return Err(WalletError::PendingTransfers(user_id, pending.len()).into());
+        }
+        Ok(())
+    }
+
+    /// Applies a status change worked out by the domain on the current wallet
+    /// and records it; the repository refuses it if the status moved meanwhile.
+    async fn change_status(
+        &self,
+        user_id: i32,
+        change: impl FnOnce(&mut WalletDomain) -> Result<WalletStatusTransition, WalletError>,
+    ) -> ServiceResult<WalletDomain> {
+        let mut wallet = self
+            .repo
+            .get_wallet_by_userid(user_id)
+            .await?
+            .ok_or(WalletError::WalletNotFound(user_id))?;
+        let transition = change(&mut wallet)?;
+        self.repo.set_status(&transition).await?;
+        tracing::info!(
+            "wallet of user_id {} moved from {:?} to {:?} by {} ({:?})",
+            user_id,
+            transition.from,
+            transition.to,
+            transition.actor,
+            transition.reason
+        );
+        Ok(Self::construct_wallet(wallet))
+    }
+
+    /// balance and limit checks happen inside the repository transaction, after
+    /// the replay check, so a retried leg is never rejected for funds it already moved
+    async fn apply_movement(
+        &self,
+        user_id: i32,
+        amount: Money,
+        signed_amount: Money,
+        reference: &str,
+        description: &str,
+        compensation: bool,
+    ) -> ServiceResult<WalletDomain> {
+        if !amount.is_positive() {
+            return Err(WalletError::InvalidAmount(amount).into());
+        }
+        if self.repo.get_wallet_by_userid(user_id).await?.is_none() {
+            return Err(WalletError::WalletNotFound(user_id).into());
+        }
+        self.repo
+            .move_balance(user_id, signed_amount, reference, description, compensation)
+            .await?;
+        let wallet = self
+            .repo
+            .get_wallet_by_userid(user_id)
+            .await?
+            .ok_or(WalletError::WalletNotFound(user_id))?;
+        Ok(Self::construct_wallet(wallet))
     }
 }
 
 impl Wallet for Usecase {
-    async fn get_or_create_wallet(&self, user_id: i32) -> Result<WalletDomain> {
+    async fn get_or_create_wallet(&self, user_id: i32) -> ServiceResult<WalletDomain> {
         tracing::info!("getting wallet {}", user_id);
         let opt_wallet = self.repo.get_wallet_by_userid(user_id).await?;
         match opt_wallet {
             None => {
-                let create_wallet = self.repo.create_wallet(user_id, 0f64);
+                self.ensure_active_user(user_id).await?;
+                let create_wallet = self.repo.create_wallet(user_id, Money::zero(Currency::default()));
                 match create_wallet.await {
                     Ok(d) => {
                         tracing::info!("created wallet for user_id {}", user_id);
                         Ok(Self::construct_wallet(d))
                     }
-                    Err(e) => Err(anyhow::anyhow!("Failed to get or create wallet: {}", e)),
+                    Err(e) => Err(e.context("Failed to get or create wallet").into()),
                 }
             }
             Some(data) => {
@@ -58,70 +202,301 @@ impl Wallet for Usecase {
         }
     }
 
+    /// Moves `amount` from the sender's pocket in `amount`'s currency to the receiver.
+    ///
+    /// Without `target_currency` the receiver is credited in the same currency.
+    /// With a different `target_currency` the amount is converted using the
+    /// rate provider and the applied rate is recorded on the returned transfer.
+    ///
+    /// A given `transaction_id` is used instead of a random one; a transfer
+    /// already booked under it fails with `AlreadyApplied`.
     async fn transfer_balance(
         &self,
         from_id: i32,
         to_id: i32,
-        amount: f64,
-    ) -> Result<WalletDomain> {
+        amount: Money,
+        target_currency: Option<Currency>,
+        transaction_id: Option<String>,
+    ) -> ServiceResult<TransferResponse> {
         tracing::info!("transfer balance wallet for user_id {}", from_id);
-        if amount <= 0f64 {
-            return Err(anyhow::anyhow!("Invalid transfer amount"));
+        if !amount.is_positive() {
+            return Err(WalletError::InvalidAmount(amount).into());
         }
+        self.ensure_active_user(from_id).await?;
+        self.ensure_active_user(to_id).await?;
+
+        let conversion = match target_currency {
+            Some(target) if target != amount.currency() => {
+                let rate = self.rates.get_rate(amount.currency(), target).await?;
+                let conversion = rate.convert(amount)?;
+                tracing::info!(
+                    "converting {} to {} at applied rate {}",
+                    conversion.debit_amount,
+                    conversion.credit_amount,
+                    conversion.applied_rate
+                );
+                Some(conversion)
+            }
+            _ => None,
+        };
+        let credit_amount = conversion.map(|c| c.credit_amount).unwrap_or(amount);
 
         let sender_wallet = self.repo.get_wallet_by_userid(from_id).await?;
         match sender_wallet {
-            None => Err(anyhow::anyhow!("Sender wallet not found")),
+            None => Err(WalletError::WalletNotFound(from_id).into()),
             Some(mut sender_wallet) => match sender_wallet.debit(amount) {
                 Ok(_) => {
                     let receiver_wallet = self.repo.get_wallet_by_userid(to_id).await?;
                     match receiver_wallet {
-                        None => Err(anyhow::anyhow!("Receiver wallet not found")),
-                        Some(_receiver_wallet) => {
+                        None => Err(WalletError::WalletNotFound(to_id).into()),
+                        Some(mut receiver_wallet) => {
+                            // the receiver must already hold the credited currency,
+                            // cross-currency transfers need an explicit target currency
+                            receiver_wallet.credit(credit_amount)?;
                             tracing::info!(
                                 "sender and receiver wallet are exists, processing transfer balance....."
                             );
 
-                            let (sender_balance, receiver_balance) =
-                                self.repo.transfer_balance(from_id, to_id, amount).await?;
+                            let mut transfer = Transfer::new(
+                                &sender_wallet.norek,
+                                &receiver_wallet.norek,
+                                amount,
+                                AuditMetadata::new(),
+                            );
+                            if let Some(conversion) = conversion {
+                                transfer = transfer.with_conversion(conversion);
+                            }
+                            if let Some(transaction_id) = transaction_id {
+                                transfer = transfer.with_transaction_id(transaction_id);
+                            }
+
+                            let (sender_balance, receiver_balance) = self
+                                .repo
+                                .transfer_balance(
+                                    from_id,
+                                    to_id,
+                                    amount,
+                                    credit_amount,
+                                    &transfer.transaction_id,
+                                )
+                                .await?;
+                            // debit and credit commit in one database transaction
+                            transfer.mark_reserved("sender debited")?;
+                            transfer.mark_committed("receiver credited")?;
 
                             tracing::info!(
-                                "transfer done, current balance sender: {} receiver: {}",
+                                "transfer {} done, current balance sender: {} receiver: {}",
+                                transfer.transaction_id,
                                 sender_balance,
                                 receiver_balance
                             );
 
-                            Ok(sender_wallet)
+                            Ok(TransferResponse {
+                                wallet: sender_wallet,
+                                transfer,
+                            })
                         }
                     }
                 }
-                Err(e) => Err(anyhow::anyhow!(e)),
+                Err(e) => Err(e.into()),
             },
         }
     }
 
-    async fn delete_wallet(&self, id: i32) -> Result<()> {
-        tracing::info!("updating wallet for user_id {}", id);
-        let opt_wallet = self.repo.get_wallet_by_userid(id).await?;
-        match opt_wallet {
-            None => Err(anyhow::anyhow!("Wallet not found")),
-            Some(wallet) => {
-                self.repo.delete_wallet(id).await?;
-                Ok(())
+    async fn open_pocket(&self, user_id: i32, currency: Currency) -> ServiceResult<WalletDomain> {
+        tracing::info!("opening {} pocket for user_id {}", currency, user_id);
+        match self.repo.get_wallet_by_userid(user_id).await? {
+            None => Err(WalletError::WalletNotFound(user_id).into()),
+            Some(wallet) if wallet.balance(currency).is_some() => Ok(Self::construct_wallet(wallet)),
+            Some(_) => {
+                self.ensure_active_user(user_id).await?;
+                let wallet = self.repo.open_pocket(user_id, currency).await?;
+                Ok(Self::construct_wallet(wallet))
             }
         }
     }
 
-    async fn update_balance(&self, user_id: i32, amount: f64) -> Result<WalletDomain> {
-        tracing::info!("updating wallet for user_id {}", user_id);
-        let opt_wallet = self.repo.get_wallet_by_userid(user_id).await?;
-        match opt_wallet {
-            None => Err(anyhow::anyhow!("Wallet not found")),
-            Some(mut wallet) => {
-                let _ = self.repo.update_balance(user_id, amount).await;
-                wallet.debit(amount)?;
-                Ok(wallet)
+    /// Closes the wallet for good, sweeping what is left in it to the wallet
+    /// of `sweep_to`, or to the suspense account when nobody is nominated.
+    ///
+    /// Refused while funds are on hold or transfers from or to the wallet are
+    /// pending. Transfers are checked again once the wallet is PendingClosure
+    /// and no new ones can start; if one slipped in, the wallet stays
+    /// PendingClosure and calling again after it settled finishes the closure.
+    /// Closing a closed wallet returns its receipt.
+    async fn close_wallet(
+        &self,
+        user_id: i32,
+        sweep_to: Option<i32>,
+        reason: StatusReason,
+        note: Option<String>,
+        actor: &str,
+    ) -> ServiceResult<ClosureReceipt> {
+        tracing::info!("closing wallet for user_id {}", user_id);
+        let wallet = self
+            .repo
+            .get_wallet_by_userid(user_id)
+            .await?
+            .ok_or(WalletError::WalletNotFound(user_id))?;
+        if wallet.status == WalletStatus::Closed
+            && let Some(receipt) = self.repo.get_closure(user_id).await?
+        {
+            return Ok(receipt);
+        }
+
+        let destination = match sweep_to {
+            Some(to_id) if to_id == user_id => return Err(WalletError::InvalidSweepTarget(user_id).into()),
+            Some(to_id) => {
+                self.ensure_active_user(to_id).await?;
+                if self.repo.get_wallet_by_userid(to_id).await?.is_none() {
+                    return Err(WalletError::WalletNotFound(to_id).into());
+                }
+                SweepDestination::Wallet(to_id)
             }
+            None => SweepDestination::Suspense,
+        };
+
+        if wallet.status != WalletStatus::PendingClosure {
+            wallet.ensure_no_holds()?;
+            Self::ensure_no_pending_transfers(self.transfers.as_ref(), user_id).await?;
+            self.change_status(user_id, |wallet| wallet.begin_closure(reason, note.clone(), actor))
+                .await?;
+        }
+        Self::ensure_no_pending_transfers(self.transfers.as_ref(), user_id).await?;
+
+        let receipt = self
+            .repo
+            .close_wallet(user_id, destination, reason, note, actor)
+            .await?;
+        tracing::info!(
+            "wallet of user_id {} closed, swept {:?} to {:?}",
+            user_id,
+            receipt.swept,
+            receipt.destination
+        );
+        Ok(receipt)
+    }
+
+    /// Every journal entry that moved money in or out of the user's wallet, oldest first.
+    async fn get_wallet_history(&self, user_id: i32) -> ServiceResult<Vec<JournalEntry>> {
+        tracing::info!("getting ledger history for user_id {}", user_id);
+        if self.repo.get_wallet_by_userid(user_id).await?.is_none() {
+            return Err(WalletError::WalletNotFound(user_id).into());
+        }
+        Ok(self
+            .ledger
+            .get_entries_by_account(&LedgerAccount::Wallet(user_id))
+            .await?)
+    }
+
+    /// One leg of an orchestrated transfer: takes `amount` out of the wallet.
+    /// Retrying with the same `reference` does not debit twice.
+    ///
+    /// The debit counts against the spending limits unless it is a
+    /// `compensation`, i.e. a reversal taking back what the wallet received.
+    async fn debit_wallet(&self, user_id: i32, amount: Money, reference: &str, compensation: bool) -> ServiceResult<WalletDomain> {
+        tracing::info!("debit wallet for user_id {} reference {}", user_id, reference);
+        self.ensure_active_user_for_leg(user_id, amount.currency(), reference).await?;
+        let negated = Money::zero(amount.currency()).checked_sub(amount)?;
+        self.apply_movement(user_id, amount, negated, reference, "debit", compensation).await
+    }
+
+    /// One leg of an orchestrated transfer: puts `amount` into the wallet.
+    /// Retrying with the same `reference` does not credit twice.
+    ///
+    /// A `compensation` credit gives a sender their money back and is accepted
+    /// even when the user has been deactivated or the wallet frozen or
+    /// put up for closure since.
+    async fn credit_wallet(&self, user_id: i32, amount: Money, reference: &str, compensation: bool) -> ServiceResult<WalletDomain> {
+        tracing::info!("credit wallet for user_id {} reference {}", user_id, reference);
+        if !compensation {
+            self.ensure_active_user_for_leg(user_id, amount.currency(), reference).await?;
+        }
+        self.apply_movement(user_id, amount, amount, reference, "credit", compensation).await
+    }
+
+    /// Assigns the user's tier and/or overrides the limits of one of their pockets.
+    async fn set_limits(&self, user_id: i32, tier: Option<UserTier>, overrides: Option<SpendingLimits>) -> ServiceResult<()> {
+        tracing::info!("updating limits for user_id {}", user_id);
+        if self.repo.get_wallet_by_userid(user_id).await?.is_none() {
+            return Err(WalletError::WalletNotFound(user_id).into());
+        }
+        if let Some(tier) = tier {
+            self.limits.set_tier(user_id, tier).await?;
+        }
+        if let Some(overrides) = overrides {
+            self.limits.set_overrides(user_id, &overrides).await?;
         }
+        Ok(())
+    }
+
+    /// Blocks debits and holds on the wallet, credits keep landing.
+    async fn freeze_wallet(&self, user_id: i32, reason: StatusReason, note: Option<String>, actor: &str) -> ServiceResult<WalletDomain> {
+        tracing::info!("freezing wallet for user_id {}", user_id);
+        self.change_status(user_id, |wallet| wallet.freeze(reason, note, actor))
+            .await
+    }
+
+    async fn unfreeze_wallet(&self, user_id: i32, reason: StatusReason, note: Option<String>, actor: &str) -> ServiceResult<WalletDomain> {
+        tracing::info!("unfreezing wallet for user_id {}", user_id);
+        self.change_status(user_id, |wallet| wallet.unfreeze(reason, note, actor))
+            .await
+    }
+
+    /// Every status change of the wallet, oldest first.
+    async fn get_status_history(&self, user_id: i32) -> ServiceResult<Vec<WalletStatusTransition>> {
+        tracing::info!("getting status history for user_id {}", user_id);
+        if self.repo.get_wallet_by_userid(user_id).await?.is_none() {
+            return Err(WalletError::WalletNotFound(user_id).into());
+        }
+        Ok(self.repo.get_status_history(user_id).await?)
+    }
+
+    /// Wallet owning the account number, which may be typed with spaces or dashes.
+    async fn get_wallet_by_norek(&self, norek: &str) -> ServiceResult<WalletDomain> {
+        let norek = self.repo.norek_format().validate(norek)?;
+        tracing::info!("getting wallet by norek {}", norek);
+        let wallet = self
+            .repo
+            .get_wallet_by_norek(&norek)
+            .await?
+            .ok_or(WalletError::NorekNotFound(norek))?;
+        Ok(Self::construct_wallet(wallet))
+    }
+
+    /// User id of a transfer's receiver, given either directly or by account number.
+    async fn resolve_recipient(&self, to_id: Option<i32>, to_norek: Option<&str>) -> ServiceResult<i32> {
+        match (to_id, to_norek) {
+            (Some(to_id), None) => Ok(to_id),
+            (None, Some(norek)) => Ok(self.get_wallet_by_norek(norek).await?.user_id),
+            (Some(_), Some(_)) => Err(WalletServiceError::InvalidRequest(
+                "Give either to_id or to_norek, not both".to_string(),
+            )),
+            (None, None) => Err(WalletServiceError::InvalidRequest(
+                "Missing recipient, give to_id or to_norek".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::http::transfer_gateway::MockRestTransferRepository;
+    use mockall::predicate::eq;
+
+    #[tokio::test]
+    async fn closure_waits_for_pending_transfers() {
+        let mut transfers = MockRestTransferRepository::new();
+        transfers
+            .expect_pending_transfers()
+            .with(eq(1))
+            .times(1)
+            .returning(|_| Ok(vec![Transfer::new("1", "2", Money::new(10_000, Currency::IDR), AuditMetadata::new())]));
+        transfers.expect_pending_transfers().with(eq(2)).returning(|_| Ok(Vec::new()));
+
+        let refused = Usecase::ensure_no_pending_transfers(&transfers, 1).await;
+        assert!(matches!(refused, Err(WalletServiceError::Conflict(_))));
+        Usecase::ensure_no_pending_transfers(&transfers, 2).await.unwrap();
     }
 }